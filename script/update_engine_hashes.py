@@ -0,0 +1,51 @@
+import hashlib
+import json
+import os
+import sys
+import urllib.request
+
+# 引擎清单位置（相对本脚本）
+MANIFEST_PATH = os.path.join(os.path.dirname(__file__), '..', 'src-tauri', 'engines.json')
+# 每次读取的字节数
+CHUNK_SIZE = 1024 * 1024
+
+
+def hash_url(url):
+    """
+    下载引擎包并计算其大小与 SHA-256，不在本地保留文件。
+    """
+    hasher = hashlib.sha256()
+    size = 0
+    with urllib.request.urlopen(url) as response:
+        while True:
+            chunk = response.read(CHUNK_SIZE)
+            if not chunk:
+                break
+            hasher.update(chunk)
+            size += len(chunk)
+    return size, hasher.hexdigest()
+
+
+def update_manifest(manifest_path):
+    """
+    为清单中的每个发行包写入 size 与 sha256。
+    应用拒绝安装没有 sha256 的引擎包，发布或修改引擎下载地址后需要运行本脚本。
+    """
+    with open(manifest_path, 'r', encoding='utf-8') as f:
+        manifest = json.load(f)
+
+    for entry in manifest['engines']:
+        for platform, asset in entry.get('platforms', {}).items():
+            print(f"正在下载 {entry['id']} ({platform}): {asset['url']}")
+            size, sha256 = hash_url(asset['url'])
+            asset['size'] = size
+            asset['sha256'] = sha256
+            print(f"  size={size} sha256={sha256}")
+
+    with open(manifest_path, 'w', encoding='utf-8') as f:
+        json.dump(manifest, f, ensure_ascii=False, indent=2)
+        f.write('\n')
+
+
+if __name__ == '__main__':
+    update_manifest(sys.argv[1] if len(sys.argv) > 1 else MANIFEST_PATH)
@@ -0,0 +1,244 @@
+// --- 文件: src-tauri/src/actions.rs ---
+
+//! 截图后的首要动作
+//!
+//! 每种动作由一个 `ActionHandler` 实现，并登记在 `HANDLERS` 中。选区截图、保存区域、窗口截图、
+//! 长截图以及预览窗口中的手动处理都通过 `dispatch` 执行动作。
+//! 新增动作时，在 `PrimaryAction` 中添加变体，再为它实现 `ActionHandler` 并登记到 `HANDLERS`。
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::commands::{cache_result, create_and_show_image_viewer_window, perform_ocr, send_notification};
+use crate::settings::{copy_image_to_clipboard, save_image_to_desktop, AppSettings};
+use crate::translator;
+use crate::ImageViewerPayload;
+
+/// 截图后的首要动作，序列化为设置文件和前端使用的 snake_case 名称。
+///
+/// 无法识别的名称保留为 `Unknown`，不会导致整份设置解析失败；`AppSettings::validate` 会把它报告为字段错误。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum PrimaryAction {
+    // 识别文字并复制到剪贴板
+    Ocr,
+    // 识别文字并翻译，译文复制到剪贴板
+    OcrTranslate,
+    // 复制截图
+    Copy,
+    // 保存截图到桌面
+    Save,
+    // 打开预览窗口
+    Preview,
+    // 无法识别的动作名称（手动编辑或更新版本写入的设置）
+    Unknown(String),
+}
+
+// 可以识别的动作
+const KNOWN_ACTIONS: [PrimaryAction; 5] = [
+    PrimaryAction::Ocr,
+    PrimaryAction::OcrTranslate,
+    PrimaryAction::Copy,
+    PrimaryAction::Save,
+    PrimaryAction::Preview,
+];
+
+impl PrimaryAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PrimaryAction::Ocr => "ocr",
+            PrimaryAction::OcrTranslate => "ocr_translate",
+            PrimaryAction::Copy => "copy",
+            PrimaryAction::Save => "save",
+            PrimaryAction::Preview => "preview",
+            PrimaryAction::Unknown(name) => name,
+        }
+    }
+
+    /// 无法识别的动作返回错误，供命令在执行前拒绝
+    pub fn ensure_supported(&self) -> Result<(), String> {
+        match self {
+            PrimaryAction::Unknown(name) => Err(format!("不支持的动作: {}", name)),
+            _ => Ok(()),
+        }
+    }
+
+    /// 该动作是否会产生 OCR 结果（可在结果窗口中查看）
+    pub fn produces_ocr_result(&self) -> bool {
+        matches!(self, PrimaryAction::Ocr | PrimaryAction::OcrTranslate)
+    }
+}
+
+impl From<String> for PrimaryAction {
+    fn from(name: String) -> Self {
+        KNOWN_ACTIONS
+            .into_iter()
+            .find(|action| action.as_str() == name)
+            .unwrap_or(PrimaryAction::Unknown(name))
+    }
+}
+
+impl From<PrimaryAction> for String {
+    fn from(action: PrimaryAction) -> Self {
+        action.as_str().to_string()
+    }
+}
+
+impl fmt::Display for PrimaryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 对一张已保存到缓存目录的图片执行的动作
+#[async_trait]
+pub trait ActionHandler {
+    async fn run(&self, app: &AppHandle, image_path: &Path, settings: &AppSettings);
+}
+
+// 动作处理器登记表
+static HANDLERS: [(PrimaryAction, &(dyn ActionHandler + Send + Sync)); 5] = [
+    (PrimaryAction::Ocr, &OcrAction { translate: false }),
+    (PrimaryAction::OcrTranslate, &OcrAction { translate: true }),
+    (PrimaryAction::Copy, &CopyAction),
+    (PrimaryAction::Save, &SaveAction),
+    (PrimaryAction::Preview, &PreviewAction),
+];
+
+/// 查找动作对应的处理器并执行
+pub async fn dispatch(app: &AppHandle, action: &PrimaryAction, image_path: &Path, settings: &AppSettings) {
+    println!("[ACTIONS] 执行动作 {}: {:?}", action, image_path);
+    match HANDLERS.iter().find(|(registered, _)| registered == action) {
+        Some((_, handler)) => handler.run(app, image_path, settings).await,
+        None => {
+            eprintln!("[ACTIONS] 动作 {} 没有登记处理器", action);
+            send_notification(app, "❌ 处理失败", &format!("不支持的动作: {}", action));
+        }
+    }
+}
+
+// --- 动作处理器 ---
+
+struct OcrAction {
+    translate: bool,
+}
+
+#[async_trait]
+impl ActionHandler for OcrAction {
+    async fn run(&self, app: &AppHandle, image_path: &Path, settings: &AppSettings) {
+        let image_path = image_path.to_string_lossy().to_string();
+        let ocr = match perform_ocr(app, &image_path, settings) {
+            Ok(ocr) => ocr,
+            Err(e) => {
+                send_notification(app, "❌ 识别失败", &e);
+                cache_result(app, None, None, image_path);
+                return;
+            }
+        };
+
+        let text = ocr.text.clone();
+        if let Ok(mut clipboard) = arboard::Clipboard::new() {
+            let _ = clipboard.set_text(text.clone());
+        }
+        if !self.translate {
+            send_notification(app, "✅ 文字识别成功", "内容已复制到剪贴板。");
+            cache_result(app, Some(ocr), None, image_path);
+            return;
+        }
+
+        let translator = translator::get_translator(app);
+        match translator.translate(&text, &settings.target_lang).await {
+            Ok(trans_text) => {
+                if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                    let _ = clipboard.set_text(trans_text.clone());
+                }
+                send_notification(app, "✅ 翻译完成", "译文已复制。按 Win+V 查看原文。");
+                cache_result(app, Some(ocr), Some(trans_text), image_path);
+            }
+            Err(e) => {
+                let err_msg = if e.contains("找不到翻译引擎") { "未安装翻译引擎，请在设置中下载".to_string() } else { format!("OCR成功但翻译出错: {}", e) };
+                send_notification(app, "⚠️ 翻译失败", &err_msg);
+                cache_result(app, Some(ocr), Some(err_msg), image_path);
+            }
+        }
+    }
+}
+
+struct CopyAction;
+
+#[async_trait]
+impl ActionHandler for CopyAction {
+    async fn run(&self, app: &AppHandle, image_path: &Path, _settings: &AppSettings) {
+        match copy_image_to_clipboard(image_path.to_string_lossy().to_string()).await {
+            Ok(_) => send_notification(app, "✅ 复制成功", "截图已复制到剪贴板。"),
+            Err(e) => send_notification(app, "❌ 复制失败", &e),
+        }
+    }
+}
+
+struct SaveAction;
+
+#[async_trait]
+impl ActionHandler for SaveAction {
+    async fn run(&self, app: &AppHandle, image_path: &Path, _settings: &AppSettings) {
+        match save_image_to_desktop(image_path.to_string_lossy().to_string()).await {
+            Ok(_) => send_notification(app, "✅ 保存成功", "截图已保存到桌面。"),
+            Err(e) => send_notification(app, "❌ 保存失败", &e),
+        }
+    }
+}
+
+struct PreviewAction;
+
+#[async_trait]
+impl ActionHandler for PreviewAction {
+    async fn run(&self, app: &AppHandle, image_path: &Path, _settings: &AppSettings) {
+        match fs::read(image_path) {
+            Ok(bytes) => {
+                let b64 = general_purpose::STANDARD.encode(&bytes);
+                let payload = ImageViewerPayload {
+                    image_data_url: format!("data:image/png;base64,{}", b64),
+                    image_path: image_path.to_string_lossy().to_string(),
+                };
+                create_and_show_image_viewer_window(app, payload);
+            }
+            Err(_) => send_notification(app, "❌ 错误", "无法读取截图文件进行预览。"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_action_names() {
+        for action in KNOWN_ACTIONS {
+            assert_eq!(PrimaryAction::from(action.as_str().to_string()), action);
+            assert!(HANDLERS.iter().any(|(registered, _)| *registered == action), "{} 没有登记处理器", action);
+        }
+        assert_eq!(PrimaryAction::from("OCR".to_string()), PrimaryAction::Unknown("OCR".to_string()));
+    }
+
+    #[test]
+    fn rejects_unknown_actions() {
+        for action in KNOWN_ACTIONS {
+            assert!(action.ensure_supported().is_ok());
+        }
+        let error = PrimaryAction::from("teleport".to_string()).ensure_supported().unwrap_err();
+        assert_eq!(error, "不支持的动作: teleport");
+    }
+
+    #[test]
+    fn serializes_unknown_actions_unchanged() {
+        let action: PrimaryAction = serde_json::from_str("\"fax\"").unwrap();
+        assert_eq!(action, PrimaryAction::Unknown("fax".to_string()));
+        assert_eq!(serde_json::to_string(&action).unwrap(), "\"fax\"");
+        assert_eq!(serde_json::to_string(&PrimaryAction::OcrTranslate).unwrap(), "\"ocr_translate\"");
+    }
+}
@@ -0,0 +1,287 @@
+// --- 文件: src-tauri/src/archive.rs ---
+
+//! 引擎包解压
+//!
+//! 在线下载的引擎包都是 7z 格式；离线安装时管理员也可能提供 zip 或 tar.gz 格式的包，
+//! 这里按扩展名（无法识别时按文件头）判断格式并解压到指定目录。
+//! 解压前逐个检查条目路径，拒绝绝对路径和 `..`，防止引擎包把文件写到解压目录之外。
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// 支持的引擎包格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    SevenZ,
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// 清单中使用的格式名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SevenZ => "7z",
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    /// 先按扩展名判断，无法识别时读取文件头
+    pub fn detect(path: &Path) -> Result<Self, String> {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if name.ends_with(".7z") {
+            return Ok(Self::SevenZ);
+        }
+        if name.ends_with(".zip") {
+            return Ok(Self::Zip);
+        }
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            return Ok(Self::TarGz);
+        }
+
+        let mut header = [0u8; 6];
+        let read = fs::File::open(path)
+            .and_then(|mut f| f.read(&mut header))
+            .map_err(|e| format!("读取引擎包失败: {}", e))?;
+        match &header[..read] {
+            [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C] => Ok(Self::SevenZ),
+            [b'P', b'K', 0x03, 0x04, ..] => Ok(Self::Zip),
+            [0x1F, 0x8B, ..] => Ok(Self::TarGz),
+            _ => Err(format!("无法识别的引擎包格式: {}，支持 .7z / .zip / .tar.gz", path.display())),
+        }
+    }
+}
+
+/// 校验压缩包内条目的路径，拒绝绝对路径、盘符和 `..`，返回相对解压目录的安全路径。
+///
+/// 压缩包根目录条目（如 tar 中的 `./`）返回空路径，由调用方跳过。
+pub fn safe_entry_path(name: &str) -> Result<PathBuf, String> {
+    let normalized = name.replace('\\', "/");
+    let unsafe_entry = || format!("引擎包中包含不安全的路径，已拒绝安装: {}", name);
+    if normalized.starts_with('/') {
+        return Err(unsafe_entry());
+    }
+    let mut path = PathBuf::new();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return Err(unsafe_entry()),
+            // Windows 盘符 (C:) 或 NTFS 备用数据流
+            p if p.contains(':') => return Err(unsafe_entry()),
+            p => path.push(p),
+        }
+    }
+    Ok(path)
+}
+
+/// 把引擎包解压到 `dest` 目录。任何条目的路径不安全时整体失败（已解压的部分由调用方清理）。
+pub fn extract(archive: &Path, dest: &Path) -> Result<(), String> {
+    let format = ArchiveFormat::detect(archive)?;
+    println!("[ARCHIVE] 解压 {:?} ({:?}) 到 {:?}", archive, format, dest);
+    fs::create_dir_all(dest).map_err(|e| format!("创建目录失败: {}", e))?;
+    match format {
+        ArchiveFormat::SevenZ => extract_7z(archive, dest),
+        ArchiveFormat::Zip => extract_zip(archive, dest),
+        ArchiveFormat::TarGz => extract_tar_gz(archive, dest),
+    }
+}
+
+fn extract_7z(archive: &Path, dest: &Path) -> Result<(), String> {
+    // 路径校验失败的原因，用于替换 sevenz-rust 的错误信息
+    let mut rejected = None;
+    let result = sevenz_rust::decompress_file_with_extract_fn(archive, dest, |entry, reader, _| {
+        let relative = match safe_entry_path(entry.name()) {
+            Ok(relative) => relative,
+            Err(e) => {
+                rejected = Some(e.clone());
+                return Err(sevenz_rust::Error::other(e));
+            }
+        };
+        if relative.as_os_str().is_empty() {
+            return Ok(true);
+        }
+        sevenz_rust::default_entry_extract_fn(entry, reader, &dest.join(relative))
+    });
+    match (result, rejected) {
+        (_, Some(e)) => Err(e),
+        (Err(e), None) => Err(format!("解压7z文件失败: {:?}", e)),
+        (Ok(()), None) => Ok(()),
+    }
+}
+
+fn extract_zip(archive: &Path, dest: &Path) -> Result<(), String> {
+    let file = fs::File::open(archive).map_err(|e| format!("打开zip文件失败: {}", e))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("读取zip文件失败: {}", e))?;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| format!("读取zip文件失败: {}", e))?;
+        let relative = safe_entry_path(entry.name())?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let out_path = dest.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path).map_err(|e| format!("创建目录失败: {}", e))?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let mut out = fs::File::create(&out_path).map_err(|e| format!("创建文件失败: {}", e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| format!("解压zip文件失败: {}", e))?;
+        // 保留可执行权限，否则 Linux / macOS 上的引擎无法运行
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&out_path, fs::Permissions::from_mode(mode));
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar_gz(archive: &Path, dest: &Path) -> Result<(), String> {
+    let file = fs::File::open(archive).map_err(|e| format!("打开tar.gz文件失败: {}", e))?;
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let entries = tar.entries().map_err(|e| format!("读取tar.gz文件失败: {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("读取tar.gz文件失败: {}", e))?;
+        let name = entry.path().map_err(|e| format!("读取tar.gz文件失败: {}", e))?.to_string_lossy().into_owned();
+        let relative = safe_entry_path(&name)?;
+        // 链接可能指向解压目录之外，同样检查其目标
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let target = entry.link_name().ok().flatten()
+                .map(|t| t.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            safe_entry_path(&target)?;
+        }
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let out_path = dest.join(relative);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        entry.unpack(&out_path).map_err(|e| format!("解压tar.gz文件失败: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("archive-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 生成 tar.gz，`links` 中的每一项为 (链接名, 链接目标)
+    fn write_tar_gz(path: &Path, files: &[(&str, &[u8])], links: &[(&str, &str)]) {
+        let encoder = flate2::write::GzEncoder::new(fs::File::create(path).unwrap(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            builder.append_data(&mut header, name, *content).unwrap();
+        }
+        for (name, target) in links {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, name, target).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    fn write_zip(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn accepts_relative_entry_paths() {
+        assert_eq!(safe_entry_path("bin/engine.exe").unwrap(), Path::new("bin").join("engine.exe"));
+        assert_eq!(safe_entry_path("./models//zh.bin").unwrap(), Path::new("models").join("zh.bin"));
+        assert_eq!(safe_entry_path("models\\en.bin").unwrap(), Path::new("models").join("en.bin"));
+        assert!(safe_entry_path("./").unwrap().as_os_str().is_empty());
+    }
+
+    #[test]
+    fn rejects_unsafe_entry_paths() {
+        for name in [
+            "../evil.exe",
+            "models/../../evil.exe",
+            "..\\evil.exe",
+            "/etc/passwd",
+            "\\Windows\\System32\\evil.dll",
+            "C:/Windows/evil.dll",
+            "C:\\Windows\\evil.dll",
+            "c:evil.dll",
+            "engine.exe:stream",
+        ] {
+            assert!(safe_entry_path(name).is_err(), "应拒绝 {}", name);
+        }
+    }
+
+    #[test]
+    fn detects_format_by_extension_and_header() {
+        let dir = test_dir("detect");
+        let zip_path = dir.join("engine.zip");
+        write_zip(&zip_path, &[("a.txt", b"a")]);
+        assert_eq!(ArchiveFormat::detect(&zip_path).unwrap(), ArchiveFormat::Zip);
+        assert_eq!(ArchiveFormat::detect(Path::new("engine.TGZ")).unwrap(), ArchiveFormat::TarGz);
+
+        // 没有扩展名时按文件头判断
+        let renamed = dir.join("engine");
+        fs::rename(&zip_path, &renamed).unwrap();
+        assert_eq!(ArchiveFormat::detect(&renamed).unwrap(), ArchiveFormat::Zip);
+        fs::write(&renamed, b"plain text").unwrap();
+        assert!(ArchiveFormat::detect(&renamed).is_err());
+    }
+
+    #[test]
+    fn extracts_zip_and_tar_gz() {
+        let dir = test_dir("extract");
+        let zip_path = dir.join("engine.zip");
+        write_zip(&zip_path, &[("engine/run.exe", b"zip"), ("engine/models/zh.bin", b"model")]);
+        extract(&zip_path, &dir.join("from-zip")).unwrap();
+        assert_eq!(fs::read(dir.join("from-zip/engine/run.exe")).unwrap(), b"zip");
+        assert_eq!(fs::read(dir.join("from-zip/engine/models/zh.bin")).unwrap(), b"model");
+
+        let tar_path = dir.join("engine.tar.gz");
+        write_tar_gz(&tar_path, &[("engine/run", b"tar")], &[("engine/run-link", "run")]);
+        extract(&tar_path, &dir.join("from-tar")).unwrap();
+        assert_eq!(fs::read(dir.join("from-tar/engine/run")).unwrap(), b"tar");
+    }
+
+    #[test]
+    fn rejects_zip_entries_outside_the_destination() {
+        let dir = test_dir("zip-slip");
+        let zip_path = dir.join("evil.zip");
+        write_zip(&zip_path, &[("engine/run.exe", b"ok"), ("../evil.exe", b"evil")]);
+        let dest = dir.join("out");
+        assert!(extract(&zip_path, &dest).unwrap_err().contains("不安全的路径"));
+        assert!(!dir.join("evil.exe").exists());
+    }
+
+    #[test]
+    fn rejects_symlinks_pointing_outside_the_destination() {
+        let dir = test_dir("symlink");
+        for (index, target) in ["/etc/passwd", "../../outside", "C:\\Windows\\System32"].iter().enumerate() {
+            let tar_path = dir.join(format!("evil-{}.tar.gz", index));
+            write_tar_gz(&tar_path, &[("engine/run", b"tar")], &[("engine/link", target)]);
+            let error = extract(&tar_path, &dir.join(format!("out-{}", index))).unwrap_err();
+            assert!(error.contains("不安全的路径"), "{}: {}", target, error);
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use xcap::Monitor;
+use xcap::{Monitor, Window};
 use image::RgbaImage;
+use serde::Serialize;
 use std::io::Cursor;
 use base64::{Engine as _, engine::general_purpose};
 // --- 新增：引入 png 库的相关模块以进行性能优化 ---
@@ -13,31 +14,50 @@ use png::Compression;
 /// - `Ok(RgbaImage)`: 成功捕获到的 RGBA 格式的图像缓冲区。
 /// - `Err(String)`: 捕获过程中发生的错误信息。
 pub fn capture_fullscreen() -> Result<RgbaImage, String> {
+    capture_monitor(None)
+}
+
+/// 捕获指定名称显示器的全屏图像。
+///
+/// # 参数
+/// - `monitor_name`: 显示器名称；为 `None` 或找不到同名显示器时使用主显示器。
+///
+/// # 返回
+///
+/// `Result<RgbaImage, String>`: 与 `capture_fullscreen` 相同。
+pub fn capture_monitor(monitor_name: Option<&str>) -> Result<RgbaImage, String> {
     // 1. 获取所有连接的显示器
     let monitors = Monitor::all().map_err(|e| format!("无法获取显示器列表: {}", e))?;
     if monitors.is_empty() {
         return Err("未找到任何显示器".to_string());
     }
 
-    // 2. 查找主显示器
-    let primary_monitor = monitors.into_iter()
-        .find(|m| m.is_primary().unwrap_or(false))
-        .or_else(|| Monitor::all().ok()?.into_iter().next()) // 如果没有主显示器，就用第一个
+    // 2. 优先查找指定名称的显示器，其次是主显示器
+    let named_monitor = monitor_name.and_then(|name| {
+        let found = monitors.iter().find(|m| m.name().map(|n| n == name).unwrap_or(false)).cloned();
+        if found.is_none() {
+            println!("未找到名为 '{}' 的显示器，改用主显示器", name);
+        }
+        found
+    });
+    let target_monitor = named_monitor
+        .or_else(|| monitors.iter().find(|m| m.is_primary().unwrap_or(false)).cloned())
+        .or_else(|| monitors.into_iter().next()) // 如果没有主显示器，就用第一个
         .ok_or_else(|| "无法确定要捕获的显示器".to_string())?;
 
-    let monitor_name = primary_monitor.name().unwrap_or_else(|_| "未知名称".to_string());
-    let monitor_width = primary_monitor.width().unwrap_or(0);
-    let monitor_height = primary_monitor.height().unwrap_or(0);
+    let monitor_name = target_monitor.name().unwrap_or_else(|_| "未知名称".to_string());
+    let monitor_width = target_monitor.width().unwrap_or(0);
+    let monitor_height = target_monitor.height().unwrap_or(0);
 
     println!(
-        "准备在主显示器上截图: (名称={}, 尺寸={}x{})",
+        "准备在显示器上截图: (名称={}, 尺寸={}x{})",
         monitor_name,
         monitor_width,
         monitor_height
     );
 
     // 3. 执行截图操作
-    let image = primary_monitor
+    let image = target_monitor
         .capture_image()
         .map_err(|e| format!("在显示器 '{}' 上截图失败: {}", monitor_name, e))?;
 
@@ -47,6 +67,128 @@ pub fn capture_fullscreen() -> Result<RgbaImage, String> {
     Ok(image)
 }
 
+/// 获取主显示器的名称，用于记录保存区域所在的显示器。
+pub fn primary_monitor_name() -> Option<String> {
+    Monitor::all().ok()?
+        .into_iter()
+        .find(|m| m.is_primary().unwrap_or(false))
+        .and_then(|m| m.name().ok())
+}
+
+
+/// 顶层窗口的基本信息
+///
+/// `list_windows` 返回的坐标是全局屏幕坐标；
+/// `list_windows_on_primary_monitor` 返回的坐标相对于主显示器截图（即选区遮罩）的左上角，单位为截图的像素。
+#[derive(Clone, Debug, Serialize)]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    // 层叠顺序，数值越大越靠前
+    pub z: i32,
+    pub is_minimized: bool,
+}
+
+/// 枚举所有可见的顶层窗口，按层叠顺序从前到后排列。
+///
+/// 本程序自身的窗口（如选区遮罩、结果窗口）不会出现在列表中。
+pub fn list_windows() -> Result<Vec<WindowInfo>, String> {
+    let own_pid = std::process::id();
+    let windows = Window::all().map_err(|e| format!("无法获取窗口列表: {}", e))?;
+
+    let mut infos: Vec<WindowInfo> = windows.iter()
+        .filter(|w| w.pid().map(|pid| pid != own_pid).unwrap_or(true))
+        .filter_map(|w| {
+            Some(WindowInfo {
+                id: w.id().ok()?,
+                title: w.title().unwrap_or_default(),
+                app_name: w.app_name().unwrap_or_default(),
+                x: w.x().ok()?,
+                y: w.y().ok()?,
+                width: w.width().ok()?,
+                height: w.height().ok()?,
+                z: w.z().unwrap_or(0),
+                is_minimized: w.is_minimized().unwrap_or(false),
+            })
+        })
+        .filter(|info| info.width > 0 && info.height > 0)
+        .collect();
+
+    infos.sort_by(|a, b| b.z.cmp(&a.z));
+    Ok(infos)
+}
+
+/// 列出主显示器上可见的窗口，坐标换算为相对主显示器左上角并裁剪到显示器范围内。
+///
+/// 窗口与显示器的坐标先在系统坐标中求交集，再换算为截图（宽度为 `image_width`）的像素。
+/// Windows 上本程序按每个显示器感知 DPI，系统坐标已经是物理像素，比例通常为 1；
+/// 仍按截图宽度与显示器宽度之比换算，以兼容未感知 DPI 的情况。
+/// macOS 上的坐标是逻辑点，按显示器的缩放比例换算。
+/// 选区遮罩使用这些矩形实现“吸附到鼠标下方窗口”。
+pub fn list_windows_on_primary_monitor(image_width: u32) -> Result<Vec<WindowInfo>, String> {
+    let monitor = Monitor::all().map_err(|e| format!("无法获取显示器列表: {}", e))?
+        .into_iter()
+        .find(|m| m.is_primary().unwrap_or(false))
+        .ok_or_else(|| "无法确定主显示器".to_string())?;
+    let (mx, my) = (monitor.x().unwrap_or(0), monitor.y().unwrap_or(0));
+    let (mw, mh) = (monitor.width().unwrap_or(0) as i32, monitor.height().unwrap_or(0) as i32);
+    let scale = if cfg!(target_os = "macos") {
+        monitor.scale_factor().unwrap_or(1.0) as f64
+    } else if mw > 0 {
+        image_width as f64 / mw as f64
+    } else {
+        1.0
+    };
+    let to_physical = |v: i32| (v as f64 * scale).round() as i32;
+
+    Ok(list_windows()?
+        .into_iter()
+        .filter(|w| !w.is_minimized)
+        .filter_map(|w| {
+            // 求窗口与显示器的交集
+            let left = (w.x - mx).max(0);
+            let top = (w.y - my).max(0);
+            let right = (w.x - mx + w.width as i32).min(mw);
+            let bottom = (w.y - my + w.height as i32).min(mh);
+            if right <= left || bottom <= top {
+                return None;
+            }
+            let (left, top, right, bottom) = (to_physical(left), to_physical(top), to_physical(right), to_physical(bottom));
+            Some(WindowInfo { x: left, y: top, width: (right - left) as u32, height: (bottom - top) as u32, ..w })
+        })
+        .collect())
+}
+
+/// 按窗口 ID 直接截取窗口内容，即使窗口被其它窗口遮挡也能截取。
+///
+/// # 返回
+///
+/// `Result<RgbaImage, String>`:
+/// - `Ok(RgbaImage)`: 窗口图像。
+/// - `Err(String)`: 找不到窗口、窗口已最小化或截图失败。
+pub fn capture_window(window_id: u32) -> Result<RgbaImage, String> {
+    let window = Window::all().map_err(|e| format!("无法获取窗口列表: {}", e))?
+        .into_iter()
+        .find(|w| w.id().map(|id| id == window_id).unwrap_or(false))
+        .ok_or_else(|| format!("找不到 ID 为 {} 的窗口", window_id))?;
+
+    let title = window.title().unwrap_or_default();
+    if window.is_minimized().unwrap_or(false) {
+        return Err(format!("窗口 '{}' 已最小化，无法截取", title));
+    }
+
+    println!("准备截取窗口: (ID={}, 标题={})", window_id, title);
+    let image = window.capture_image()
+        .map_err(|e| format!("截取窗口 '{}' 失败: {}", title, e))?;
+    println!("窗口截图成功，图像尺寸: {}x{}", image.width(), image.height());
+    Ok(image)
+}
+
 
 /// 将图像缓冲区编码为 Base64 格式的 Data URL。
 ///
@@ -83,4 +225,23 @@ pub fn encode_image_to_data_url(image: &RgbaImage) -> Result<String, String> {
 
     // 5. 构造成前端可以直接使用的 Data URL 格式
     Ok(format!("data:image/png;base64,{}", base64_str))
-}
\ No newline at end of file
+}
+
+/// 从整屏图像中裁剪出指定区域，并把区域限制在图像边界之内。
+///
+/// # 返回
+///
+/// `Result<RgbaImage, String>`:
+/// - `Ok(RgbaImage)`: 裁剪后的图像。
+/// - `Err(String)`: 区域完全落在图像之外或尺寸为零。
+pub fn crop_to_region(image: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> Result<RgbaImage, String> {
+    if x >= image.width() || y >= image.height() {
+        return Err(format!("区域起点 ({}, {}) 超出截图范围 {}x{}", x, y, image.width(), image.height()));
+    }
+    let width = width.min(image.width() - x);
+    let height = height.min(image.height() - y);
+    if width == 0 || height == 0 {
+        return Err("区域尺寸为零".to_string());
+    }
+    Ok(image::imageops::crop_imm(image, x, y, width, height).to_image())
+}
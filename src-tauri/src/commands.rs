@@ -2,41 +2,42 @@
 
 use serde::{Serialize};
 use tauri::{Manager, State};
-use std::process::Command as StdCommand;
 use std::fs;
-use std::io::Write;
 use base64::{Engine as _, engine::general_purpose};
 use std::sync::atomic::Ordering;
 use tauri::api::notification::Notification;
-use futures_util::StreamExt;
 use std::path::{Path, PathBuf};
+use std::collections::HashSet;
 
 use crate::ImageViewerPayload;
-use crate::settings::{AppSettings, AppState, LastOcrResult, copy_image_to_clipboard, save_image_to_desktop};
-use crate::translator;
-
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
-
-// --- 事件 Payload 定义 ---
+use crate::actions::{self, PrimaryAction};
+use crate::engine::{self, EngineId};
+use crate::settings::{AppSettings, AppState, LastOcrResult};
+use crate::ocr::{self, OcrEngine, OcrOutput};
+use crate::preprocess::PreprocessOptions;
+use crate::worker::{self, WorkerStatus};
+
+// 翻译引擎状态：是否已安装，以及常驻进程的运行情况
 #[derive(Clone, Serialize)]
-struct DownloadProgressPayload {
-    progress: u64,
-    total: u64,
-    status: String,
+pub struct TranslatorStatus {
+    installed: bool,
+    worker: WorkerStatus,
 }
 
-// --- 常量定义 ---
-// OCR 引擎 (RapidOCR)
-const OCR_URL: &str = "https://github.com/hiroi-sora/RapidOCR-json/releases/download/v0.2.0/RapidOCR-json_v0.2.0.7z";
-const OCR_EXE_NAME: &str = "RapidOCR-json.exe";
-// 定义解压后的子目录名
-const OCR_DIR_NAME: &str = "RapidOCR-json_v0.2.0";
+// 缓存清理结果
+#[derive(Clone, Serialize, Default)]
+pub struct CacheCleanupReport {
+    removed_files: usize,
+    freed_bytes: u64,
+    // 被历史记录引用或仍在使用而保留的文件数
+    kept_files: usize,
+}
 
-// 翻译引擎 (LocalTranslator)
-// 修改：升级到 0.2.0 版本，使用 7z 格式
-const TRANSLATOR_URL: &str = "https://github.com/git-hub-cc/LocalTranslator/releases/download/V0.2.0/LocalTranslator-0.2.0.7z";
-const TRANSLATOR_EXE_NAME: &str = "translate_engine.exe";
+// --- 常量定义 ---
+// 延时截图的最长等待时间（秒）
+const MAX_CAPTURE_DELAY_SECS: u64 = 60;
+// 最近修改过的缓存文件可能仍在使用（翻译请求、监视帧），清理时跳过
+const CACHE_IN_USE_SECS: u64 = 60;
 
 // --- Tauri 命令定义 ---
 
@@ -48,147 +49,49 @@ pub fn get_last_ocr_result(state: State<AppState>) -> Option<LastOcrResult> {
 
 // --- OCR 引擎管理 ---
 #[tauri::command]
-pub async fn check_ocr_status(app: tauri::AppHandle) -> Result<bool, String> {
-    let local_data_dir = app.path_resolver().app_local_data_dir()
-        .ok_or("无法获取本地数据目录")?;
-    // 在路径中加入子目录
-    let exe_path = local_data_dir.join(OCR_DIR_NAME).join(OCR_EXE_NAME);
-    let exists = exe_path.exists();
-    println!("[STATUS] 检查 OCR 状态: 路径='{:?}', 是否存在={}", exe_path, exists);
-    Ok(exists)
+pub async fn check_ocr_status(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    // Tesseract 需要实际运行一次 `--version`，放到阻塞线程中执行
+    let available = tokio::task::spawn_blocking({
+        let app = app.clone();
+        let settings = settings.clone();
+        move || ocr::is_engine_available(&app, &settings)
+    }).await.map_err(|e| format!("检查 OCR 引擎任务异常: {}", e))?;
+    println!("[STATUS] 检查 OCR 状态: 引擎='{}', 是否可用={}", settings.ocr_engine, available);
+    Ok(available)
 }
 
+/// 列出本机 Tesseract 已安装的语言包，供设置页面选择
 #[tauri::command]
-pub async fn download_ocr(app: tauri::AppHandle) -> Result<(), String> {
-    println!("[DOWNLOAD_OCR] 开始下载 OCR 引擎...");
-    let window = app.get_window("main").ok_or("找不到主窗口")?;
-    let local_data_dir = app.path_resolver().app_local_data_dir().ok_or("无法获取本地数据目录")?;
-    println!("[DOWNLOAD_OCR] 本地数据目录: {:?}", local_data_dir);
-    if !local_data_dir.exists() {
-        println!("[DOWNLOAD_OCR] 目录不存在，正在创建...");
-        fs::create_dir_all(&local_data_dir).map_err(|e| format!("创建目录失败: {}", e))?;
-    }
-    let archive_path = local_data_dir.join("ocr.7z");
-    println!("[DOWNLOAD_OCR] 存档将保存到: {:?}", archive_path);
-
-    // 1. 下载文件
-    println!("[DOWNLOAD_OCR] 正在从 URL 下载: {}", OCR_URL);
-    let client = reqwest::Client::new();
-    let res = client.get(OCR_URL).send().await.map_err(|e| {
-        let err_msg = format!("请求失败: {}", e);
-        println!("[DOWNLOAD_OCR] 错误: {}", err_msg);
-        err_msg
-    })?;
-    let total_size = res.content_length().unwrap_or(0);
-    println!("[DOWNLOAD_OCR] 文件总大小: {} bytes", total_size);
-
-    let mut downloaded: u64 = 0;
-    let mut stream = res.bytes_stream();
-    let mut file = fs::File::create(&archive_path).map_err(|e| {
-        let err_msg = format!("创建文件失败: {}", e);
-        println!("[DOWNLOAD_OCR] 错误: {}", err_msg);
-        err_msg
-    })?;
-
-    while let Some(item) = stream.next().await {
-        let chunk = item.map_err(|e| {
-            let err_msg = format!("下载流出错: {}", e);
-            println!("[DOWNLOAD_OCR] 错误: {}", err_msg);
-            err_msg
-        })?;
-        file.write_all(&chunk).map_err(|e| {
-            let err_msg = format!("写入文件块失败: {}", e);
-            println!("[DOWNLOAD_OCR] 错误: {}", err_msg);
-            err_msg
-        })?;
-        downloaded += chunk.len() as u64;
-        window.emit("ocr-download-progress", DownloadProgressPayload {
-            progress: downloaded, total: total_size, status: "downloading".to_string(),
-        }).unwrap_or(());
-    }
-    println!("[DOWNLOAD_OCR] 下载完成. 总共下载 {} bytes", downloaded);
-
-    // 2. 解压文件 (.7z)
-    println!("[DOWNLOAD_OCR] 开始解压文件: {:?}", archive_path);
-    window.emit("ocr-download-progress", DownloadProgressPayload {
-        progress: total_size, total: total_size, status: "extracting".to_string(),
-    }).unwrap_or(());
-
-    sevenz_rust::decompress_file(&archive_path, &local_data_dir)
-        .map_err(|e| {
-            let err_msg = format!("解压7z文件失败: {:?}", e);
-            println!("[DOWNLOAD_OCR] 错误: {}", err_msg);
-            err_msg
-        })?;
-    println!("[DOWNLOAD_OCR] 解压成功到: {:?}", local_data_dir);
-
-    // 3. 清理并通知完成
-    println!("[DOWNLOAD_OCR] 删除临时存档: {:?}", archive_path);
-    let _ = fs::remove_file(archive_path);
-    println!("[DOWNLOAD_OCR] OCR 引擎安装流程完成.");
-    window.emit("ocr-download-progress", DownloadProgressPayload {
-        progress: total_size, total: total_size, status: "completed".to_string(),
-    }).unwrap_or(());
-
-    Ok(())
+pub async fn list_tesseract_languages(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    tokio::task::spawn_blocking(move || ocr::list_tesseract_languages(&settings))
+        .await
+        .map_err(|e| format!("查询语言包任务异常: {}", e))?
 }
 
 
 // --- 翻译引擎管理 ---
 #[tauri::command]
-pub async fn check_translator_status(app: tauri::AppHandle) -> Result<bool, String> {
-    let local_data_dir = app.path_resolver().app_local_data_dir()
-        .ok_or("无法获取本地数据目录")?;
-    // 注意：如果新版 7z 解压后有一层文件夹（如 LocalTranslator-0.2.0），可能需要调整此路径
-    // 目前保持原逻辑，假设 exe 直接或间接位于我们预期的位置
-    let exe_path = local_data_dir.join(TRANSLATOR_EXE_NAME);
-    Ok(exe_path.exists())
+pub async fn check_translator_status(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<TranslatorStatus, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let installed = engine::find_exe(&app, &settings, EngineId::Translator).is_ok();
+    let worker = worker::status(&app, installed).await;
+    Ok(TranslatorStatus { installed, worker })
 }
 
-#[tauri::command]
-pub async fn download_translator(app: tauri::AppHandle) -> Result<(), String> {
-    let window = app.get_window("main").ok_or("找不到主窗口")?;
-    let local_data_dir = app.path_resolver().app_local_data_dir().ok_or("无法获取本地数据目录")?;
-    if !local_data_dir.exists() {
-        fs::create_dir_all(&local_data_dir).map_err(|e| format!("创建目录失败: {}", e))?;
-    }
+// --- 引擎更新 ---
 
-    // 修改：文件后缀改为 .7z
-    let archive_path = local_data_dir.join("translator.7z");
-
-    // 1. 下载文件
-    println!("[DOWNLOAD_TRANS] 正在从 URL 下载: {}", TRANSLATOR_URL);
-    let client = reqwest::Client::new();
-    let res = client.get(TRANSLATOR_URL).send().await.map_err(|e| format!("请求失败: {}", e))?;
-    let total_size = res.content_length().unwrap_or(0);
-    let mut downloaded: u64 = 0;
-    let mut stream = res.bytes_stream();
-    let mut file = fs::File::create(&archive_path).map_err(|e| format!("创建文件失败: {}", e))?;
-
-    while let Some(item) = stream.next().await {
-        let chunk = item.map_err(|e| format!("下载出错: {}", e))?;
-        file.write_all(&chunk).map_err(|e| format!("写入文件失败: {}", e))?;
-        downloaded += chunk.len() as u64;
-        window.emit("download-progress", DownloadProgressPayload {
-            progress: downloaded, total: total_size, status: "downloading".to_string(),
-        }).unwrap_or(());
+/// 按引擎清单检查已安装的引擎是否有新版本
+#[tauri::command]
+pub async fn check_engine_updates(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<Vec<engine::EngineUpdateInfo>, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let manifest = engine::load_manifest(&settings).await?;
+    let updates = engine::check_updates(&app, &settings, &manifest);
+    for info in &updates {
+        println!("[UPDATE] {}: 已安装={:?}, 最新={}, 可更新={}", info.display_name, info.installed_version, info.latest_version, info.update_available);
     }
-
-    // 2. 解压文件 (.7z) - 修改为使用 sevenz_rust
-    window.emit("download-progress", DownloadProgressPayload {
-        progress: total_size, total: total_size, status: "extracting".to_string(),
-    }).unwrap_or(());
-
-    // 使用 sevenz-rust 进行解压，替代原来的 zip 逻辑
-    sevenz_rust::decompress_file(&archive_path, &local_data_dir)
-        .map_err(|e| format!("解压7z文件失败: {:?}", e))?;
-
-    // 3. 清理并通知完成
-    let _ = fs::remove_file(archive_path);
-    window.emit("download-progress", DownloadProgressPayload {
-        progress: total_size, total: total_size, status: "completed".to_string(),
-    }).unwrap_or(());
-    Ok(())
+    Ok(updates)
 }
 
 // --- 核心功能命令 ---
@@ -226,48 +129,190 @@ pub async fn process_screenshot_area(
     let app_for_task = app.clone();
 
     tokio::spawn(async move {
-        let temp_dir = app_for_task.path_resolver().app_cache_dir().unwrap().join("tmp");
-        let _ = tokio::fs::create_dir_all(&temp_dir).await;
+        process_captured_image(&app_for_task, cropped_image_buffer, &settings).await;
+    });
 
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        let image_filename = format!("screenshot-{}.png", timestamp);
-        let image_path = temp_dir.join(image_filename);
+    Ok(())
+}
 
-        if let Err(e) = cropped_image_buffer.save(&image_path) {
-            eprintln!("[COMMANDS] 保存截图失败: {}", e);
-            hide_loading_and_release_lock(&app_for_task);
-            return;
+/// 保存截取到的图像并按 `settings.primary_action` 执行首要动作。
+///
+/// 完成后会隐藏加载窗口并释放截图锁，调用方需要事先获取截图锁。
+pub(crate) async fn process_captured_image(app: &tauri::AppHandle, image: image::RgbaImage, settings: &AppSettings) {
+    let temp_dir = app.path_resolver().app_cache_dir().unwrap().join("tmp");
+    let _ = tokio::fs::create_dir_all(&temp_dir).await;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let image_filename = format!("screenshot-{}.png", timestamp);
+    let image_path = temp_dir.join(image_filename);
+
+    if let Err(e) = image.save(&image_path) {
+        eprintln!("[COMMANDS] 保存截图失败: {}", e);
+        hide_loading_and_release_lock(app);
+        return;
+    }
+
+    add_image_to_history(&app.state(), image_path.clone());
+
+    actions::dispatch(app, &settings.primary_action, &image_path, settings).await;
+
+    hide_loading_and_release_lock(app);
+}
+
+/// 直接截取显示器上的指定区域（不经过选区遮罩）并执行首要动作。
+pub(crate) async fn capture_and_process_region(
+    app: &tauri::AppHandle,
+    monitor: Option<String>,
+    x: u32, y: u32, width: u32, height: u32,
+    settings: &AppSettings,
+) -> Result<(), String> {
+    capture_and_process(app, move || {
+        let screen = crate::capture::capture_monitor(monitor.as_deref())?;
+        crate::capture::crop_to_region(&screen, x, y, width, height)
+    }, settings).await
+}
+
+/// 在后台线程执行 `capture` 获取图像，然后执行首要动作。
+///
+/// 会获取截图锁，避免与正在进行的选区操作冲突；失败时释放锁并通知用户。
+async fn capture_and_process<F>(app: &tauri::AppHandle, capture: F, settings: &AppSettings) -> Result<(), String>
+where
+    F: FnOnce() -> Result<image::RgbaImage, String> + Send + 'static,
+{
+    let state: State<AppState> = app.state();
+    if state.is_capturing.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return Err("截图正在进行中，请稍后再试。".to_string());
+    }
+
+    if let Some(loading_window) = app.get_window("loading") {
+        let _ = loading_window.center();
+        let _ = loading_window.show();
+    }
+
+    let captured = tokio::task::spawn_blocking(capture)
+        .await
+        .map_err(|e| format!("截图任务异常: {}", e))
+        .and_then(|r| r);
+
+    match captured {
+        Ok(image) => {
+            process_captured_image(app, image, settings).await;
+            Ok(())
         }
+        Err(e) => {
+            hide_loading_and_release_lock(app);
+            send_notification(app, "❌ 截图失败", &e);
+            Err(e)
+        }
+    }
+}
+
+// --- 窗口截图 ---
+
+/// 列出所有可见的顶层窗口（全局屏幕坐标），供脚本选择要截取的窗口
+#[tauri::command]
+pub async fn list_windows() -> Result<Vec<crate::capture::WindowInfo>, String> {
+    tokio::task::spawn_blocking(crate::capture::list_windows)
+        .await
+        .map_err(|e| format!("枚举窗口任务异常: {}", e))?
+}
+
+/// 按窗口 ID 直接截取窗口（即使被遮挡），并执行首要动作。
+///
+/// `action` 可覆盖设置中的首要动作，缺省时沿用设置。
+#[tauri::command]
+pub async fn capture_window(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    window_id: u32,
+    action: Option<PrimaryAction>,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().unwrap().clone();
+    if let Some(action) = action {
+        settings.primary_action = action;
+    }
+    settings.primary_action.ensure_supported()?;
+    println!("[COMMANDS] 截取窗口: id={}, 动作={}", window_id, settings.primary_action);
+    capture_and_process(&app, move || crate::capture::capture_window(window_id), &settings).await
+}
+
+// --- 延时截图 ---
+
+/// 延时截图命令，可供自动化脚本调用。
+///
+/// - `delay_secs`: 延时秒数，缺省时使用设置中的 `capture_delay_secs`。
+/// - `x` / `y` / `width` / `height`: 可选的截图区域。全部提供时延时结束后直接截取该区域并执行首要动作；
+///   全部省略时延时结束后打开选区遮罩。
+#[tauri::command]
+pub async fn start_delayed_capture(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    delay_secs: Option<u64>,
+    x: Option<f64>, y: Option<f64>, width: Option<f64>, height: Option<f64>,
+) -> Result<(), String> {
+    let delay = delay_secs.unwrap_or_else(|| state.settings.lock().unwrap().capture_delay_secs);
+    let region = match (x, y, width, height) {
+        (Some(x), Some(y), Some(w), Some(h)) => Some((x as u32, y as u32, w as u32, h as u32)),
+        (None, None, None, None) => None,
+        _ => return Err("区域参数 x、y、width、height 必须同时提供或同时省略".to_string()),
+    };
+    schedule_delayed_capture(&app, delay, region)
+}
 
-        let image_path_str = image_path.to_str().unwrap().to_string();
+/// 在 `delay_secs` 秒后开始截图，期间每秒广播一次 `delayed-capture-countdown` 事件。
+pub fn schedule_delayed_capture(app: &tauri::AppHandle, delay_secs: u64, region: Option<(u32, u32, u32, u32)>) -> Result<(), String> {
+    let delay_secs = delay_secs.clamp(1, MAX_CAPTURE_DELAY_SECS);
+    let state: State<AppState> = app.state();
+    if state.delayed_capture_pending.swap(true, Ordering::SeqCst) {
+        return Err("已有一个延时截图在等待中".to_string());
+    }
 
-        add_image_to_history(&app_for_task.state(), image_path.clone());
+    println!("[DELAYED] {} 秒后截图, 区域: {:?}", delay_secs, region);
+    send_notification(app, "⏱️ 延时截图", &format!("{} 秒后截图，请在此期间打开需要截取的菜单或提示。", delay_secs));
 
-        match settings.primary_action.as_str() {
-            "ocr" => handle_ocr_mode(&app_for_task, &image_path_str, &settings, false).await,
-            "ocr_translate" => handle_ocr_mode(&app_for_task, &image_path_str, &settings, true).await,
-            "copy" => handle_copy_mode(&app_for_task, image_path_str).await,
-            "save" => handle_save_mode(&app_for_task, image_path_str).await,
-            "preview" | _ => handle_preview_mode(&app_for_task, &image_path, image_path_str).await,
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for remaining in (1..=delay_secs).rev() {
+            let _ = handle.emit_all("delayed-capture-countdown", remaining);
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         }
+        let _ = handle.emit_all("delayed-capture-countdown", 0u64);
 
-        hide_loading_and_release_lock(&app_for_task);
-    });
+        let state: State<AppState> = handle.state();
+        state.delayed_capture_pending.store(false, Ordering::SeqCst);
 
+        match region {
+            None => {
+                if !crate::begin_screen_selection(&handle, "capture") {
+                    println!("[DELAYED] 截图正在进行中，放弃本次延时截图。");
+                }
+            }
+            Some((x, y, width, height)) => {
+                let settings = state.settings.lock().unwrap().clone();
+                if let Err(e) = capture_and_process_region(&handle, None, x, y, width, height, &settings).await {
+                    eprintln!("[DELAYED] 延时区域截图失败: {}", e);
+                }
+            }
+        }
+    });
     Ok(())
 }
 
+/// 对已有的图片（如预览窗口中的截图）执行指定动作，与截图后的首要动作使用同一套处理器。
+///
+/// 识别类动作完成后打开结果窗口。
 #[tauri::command]
 pub async fn process_image_from_path(
     app: tauri::AppHandle,
     state: State<'_, AppState>,
     path: String,
-    action: String
+    action: PrimaryAction,
 ) -> Result<(), String> {
     println!("[COMMANDS] 手动处理图片: {}, 动作: {}", path, action);
+    action.ensure_supported()?;
 
     // --- 修改：在开始处理时显示 Loading 窗口 ---
     if let Some(loading_window) = app.get_window("loading") {
@@ -277,20 +322,7 @@ pub async fn process_image_from_path(
 
     let settings = state.settings.lock().unwrap().clone();
 
-    let do_translate = match action.as_str() {
-        "ocr_translate" => true,
-        "ocr" => false,
-        _ => {
-            println!("[COMMANDS] 未知动作: '{}', 操作已取消。", action);
-            // 异常退出时记得隐藏 Loading
-            if let Some(loading_window) = app.get_window("loading") {
-                let _ = loading_window.hide();
-            }
-            return Ok(());
-        }
-    };
-
-    handle_ocr_mode(&app, &path, &settings, do_translate).await;
+    actions::dispatch(&app, &action, Path::new(&path), &settings).await;
 
     let app_handle_for_main_thread = app.clone();
     app.run_on_main_thread(move || {
@@ -299,7 +331,9 @@ pub async fn process_image_from_path(
             let _ = loading_window.hide();
         }
 
-        crate::show_results_window_with_cache(&app_handle_for_main_thread);
+        if action.produces_ocr_result() {
+            crate::show_results_window_with_cache(&app_handle_for_main_thread);
+        }
     }).map_err(|e| format!("无法在主线程上运行任务: {}", e))?;
 
     Ok(())
@@ -399,80 +433,61 @@ fn add_image_to_history(state: &AppState, image_path: PathBuf) {
     println!("[COMMANDS] 图片已保存至历史记录，当前历史数: {}", history.len());
 }
 
-// 隐藏加载窗口并释放截图锁的辅助函数
-fn hide_loading_and_release_lock(app: &tauri::AppHandle) {
-    if let Some(loading_window) = app.get_window("loading") {
-        let _ = loading_window.hide();
-    }
-    release_lock(app);
-}
-
-async fn handle_copy_mode(app: &tauri::AppHandle, path: String) {
-    match copy_image_to_clipboard(path).await {
-        Ok(_) => send_notification(app, "✅ 复制成功", "截图已复制到剪贴板。"),
-        Err(e) => send_notification(app, "❌ 复制失败", &e),
-    }
-}
-
-async fn handle_save_mode(app: &tauri::AppHandle, path: String) {
-    match save_image_to_desktop(path).await {
-        Ok(_) => send_notification(app, "✅ 保存成功", "截图已保存到桌面。"),
-        Err(e) => send_notification(app, "❌ 保存失败", &e),
-    }
+/// 清理缓存目录 (`app_cache_dir()/tmp`) 中不再被截图历史引用的文件，返回清理结果
+#[tauri::command]
+pub async fn clear_cache(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<CacheCleanupReport, String> {
+    let cache_dir = app.path_resolver().app_cache_dir().ok_or("无法获取应用缓存目录")?.join("tmp");
+    let mut referenced: HashSet<PathBuf> = state.screenshot_history.lock().unwrap().iter().cloned().collect();
+    referenced.extend(state.last_screenshot_path.lock().unwrap().clone());
+
+    let report = tokio::task::spawn_blocking(move || remove_unreferenced_files(&cache_dir, &referenced))
+        .await
+        .map_err(|e| format!("清理缓存任务异常: {}", e))??;
+    println!("[COMMANDS] 缓存清理完成: 删除 {} 个文件，释放 {} bytes，保留 {} 个文件", report.removed_files, report.freed_bytes, report.kept_files);
+    Ok(report)
 }
 
-async fn handle_preview_mode(app: &tauri::AppHandle, path: &std::path::Path, path_str: String) {
-    if let Ok(bytes) = fs::read(path) {
-        let b64 = general_purpose::STANDARD.encode(&bytes);
-        let payload = ImageViewerPayload {
-            image_data_url: format!("data:image/png;base64,{}", b64),
-            image_path: path_str,
+fn remove_unreferenced_files(dir: &Path, referenced: &HashSet<PathBuf>) -> Result<CacheCleanupReport, String> {
+    let mut report = CacheCleanupReport::default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // 缓存目录尚未创建，没有需要清理的文件
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+        Err(e) => return Err(format!("读取缓存目录失败: {}", e)),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let meta = match entry.metadata() {
+            Ok(meta) if meta.is_file() => meta,
+            _ => continue,
         };
-        create_and_show_image_viewer_window(app, payload);
-    } else {
-        send_notification(app, "❌ 错误", "无法读取截图文件进行预览。");
-    }
-}
-
-async fn handle_ocr_mode(
-    app: &tauri::AppHandle,
-    image_path: &str,
-    settings: &AppSettings,
-    do_translate: bool
-) {
-    let ocr_res = perform_ocr(app, image_path, settings);
-    match ocr_res {
-        Ok(text) => {
-            if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                let _ = clipboard.set_text(text.clone());
+        let recently_modified = meta.modified().ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age.as_secs() < CACHE_IN_USE_SECS);
+        if referenced.contains(&path) || recently_modified {
+            report.kept_files += 1;
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                report.removed_files += 1;
+                report.freed_bytes += meta.len();
             }
-            if !do_translate {
-                send_notification(app, "✅ 文字识别成功", "内容已复制到剪贴板。");
-                cache_result(app, Some(text), None, image_path.to_string());
-            } else {
-                let translator = translator::get_translator(app);
-                let trans_res = translator.translate(&text, &settings.target_lang).await;
-                match trans_res {
-                    Ok(trans_text) => {
-                        if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                            let _ = clipboard.set_text(trans_text.clone());
-                        }
-                        send_notification(app, "✅ 翻译完成", "译文已复制。按 Win+V 查看原文。");
-                        cache_result(app, Some(text), Some(trans_text), image_path.to_string());
-                    },
-                    Err(e) => {
-                        let err_msg = if e.contains("找不到翻译引擎") { "未安装翻译引擎，请在设置中下载".to_string() } else { format!("OCR成功但翻译出错: {}", e) };
-                        send_notification(app, "⚠️ 翻译失败", &err_msg);
-                        cache_result(app, Some(text), Some(err_msg), image_path.to_string());
-                    }
-                }
+            Err(e) => {
+                eprintln!("[COMMANDS] 删除缓存文件 {:?} 失败: {}", path, e);
+                report.kept_files += 1;
             }
-        },
-        Err(e) => {
-            send_notification(app, "❌ 识别失败", &format!("{}", e));
-            cache_result(app, None, None, image_path.to_string());
         }
     }
+    Ok(report)
+}
+
+// 隐藏加载窗口并释放截图锁的辅助函数
+pub(crate) fn hide_loading_and_release_lock(app: &tauri::AppHandle) {
+    if let Some(loading_window) = app.get_window("loading") {
+        let _ = loading_window.hide();
+    }
+    release_lock(app);
 }
 
 fn release_lock(app: &tauri::AppHandle) {
@@ -480,98 +495,104 @@ fn release_lock(app: &tauri::AppHandle) {
     state.is_capturing.store(false, Ordering::SeqCst);
 }
 
-fn cache_result(app: &tauri::AppHandle, original: Option<String>, translated: Option<String>, path: String) {
+pub(crate) fn cache_result(app: &tauri::AppHandle, ocr: Option<OcrOutput>, translated: Option<String>, path: String) {
     let state: State<AppState> = app.state();
     let mut cache = state.last_ocr_result.lock().unwrap();
     *cache = Some(LastOcrResult {
-        original_text: original,
+        original_text: ocr.as_ref().map(|o| o.text.clone()),
         translated_text: translated,
         image_path: path,
+        confidence: ocr.as_ref().map(|o| o.confidence),
+        blocks: ocr.map(|o| o.blocks).unwrap_or_default(),
     });
 }
 
-fn send_notification(app: &tauri::AppHandle, title: &str, body: &str) {
+pub(crate) fn send_notification(app: &tauri::AppHandle, title: &str, body: &str) {
     let _ = Notification::new(&app.config().tauri.bundle.identifier).title(title).body(body).show();
 }
 
-fn perform_ocr(app: &tauri::AppHandle, image_path_str: &str, settings: &AppSettings) -> Result<String, String> {
+/// 执行 OCR，并在整体置信度低于阈值时换用其它预处理方式重试，保留效果更好的结果。
+pub(crate) fn perform_ocr(app: &tauri::AppHandle, image_path_str: &str, settings: &AppSettings) -> Result<OcrOutput, String> {
     println!("[OCR] 开始执行 OCR 流程...");
     println!("[OCR] 待识别图片路径: {}", image_path_str);
-
-    let ocr_exe_path = app.path_resolver().app_local_data_dir()
-        .ok_or_else(|| "无法获取本地数据目录".to_string())?
-        .join(OCR_DIR_NAME)
-        .join(OCR_EXE_NAME);
-
-    println!("[OCR] 预期的 OCR 执行文件路径: {:?}", ocr_exe_path);
-
-    if !ocr_exe_path.exists() {
-        let err_msg = "未找到OCR引擎，请在设置页面下载。".to_string();
-        println!("[OCR] 错误: {}", err_msg);
-        return Err(err_msg);
-    }
-    println!("[OCR] OCR 执行文件存在, 准备调用.");
-
-    let ocr_dir = ocr_exe_path.parent().ok_or("无法获取OCR目录")?;
-    println!("[OCR] OCR 工作目录: {:?}", ocr_dir);
-
-    #[cfg(windows)] const CREATE_NO_WINDOW: u32 = 0x08000000;
-    let mut command = StdCommand::new(&ocr_exe_path);
-    let arg = format!("--image_path={}", image_path_str);
-    command.args(&[arg.clone()]).current_dir(&ocr_dir);
-    #[cfg(windows)] command.creation_flags(CREATE_NO_WINDOW);
-
-    println!("[OCR] 准备执行命令: {:?} with arg: '{}'", ocr_exe_path, arg);
-
-    let ocr_output = command.output().map_err(|e| {
-        let err_msg = format!("执行OCR进程失败: {}", e);
+    let separator = if settings.preserve_line_breaks { "\n" } else { " " };
+    let threshold = settings.low_confidence_threshold;
+    let engine = ocr::get_ocr_engine(app, settings).map_err(|err_msg| {
         println!("[OCR] 错误: {}", err_msg);
         err_msg
     })?;
+    if settings.ocr_engine != ocr::ENGINE_TESSERACT {
+        engine::mark_used(app, EngineId::Ocr);
+    }
 
-    println!("[OCR] 进程执行完毕. Status: {:?}", ocr_output.status);
+    // 首次识别失败（例如未识别到文字）时同样尝试备选预处理，全部失败则返回首次的错误
+    let first = run_ocr_pass(engine.as_ref(), image_path_str, &settings.ocr_preprocess, separator);
+    let (mut best, first_error) = match first {
+        Ok(output) => {
+            println!("[OCR] 首次识别整体置信度: {:.3}", output.confidence);
+            (Some(output), None)
+        }
+        Err(e) => (None, Some(e)),
+    };
 
-    if !ocr_output.status.success() {
-        let stderr = String::from_utf8_lossy(&ocr_output.stderr).into_owned();
-        let err_msg = format!("OCR进程返回错误: {}", stderr);
-        println!("[OCR] 错误: {}", err_msg);
-        println!("[OCR] Stderr (raw bytes): {:?}", &ocr_output.stderr);
-        return Err(err_msg);
+    for (index, options) in crate::preprocess::retry_options(&settings.ocr_preprocess).iter().enumerate() {
+        if best.as_ref().is_some_and(|b| b.confidence >= threshold) {
+            break;
+        }
+        println!("[OCR] 置信度低于阈值 {:.2}，使用备选预处理 #{} 重试...", threshold, index + 1);
+        match run_ocr_pass(engine.as_ref(), image_path_str, options, separator) {
+            Ok(retry) => {
+                println!("[OCR] 重试 #{} 整体置信度: {:.3}", index + 1, retry.confidence);
+                best = Some(match best {
+                    Some(current) => current.better(retry),
+                    None => retry,
+                });
+            }
+            Err(e) => println!("[OCR] 重试 #{} 失败: {}", index + 1, e),
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&ocr_output.stdout).into_owned();
-    println!("[OCR] Stdout (decoded): '{}'", stdout);
-    println!("[OCR] Stdout (raw bytes): {:?}", &ocr_output.stdout);
-
-    let json_start = stdout.lines().find(|line| line.starts_with('{')).unwrap_or("{}");
-    println!("[OCR] 提取到的 JSON 字符串: '{}'", json_start);
+    let mut best = match best {
+        Some(best) => best,
+        None => return Err(first_error.unwrap_or_else(|| "未识别到文字".to_string())),
+    };
+    best.mark_low_confidence(threshold);
+    if best.text.trim().is_empty() {
+        println!("[OCR] 警告: 未识别到任何文字.");
+        Err("未识别到文字".to_string())
+    } else {
+        println!("[OCR] 识别成功, 置信度 {:.3}, 文本内容: '{}'", best.confidence, best.text);
+        Ok(best)
+    }
+}
 
-    let ocr_value: serde_json::Value = serde_json::from_str(json_start).map_err(|e| {
-        let err_msg = format!("解析OCR结果JSON失败: {}", e);
-        println!("[OCR] 错误: {}", err_msg);
-        err_msg
-    })?;
-    println!("[OCR] 解析到的 JSON 值: {}", serde_json::to_string_pretty(&ocr_value).unwrap_or_default());
-
-    if ocr_value["code"].as_i64().unwrap_or(0) == 100 {
-        let separator = if settings.preserve_line_breaks { "\n" } else { " " };
-        let text = ocr_value["data"].as_array().unwrap_or(&vec![]).iter()
-            .filter_map(|item| item["text"].as_str()).collect::<Vec<_>>().join(separator);
-        if text.trim().is_empty() {
-            println!("[OCR] 警告: 未识别到任何文字.");
-            Err("未识别到文字".to_string())
-        } else {
-            println!("[OCR] 识别成功, 文本内容: '{}'", text);
-            Ok(text)
+/// 按给定的预处理选项执行一次 OCR 引擎调用
+fn run_ocr_pass(
+    engine: &dyn OcrEngine,
+    image_path_str: &str,
+    preprocess_options: &PreprocessOptions,
+    separator: &str,
+) -> Result<OcrOutput, String> {
+    // 识别前先对图片做预处理，失败时退回使用原图
+    let preprocessed_path = match crate::preprocess::prepare_ocr_input(Path::new(image_path_str), preprocess_options) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("[OCR] 警告: 图片预处理失败，使用原图识别: {}", e);
+            None
         }
-    } else {
-        let err_msg = ocr_value["data"].as_str().unwrap_or("未知OCR错误").to_string();
-        println!("[OCR] 错误: OCR 引擎返回错误码: {}", err_msg);
-        Err(err_msg)
+    };
+    let ocr_input = preprocessed_path.clone().unwrap_or_else(|| PathBuf::from(image_path_str));
+    println!("[OCR] 使用 {} 识别图片: {:?}", engine.name(), ocr_input);
+
+    let result = engine.recognize(&ocr_input, separator);
+    // 预处理生成的临时图片只用于本次识别
+    if let Some(path) = &preprocessed_path {
+        let _ = fs::remove_file(path);
     }
+    result
 }
 
-fn create_and_show_image_viewer_window(app: &tauri::AppHandle, payload: ImageViewerPayload) {
+pub(crate) fn create_and_show_image_viewer_window(app: &tauri::AppHandle, payload: ImageViewerPayload) {
     let handle = app.clone();
     let handle_for_closure = handle.clone();
     // 确保窗口操作在主线程上执行
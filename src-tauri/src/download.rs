@@ -0,0 +1,687 @@
+// --- 文件: src-tauri/src/download.rs ---
+
+//! 引擎包的下载与完整性校验
+//!
+//! 下载先写入 `<文件名>.part`，连接中断后通过 HTTP `Range` 请求从已下载的位置继续，
+//! 并按指数退避自动重试；全部完成后才重命名为目标文件。
+//! `.part` 旁边的 `<文件名>.part.json` 记录它来自哪个地址、哪个版本以及服务器返回的 ETag / Last-Modified，
+//! 版本不同时丢弃旧的 `.part`；续传时通过 `If-Range` 确认服务器上的文件没有变化，变化时服务器会返回完整文件。
+//! 提供多个下载地址（镜像）时，当前地址重试耗尽或返回不可重试的错误后换用下一个地址重新下载：
+//! 各镜像上的文件未必相同，`.part` 不会跨地址续传（已完整下载且哈希一致时直接使用）。
+//! 写入磁盘失败等本地错误换用其它地址也无法解决，直接停止。
+//! 解压前按引擎清单中的 `size` / `sha256` 校验下载得到的文件，
+//! 防止把被截断或被篡改的引擎包解压到应用数据目录。`sha256` 是必需的，清单未提供时拒绝安装；
+//! `size` 可选，未提供时只校验哈希。
+
+use futures_util::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::engine::ManifestAsset;
+
+// --- 常量定义 ---
+// 计算哈希时每次读取的字节数
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+// 连续失败（期间没有任何新数据）达到此次数后放弃
+const MAX_ATTEMPTS: u32 = 5;
+// 重试等待的初始秒数，每次失败翻倍
+const RETRY_BASE_DELAY_SECS: u64 = 1;
+const RETRY_MAX_DELAY_SECS: u64 = 30;
+// 超过此时间没有收到任何数据，视为连接已断开
+const STALL_TIMEOUT_SECS: u64 = 30;
+
+/// 单次请求的失败原因
+enum AttemptError {
+    // 网络中断、超时、服务器 5xx 等，可以续传重试
+    Retryable(String),
+    // 404 等，该地址无法提供文件，可以换用其它地址
+    Unavailable(String),
+    // 磁盘写入失败等本地错误，换用其它地址也无法解决
+    Fatal(String),
+    // 用户取消
+    Cancelled,
+}
+
+// 取消下载时返回的错误信息
+pub const CANCELLED_MESSAGE: &str = "下载已取消";
+
+/// 未完成下载的临时文件路径
+pub fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// `.part` 文件的来源记录，与 `.part` 一起保存
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+struct PartMeta {
+    url: String,
+    version: String,
+    // 服务器返回的校验标识，续传时放在 If-Range 中
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+}
+
+impl PartMeta {
+    fn path(part: &Path) -> PathBuf {
+        let mut name = part.file_name().unwrap_or_default().to_os_string();
+        name.push(".json");
+        part.with_file_name(name)
+    }
+
+    fn load(part: &Path) -> Option<Self> {
+        let content = fs::read_to_string(Self::path(part)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, part: &Path) -> Result<(), String> {
+        let content = serde_json::to_string(self).map_err(|e| format!("序列化下载记录失败: {}", e))?;
+        fs::write(Self::path(part), content).map_err(|e| format!("写入下载记录失败: {}", e))
+    }
+
+    /// 续传时使用的 If-Range 值。弱 ETag 不能用于 If-Range，此时改用 Last-Modified。
+    fn if_range(&self) -> Option<&str> {
+        self.etag.as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+
+    /// 记录响应中的 ETag / Last-Modified
+    fn update_validators(&mut self, headers: &HeaderMap) {
+        let header = |name: HeaderName| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        self.etag = header(ETAG);
+        self.last_modified = header(LAST_MODIFIED);
+    }
+}
+
+/// 删除 `.part` 及其来源记录
+fn remove_part(part: &Path) {
+    let _ = fs::remove_file(part);
+    let _ = fs::remove_file(PartMeta::path(part));
+}
+
+/// 依次从 `urls` 下载同一个文件到 `dest`，支持断点续传与自动重试。
+///
+/// `client` 由 `network::build_client` 按代理等网络设置创建。
+/// 换用地址时丢弃从其它地址下载的 `.part`，除非它已经完整且与 `asset` 的大小和哈希一致。
+/// `version` 标识要下载的文件版本，已有的 `.part` 属于其它版本或来源不明时丢弃重新下载。
+/// `asset` 来自引擎清单，其中的大小在服务器未返回文件大小时用于显示进度。
+/// `on_progress(已下载字节数, 总字节数)` 中的已下载字节数包含之前下载过的部分，总字节数未知时为 0。
+/// `cancel` 变为 true 时立即中止，删除临时文件并返回 `CANCELLED_MESSAGE`。
+/// 返回最终文件的字节数。
+pub async fn download_file(
+    client: &reqwest::Client,
+    urls: &[String],
+    dest: &Path,
+    version: &str,
+    asset: &ManifestAsset,
+    mut cancel: watch::Receiver<bool>,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<u64, String> {
+    let part = part_path(dest);
+    let mut meta = match PartMeta::load(&part) {
+        Some(meta) if meta.version == version && urls.contains(&meta.url) => meta,
+        _ => {
+            if part.exists() {
+                println!("[DOWNLOAD] 临时文件属于其它版本或来源不明，删除后重新下载: {:?}", part);
+            }
+            remove_part(&part);
+            PartMeta { version: version.to_string(), ..PartMeta::default() }
+        }
+    };
+    let mut last_error = "没有可用的下载地址".to_string();
+    for (index, url) in urls.iter().enumerate() {
+        if index > 0 {
+            println!("[DOWNLOAD] 换用下一个下载地址 ({}/{}): {}", index + 1, urls.len(), url);
+        }
+        if meta.url != *url && part.exists() {
+            if is_verified(&part, asset).await {
+                println!("[DOWNLOAD] 临时文件已完整且校验通过，不再重新下载。");
+                return finish_download(&part, dest);
+            }
+            println!("[DOWNLOAD] 临时文件来自其它地址 ({})，不跨地址续传，重新下载。", meta.url);
+            remove_part(&part);
+        }
+        match download_from(client, url, &part, &mut meta, asset.size, &mut cancel, &mut on_progress).await {
+            Ok(_) => return finish_download(&part, dest),
+            Err(AttemptError::Cancelled) => return Err(cancel_download_file(&part)),
+            Err(AttemptError::Fatal(e)) => {
+                eprintln!("[DOWNLOAD] 下载失败: {}", e);
+                return Err(e);
+            }
+            Err(AttemptError::Unavailable(e)) | Err(AttemptError::Retryable(e)) => {
+                eprintln!("[DOWNLOAD] 从 {} 下载失败: {}", url, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(match urls.len() {
+        0 | 1 => last_error,
+        n => format!("{} 个下载地址均失败，最后的错误: {}", n, last_error),
+    })
+}
+
+/// 把下载完成的 `.part` 重命名为目标文件，返回文件大小
+fn finish_download(part: &Path, dest: &Path) -> Result<u64, String> {
+    fs::rename(part, dest).map_err(|e| format!("保存下载文件失败: {}", e))?;
+    let _ = fs::remove_file(PartMeta::path(part));
+    let size = fs::metadata(dest).map(|m| m.len()).unwrap_or(0);
+    println!("[DOWNLOAD] 下载完成: {:?} ({} bytes)", dest, size);
+    Ok(size)
+}
+
+/// `.part` 是否已完整，且大小与哈希和清单一致。清单未提供大小时不计算哈希，直接视为不一致。
+async fn is_verified(part: &Path, asset: &ManifestAsset) -> bool {
+    let size = fs::metadata(part).map(|m| m.len()).ok();
+    if asset.size.is_none() || size != asset.size {
+        return false;
+    }
+    let (path, asset) = (part.to_path_buf(), asset.clone());
+    tokio::task::spawn_blocking(move || verify_archive(&path, &asset).is_ok())
+        .await
+        .unwrap_or(false)
+}
+
+/// 从单个地址下载到 `.part` 文件，连接中断时续传重试。重试耗尽时返回 `Retryable`，调用方可换用其它地址。
+async fn download_from(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    meta: &mut PartMeta,
+    expected_size: Option<u64>,
+    cancel: &mut watch::Receiver<bool>,
+    on_progress: &mut impl FnMut(u64, u64),
+) -> Result<u64, AttemptError> {
+    let mut failures = 0;
+    loop {
+        let before = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        let result = tokio::select! {
+            result = download_attempt(client, url, part, meta, expected_size, on_progress) => result,
+            _ = cancelled(cancel) => Err(AttemptError::Cancelled),
+        };
+        match result {
+            Err(AttemptError::Retryable(e)) => {
+                let after = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+                // 本次有新数据写入时重新计数，只有连续无进展的失败才会耗尽重试次数
+                failures = if after > before { 1 } else { failures + 1 };
+                if failures >= MAX_ATTEMPTS {
+                    return Err(AttemptError::Retryable(format!("下载失败，已重试 {} 次: {}", MAX_ATTEMPTS, e)));
+                }
+                let delay = (RETRY_BASE_DELAY_SECS << (failures - 1)).min(RETRY_MAX_DELAY_SECS);
+                eprintln!("[DOWNLOAD] 下载中断 ({})，已下载 {} bytes，{} 秒后续传...", e, after, delay);
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(delay)) => {}
+                    _ = cancelled(cancel) => return Err(AttemptError::Cancelled),
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// 等待取消信号。发送端已被丢弃时永远不会完成。
+async fn cancelled(cancel: &mut watch::Receiver<bool>) {
+    if cancel.wait_for(|&c| c).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// 取消时删除临时文件，返回取消的错误信息
+fn cancel_download_file(part: &Path) -> String {
+    println!("[DOWNLOAD] 下载已取消，删除临时文件: {:?}", part);
+    remove_part(part);
+    CANCELLED_MESSAGE.to_string()
+}
+
+/// 发起一次请求，从 `.part` 文件的末尾继续下载。完整下载后返回文件大小。
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    meta: &mut PartMeta,
+    expected_size: Option<u64>,
+    on_progress: &mut impl FnMut(u64, u64),
+) -> Result<u64, AttemptError> {
+    let mut offset = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+    if expected_size.is_some_and(|size| offset > size) {
+        println!("[DOWNLOAD] 临时文件大于预期大小，重新下载。");
+        offset = 0;
+    }
+
+    let mut request = client.get(url);
+    if offset > 0 {
+        println!("[DOWNLOAD] 从 {} bytes 处续传: {}", offset, url);
+        request = request.header(RANGE, format!("bytes={}-", offset));
+        // 服务器上的文件已变化时，服务器忽略 Range 返回完整文件
+        if let Some(validator) = meta.if_range() {
+            request = request.header(IF_RANGE, validator);
+        }
+    } else {
+        println!("[DOWNLOAD] 开始下载: {}", url);
+    }
+    let response = request.send().await
+        .map_err(|e| AttemptError::Retryable(format!("请求失败: {}", e)))?;
+
+    let status = response.status();
+    let total = match status {
+        StatusCode::PARTIAL_CONTENT => {
+            let (start, total) = response.headers().get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_content_range)
+                .ok_or_else(|| AttemptError::Retryable("服务器返回了无效的 Content-Range".to_string()))?;
+            if start != offset {
+                // 服务器返回的起点与请求不符，丢弃已下载内容重新开始
+                remove_part(part);
+                return Err(AttemptError::Retryable(format!("续传位置不匹配 (请求 {}，返回 {})", offset, start)));
+            }
+            total.or_else(|| response.content_length().map(|len| offset + len))
+        }
+        StatusCode::OK => {
+            if offset > 0 {
+                println!("[DOWNLOAD] 服务器不支持断点续传，从头下载。");
+                offset = 0;
+            }
+            response.content_length()
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // 临时文件可能已经是完整的（上次在重命名前中断）
+            let total = response.headers().get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("bytes */"))
+                .and_then(|v| v.parse::<u64>().ok());
+            if total == Some(offset) {
+                return Ok(offset);
+            }
+            remove_part(part);
+            return Err(AttemptError::Retryable("续传范围无效，重新下载".to_string()));
+        }
+        s if s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS || s == StatusCode::REQUEST_TIMEOUT => {
+            return Err(AttemptError::Retryable(format!("服务器返回 {}", s)));
+        }
+        s => return Err(AttemptError::Unavailable(format!("下载失败，服务器返回 {}", s))),
+    }
+    .or(expected_size)
+    .unwrap_or(0);
+
+    // 写入数据前先记录来源，之后中断时可以确认 `.part` 的内容来自哪里
+    if offset == 0 || meta.if_range().is_none() {
+        meta.update_validators(response.headers());
+    }
+    meta.url = url.to_string();
+    meta.save(part).map_err(AttemptError::Fatal)?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(part)
+        .map_err(|e| AttemptError::Fatal(format!("创建文件失败: {}", e)))?;
+
+    let mut downloaded = offset;
+    on_progress(downloaded, total);
+    let mut stream = response.bytes_stream();
+    loop {
+        let item = tokio::time::timeout(Duration::from_secs(STALL_TIMEOUT_SECS), stream.next()).await
+            .map_err(|_| AttemptError::Retryable(format!("{} 秒内未收到数据", STALL_TIMEOUT_SECS)))?;
+        let chunk = match item {
+            Some(chunk) => chunk.map_err(|e| AttemptError::Retryable(format!("下载流出错: {}", e)))?,
+            None => break,
+        };
+        file.write_all(&chunk).map_err(|e| AttemptError::Fatal(format!("写入文件块失败: {}", e)))?;
+        downloaded += chunk.len() as u64;
+        on_progress(downloaded, total);
+    }
+    file.flush().map_err(|e| AttemptError::Fatal(format!("写入文件失败: {}", e)))?;
+
+    // 连接提前关闭时，流可能正常结束但数据不完整
+    if total > 0 && downloaded < total {
+        return Err(AttemptError::Retryable(format!("连接提前关闭 ({} / {} bytes)", downloaded, total)));
+    }
+    Ok(downloaded)
+}
+
+/// 解析 `Content-Range: bytes <start>-<end>/<total>`，返回起始位置和总大小（`*` 表示未知）
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.trim().parse().ok()?;
+    Some((start, total.trim().parse().ok()))
+}
+
+/// 计算文件的 SHA-256，返回小写十六进制字符串
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("读取文件失败: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 清单中记录的 SHA-256。没有哈希的引擎包无法确认来源，不允许安装。
+pub fn expected_sha256(asset: &ManifestAsset) -> Result<&str, String> {
+    asset.sha256.as_deref().map(str::trim).filter(|h| !h.is_empty())
+        .ok_or_else(|| "引擎清单未提供该引擎包的 SHA-256，无法校验文件完整性，已拒绝安装。".to_string())
+}
+
+/// 按清单校验引擎包的大小与 SHA-256，失败时返回可直接展示给用户的错误信息。
+pub fn verify_archive(path: &Path, asset: &ManifestAsset) -> Result<(), String> {
+    let expected = expected_sha256(asset)?;
+    let actual_size = fs::metadata(path).map_err(|e| format!("读取下载文件失败: {}", e))?.len();
+    if let Some(expected_size) = asset.size {
+        if actual_size != expected_size {
+            return Err(format!(
+                "下载的文件大小不符 (应为 {} 字节，实际 {} 字节)，文件可能不完整，请重新下载。",
+                expected_size, actual_size,
+            ));
+        }
+    }
+
+    let actual = sha256_file(path)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(format!(
+            "下载的文件校验失败 (SHA-256 应为 {}，实际为 {})，文件可能已损坏或被篡改，请重新下载。",
+            expected, actual,
+        ));
+    }
+    println!("[DOWNLOAD] SHA-256 校验通过: {}", actual);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const ETAG_VALUE: &str = "\"v1\"";
+
+    /// 本地 HTTP 服务器，支持 Range / If-Range。`cut_after[n]` 为 Some 时，第 n 个请求只发送这么多字节后断开。
+    /// 返回服务器地址和收到的请求头（小写）。
+    async fn serve(body: Vec<u8>, cut_after: Vec<Option<usize>>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        tokio::spawn(async move {
+            for index in 0.. {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                log.lock().unwrap().push(request.clone());
+
+                if request.starts_with("get /missing") {
+                    let _ = socket.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+                    continue;
+                }
+
+                let header = |name: &str| request.lines().find_map(|line| line.strip_prefix(name)).map(str::trim).map(str::to_string);
+                let range_start = header("range: bytes=").and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+                let validator_matches = header("if-range:").map(|v| v == ETAG_VALUE).unwrap_or(true);
+                let start = match range_start {
+                    Some(start) if validator_matches => start,
+                    _ => 0,
+                };
+                let mut head = match range_start.is_some() && validator_matches {
+                    true => format!("HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n", start, body.len() - 1, body.len()),
+                    false => "HTTP/1.1 200 OK\r\n".to_string(),
+                };
+                head.push_str(&format!("Content-Length: {}\r\nETag: {}\r\nConnection: close\r\n\r\n", body.len() - start, ETAG_VALUE));
+                let mut payload = &body[start..];
+                if let Some(cut) = cut_after.get(index).copied().flatten() {
+                    payload = &payload[..cut.min(payload.len())];
+                }
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(payload).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (address, requests)
+    }
+
+    fn test_body() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    fn test_dest(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("download-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("engine.7z")
+    }
+
+    fn test_asset(body: &[u8]) -> ManifestAsset {
+        ManifestAsset {
+            url: String::new(),
+            size: Some(body.len() as u64),
+            sha256: Some(Sha256::digest(body).iter().map(|b| format!("{:02x}", b)).collect()),
+            archive_sha256: Default::default(),
+            exe_path: None,
+        }
+    }
+
+    async fn download(urls: &[String], dest: &Path, version: &str) -> Result<u64, String> {
+        let (_sender, cancel) = watch::channel(false);
+        download_file(&reqwest::Client::new(), urls, dest, version, &test_asset(&test_body()), cancel, |_, _| {}).await
+    }
+
+    #[test]
+    fn parses_content_range() {
+        assert_eq!(parse_content_range("bytes 100-199/1000"), Some((100, Some(1000))));
+        assert_eq!(parse_content_range("bytes 0-0/*"), Some((0, None)));
+        assert_eq!(parse_content_range("bytes  42 - 99 / 100"), Some((42, Some(100))));
+        assert_eq!(parse_content_range("bytes */1000"), None);
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+        assert_eq!(parse_content_range("bytes abc-1/2"), None);
+    }
+
+    #[test]
+    fn if_range_skips_weak_etags() {
+        let mut meta = PartMeta { etag: Some("W/\"weak\"".to_string()), ..PartMeta::default() };
+        assert_eq!(meta.if_range(), None);
+        meta.last_modified = Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string());
+        assert_eq!(meta.if_range(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        meta.etag = Some(ETAG_VALUE.to_string());
+        assert_eq!(meta.if_range(), Some(ETAG_VALUE));
+    }
+
+    #[test]
+    fn verifies_size_and_hash() {
+        let body = test_body();
+        let path = test_dest("verify");
+        fs::write(&path, &body).unwrap();
+        let mut asset = test_asset(&body);
+        assert!(verify_archive(&path, &asset).is_ok());
+
+        // 清单中的哈希是大写时同样通过
+        asset.sha256 = asset.sha256.map(|h| h.to_uppercase());
+        assert!(verify_archive(&path, &asset).is_ok());
+        // 没有记录大小时只校验哈希
+        asset.size = None;
+        assert!(verify_archive(&path, &asset).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_size_mismatch() {
+        let body = test_body();
+        let path = test_dest("verify-size");
+        fs::write(&path, &body[..body.len() - 1]).unwrap();
+        let error = verify_archive(&path, &test_asset(&body)).unwrap_err();
+        assert!(error.contains("大小不符"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_hash_mismatch() {
+        let mut body = test_body();
+        let asset = test_asset(&body);
+        body[0] ^= 0xff;
+        let path = test_dest("verify-hash");
+        fs::write(&path, &body).unwrap();
+        let error = verify_archive(&path, &asset).unwrap_err();
+        assert!(error.contains("校验失败"), "{}", error);
+    }
+
+    #[test]
+    fn refuses_an_asset_without_a_hash() {
+        let body = test_body();
+        let path = test_dest("verify-missing");
+        fs::write(&path, &body).unwrap();
+        for sha256 in [None, Some(String::new()), Some("  ".to_string())] {
+            let asset = ManifestAsset { sha256, ..test_asset(&body) };
+            let error = verify_archive(&path, &asset).unwrap_err();
+            assert!(error.contains("未提供该引擎包的 SHA-256"), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_connection_drops() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![Some(30_000)]).await;
+        let dest = test_dest("resume");
+
+        let size = download(&[format!("{}/engine.7z", address)], &dest, "1.0").await.unwrap();
+        assert_eq!(size, body.len() as u64);
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert!(!part_path(&dest).exists());
+        assert!(!PartMeta::path(&part_path(&dest)).exists());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].contains("range:"));
+        assert!(requests[1].contains("range: bytes=30000-"));
+        assert!(requests[1].contains(&format!("if-range: {}", ETAG_VALUE)));
+    }
+
+    #[tokio::test]
+    async fn restarts_when_the_file_changed_on_the_server() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![]).await;
+        let url = format!("{}/engine.7z", address);
+        let dest = test_dest("changed");
+        let part = part_path(&dest);
+        fs::write(&part, vec![0xAA; 500]).unwrap();
+        PartMeta { url: url.clone(), version: "1.0".to_string(), etag: Some("\"old\"".to_string()), last_modified: None }
+            .save(&part)
+            .unwrap();
+
+        download(&[url], &dest, "1.0").await.unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        // 带着旧的 ETag 续传，服务器返回了完整文件
+        assert!(requests.lock().unwrap()[0].contains("if-range: \"old\""));
+    }
+
+    #[tokio::test]
+    async fn discards_a_part_file_from_another_version() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![]).await;
+        let url = format!("{}/engine.7z", address);
+        let dest = test_dest("version");
+        let part = part_path(&dest);
+        fs::write(&part, &body[..500]).unwrap();
+        PartMeta { url: url.clone(), version: "0.9".to_string(), etag: Some(ETAG_VALUE.to_string()), last_modified: None }
+            .save(&part)
+            .unwrap();
+
+        download(&[url], &dest, "1.0").await.unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert!(!requests.lock().unwrap()[0].contains("range:"));
+    }
+
+    #[tokio::test]
+    async fn discards_a_part_file_without_a_record() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![]).await;
+        let dest = test_dest("no-record");
+        fs::write(part_path(&dest), &body[..500]).unwrap();
+
+        download(&[format!("{}/engine.7z", address)], &dest, "1.0").await.unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert!(!requests.lock().unwrap()[0].contains("range:"));
+    }
+
+    #[tokio::test]
+    async fn moves_on_to_the_next_mirror_without_resuming() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![None, Some(30_000)]).await;
+        let dest = test_dest("mirror");
+        let urls = [format!("{}/missing/engine.7z", address), format!("{}/engine.7z", address)];
+
+        download(&urls, &dest, "1.0").await.unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("get /missing"));
+        assert!(requests[2].contains("range: bytes=30000-"));
+    }
+
+    #[tokio::test]
+    async fn does_not_resume_a_part_file_from_another_mirror() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![]).await;
+        let (mirror, origin) = (format!("{}/missing/engine.7z", address), format!("{}/engine.7z", address));
+        let dest = test_dest("other-mirror");
+        let part = part_path(&dest);
+        fs::write(&part, &body[..500]).unwrap();
+        PartMeta { url: mirror.clone(), version: "1.0".to_string(), etag: Some(ETAG_VALUE.to_string()), last_modified: None }
+            .save(&part)
+            .unwrap();
+
+        download(&[mirror, origin], &dest, "1.0").await.unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        // 镜像返回 404 后换用原始地址，从头下载
+        assert!(!requests.lock().unwrap()[1].contains("range:"));
+    }
+
+    #[tokio::test]
+    async fn uses_a_verified_part_file_from_another_mirror() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![]).await;
+        let (first, second) = (format!("{}/missing/engine.7z", address), format!("{}/engine.7z", address));
+        let dest = test_dest("verified");
+        let part = part_path(&dest);
+        fs::write(&part, &body).unwrap();
+        PartMeta { url: second.clone(), version: "1.0".to_string(), ..PartMeta::default() }
+            .save(&part)
+            .unwrap();
+
+        download(&[first, second], &dest, "1.0").await.unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        // 临时文件已完整且哈希一致，不再发起请求
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stops_on_local_errors() {
+        let body = test_body();
+        let (address, requests) = serve(body.clone(), vec![]).await;
+        // 目标目录不存在，无法创建临时文件
+        let dest = test_dest("local-error").join("missing-dir").join("engine.7z");
+        let urls = [format!("{}/engine.7z", address), format!("{}/mirror/engine.7z", address)];
+
+        let error = download(&urls, &dest, "1.0").await.unwrap_err();
+        assert!(error.contains("创建文件失败") || error.contains("写入下载记录失败"), "{}", error);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+}
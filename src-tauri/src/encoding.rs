@@ -0,0 +1,135 @@
+// --- 文件: src-tauri/src/encoding.rs ---
+
+//! 引擎进程输出的解码
+//!
+//! 引擎在不同平台、不同系统语言下输出的编码不一致：Linux / macOS 上基本是 UTF-8，
+//! 中文 Windows 上的控制台程序常以 GBK 输出，日文、繁体中文系统则分别是 Shift_JIS 和 Big5。
+//! 所有引擎进程的 stdout / stderr 都通过 `decode_engine_output` 解码，依次尝试：
+//! 合法的 UTF-8 → 带 BOM 的 UTF-16 → 设置中指定的本地代码页。
+
+use encoding_rs::{Encoding, BIG5, GB18030, GBK, SHIFT_JIS, UTF_16BE, UTF_16LE};
+
+// 设置中未指定本地代码页时的默认值
+pub const DEFAULT_LEGACY_ENCODING: &str = "gbk";
+
+/// 把设置中的代码页名称转换为编码器，未知名称按 GBK 处理
+pub fn legacy_encoding(label: &str) -> &'static Encoding {
+    match label.trim().to_ascii_lowercase().as_str() {
+        "shift_jis" | "sjis" => SHIFT_JIS,
+        "big5" => BIG5,
+        "gbk" | "gb2312" => GBK,
+        "gb18030" => GB18030,
+        other => {
+            println!("[ENCODING] 警告: 未知的代码页 '{}'，按 GBK 处理。", other);
+            GBK
+        }
+    }
+}
+
+/// 解码引擎输出的原始字节。`legacy_label` 为 UTF-8 / UTF-16 都不匹配时使用的本地代码页。
+pub fn decode_engine_output(bytes: &[u8], legacy_label: &str) -> String {
+    let decoded = decode_engine_output_detailed(bytes, legacy_label);
+    if decoded.had_errors {
+        println!("[ENCODING] 警告: 输出既不是 UTF-8 也不是合法的 {}，可能出现乱码。", decoded.encoding);
+    }
+    decoded.text
+}
+
+/// 解码结果及实际使用的编码，供引擎自检报告输出编码问题
+pub struct DecodedOutput {
+    pub text: String,
+    pub encoding: &'static str,
+    // 按本地代码页解码时遇到了非法字节
+    pub had_errors: bool,
+}
+
+/// 与 `decode_engine_output` 相同，同时返回实际使用的编码
+pub fn decode_engine_output_detailed(bytes: &[u8], legacy_label: &str) -> DecodedOutput {
+    // 1. 合法的 UTF-8（去掉可能存在的 BOM）
+    let without_bom = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if let Ok(text) = std::str::from_utf8(without_bom) {
+        return DecodedOutput { text: text.to_string(), encoding: "UTF-8", had_errors: false };
+    }
+
+    // 2. 带 BOM 的 UTF-16（Windows 上部分程序重定向输出时使用）
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        let text = UTF_16LE.decode_without_bom_handling(rest).0.into_owned();
+        return DecodedOutput { text, encoding: UTF_16LE.name(), had_errors: false };
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        let text = UTF_16BE.decode_without_bom_handling(rest).0.into_owned();
+        return DecodedOutput { text, encoding: UTF_16BE.name(), had_errors: false };
+    }
+
+    // 3. 本地代码页
+    let encoding = legacy_encoding(legacy_label);
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(bytes);
+    DecodedOutput { text: decoded.into_owned(), encoding: encoding.name(), had_errors }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        let mut bytes = if big_endian { vec![0xFE, 0xFF] } else { vec![0xFF, 0xFE] };
+        for unit in text.encode_utf16() {
+            let pair = if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() };
+            bytes.extend_from_slice(&pair);
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_utf8_with_and_without_bom() {
+        let decoded = decode_engine_output_detailed("识别结果 OK".as_bytes(), "gbk");
+        assert_eq!((decoded.text.as_str(), decoded.encoding, decoded.had_errors), ("识别结果 OK", "UTF-8", false));
+
+        let with_bom = [b"\xEF\xBB\xBF".as_slice(), "你好".as_bytes()].concat();
+        assert_eq!(decode_engine_output(&with_bom, "gbk"), "你好");
+    }
+
+    #[test]
+    fn decodes_utf16_with_bom() {
+        let decoded = decode_engine_output_detailed(&utf16("翻译 test", false), "gbk");
+        assert_eq!((decoded.text.as_str(), decoded.encoding), ("翻译 test", "UTF-16LE"));
+        let decoded = decode_engine_output_detailed(&utf16("翻译 test", true), "gbk");
+        assert_eq!((decoded.text.as_str(), decoded.encoding), ("翻译 test", "UTF-16BE"));
+    }
+
+    #[test]
+    fn falls_back_to_the_legacy_code_page() {
+        // "中文" 的 GBK 编码
+        let gbk = [0xD6, 0xD0, 0xCE, 0xC4];
+        let decoded = decode_engine_output_detailed(&gbk, "gbk");
+        assert_eq!((decoded.text.as_str(), decoded.encoding, decoded.had_errors), ("中文", "GBK", false));
+
+        // "日本" 的 Shift_JIS 编码
+        assert_eq!(decode_engine_output(&[0x93, 0xFA, 0x96, 0x7B], "shift_jis"), "日本");
+        // "中文" 的 Big5 编码
+        assert_eq!(decode_engine_output(&[0xA4, 0xA4, 0xA4, 0xE5], "big5"), "中文");
+    }
+
+    #[test]
+    fn reports_invalid_legacy_bytes() {
+        let decoded = decode_engine_output_detailed(&[0x41, 0xFF, 0x42], "gbk");
+        assert!(decoded.had_errors);
+        assert!(decoded.text.starts_with('A') && decoded.text.ends_with('B'));
+    }
+
+    #[test]
+    fn maps_code_page_labels() {
+        assert_eq!(legacy_encoding(" GBK ").name(), "GBK");
+        assert_eq!(legacy_encoding("gb2312").name(), "GBK");
+        assert_eq!(legacy_encoding("gb18030").name(), "gb18030");
+        assert_eq!(legacy_encoding("sjis").name(), "Shift_JIS");
+        assert_eq!(legacy_encoding("big5").name(), "Big5");
+        assert_eq!(legacy_encoding("unknown").name(), "GBK");
+    }
+
+    #[test]
+    fn gb18030_decodes_characters_outside_gbk() {
+        // U+1F600 在 GB18030 中的四字节编码
+        assert_eq!(decode_engine_output(&[0x94, 0x39, 0xFC, 0x36], "gb18030"), "😀");
+    }
+}
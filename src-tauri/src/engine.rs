@@ -0,0 +1,675 @@
+// --- 文件: src-tauri/src/engine.rs ---
+
+//! 本地引擎注册表
+//!
+//! 集中记录每个引擎在各平台上的可执行文件位置，
+//! 并负责按“用户自定义路径 → 安装记录 → 应用数据目录中的默认位置”的顺序解析可执行文件。
+//!
+//! 每个引擎安装在应用数据目录的 `engines/<引擎 id>` 下，上一个版本保留在 `engines/<引擎 id>.previous`，
+//! 用于回滚。旧版本程序直接把引擎解压在应用数据目录下，没有安装记录时仍在该位置查找。
+//!
+//! 各引擎的版本与下载地址由引擎清单 (`engines.json`) 描述。清单默认使用随程序打包的版本，
+//! 也可以在设置中指定本地文件或 URL，以便在不发布新版本程序的情况下更新引擎。
+//! 每次安装完成后在应用数据目录中写入 `installed.json`，记录已安装的引擎版本，供检查更新使用。
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::settings::AppSettings;
+
+/// 引擎标识
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineId {
+    Ocr,
+    Translator,
+}
+
+impl EngineId {
+    /// 与序列化名称一致，用于文件名和事件
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EngineId::Ocr => "ocr",
+            EngineId::Translator => "translator",
+        }
+    }
+}
+
+/// 引擎在某个平台上的发行文件
+pub struct PlatformBinary {
+    // 与 `std::env::consts::OS` 对应: windows / linux / macos
+    pub os: &'static str,
+    // 可执行文件相对引擎包根目录的默认路径（清单中未指定时使用）
+    pub exe_path: &'static str,
+}
+
+/// 引擎描述
+pub struct EngineSpec {
+    pub id: EngineId,
+    pub display_name: &'static str,
+    pub binaries: &'static [PlatformBinary],
+}
+
+// --- 引擎注册表 ---
+static ENGINES: &[EngineSpec] = &[
+    EngineSpec {
+        id: EngineId::Ocr,
+        display_name: "识别引擎 (RapidOCR)",
+        binaries: &[
+            PlatformBinary { os: "windows", exe_path: "RapidOCR-json_v0.2.0/RapidOCR-json.exe" },
+            PlatformBinary { os: "linux", exe_path: "RapidOCR-json_v0.2.0/RapidOCR-json" },
+            PlatformBinary { os: "macos", exe_path: "RapidOCR-json_v0.2.0/RapidOCR-json" },
+        ],
+    },
+    EngineSpec {
+        id: EngineId::Translator,
+        display_name: "翻译引擎 (LocalTranslator)",
+        binaries: &[
+            PlatformBinary { os: "windows", exe_path: "translate_engine.exe" },
+            PlatformBinary { os: "linux", exe_path: "translate_engine" },
+            PlatformBinary { os: "macos", exe_path: "translate_engine" },
+        ],
+    },
+];
+
+/// 用户自定义的引擎可执行文件路径，持久化在 `AppSettings.engine_paths` 中
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct EnginePaths {
+    pub ocr: Option<String>,
+    pub translator: Option<String>,
+    // Tesseract 由系统安装而不是本程序下载，为空时从 PATH 查找
+    pub tesseract: Option<String>,
+}
+
+impl EnginePaths {
+    pub fn get(&self, id: EngineId) -> Option<&str> {
+        let path = match id {
+            EngineId::Ocr => &self.ocr,
+            EngineId::Translator => &self.translator,
+        };
+        non_empty_path(path)
+    }
+
+    pub fn tesseract(&self) -> Option<&str> {
+        non_empty_path(&self.tesseract)
+    }
+}
+
+fn non_empty_path(path: &Option<String>) -> Option<&str> {
+    path.as_deref().map(str::trim).filter(|p| !p.is_empty())
+}
+
+// --- 查询函数 ---
+
+pub fn spec(id: EngineId) -> &'static EngineSpec {
+    ENGINES.iter().find(|e| e.id == id).expect("引擎注册表缺少条目")
+}
+
+/// 当前平台对应的发行文件
+pub fn current_binary(id: EngineId) -> Option<&'static PlatformBinary> {
+    spec(id).binaries.iter().find(|b| b.os == std::env::consts::OS)
+}
+
+/// 解析引擎可执行文件路径：优先使用设置中的自定义路径，其次是安装记录中的路径，
+/// 最后是旧版本程序解压到应用数据目录中的位置。
+///
+/// 只负责计算路径，不检查文件是否存在。
+pub fn resolve_exe(app: &AppHandle, settings: &AppSettings, id: EngineId) -> Result<PathBuf, String> {
+    if let Some(custom) = settings.engine_paths.get(id) {
+        return Ok(PathBuf::from(custom));
+    }
+    let local_data_dir = app.path_resolver().app_local_data_dir()
+        .ok_or_else(|| "无法获取本地数据目录".to_string())?;
+    if let Some(record) = read_installed(app).remove(&id) {
+        return Ok(local_data_dir.join(record.exe_path));
+    }
+    let binary = current_binary(id)
+        .ok_or_else(|| format!("{} 不支持当前平台 ({})", spec(id).display_name, std::env::consts::OS))?;
+    Ok(local_data_dir.join(binary.exe_path))
+}
+
+/// 解析引擎可执行文件路径，文件不存在时返回错误。
+pub fn find_exe(app: &AppHandle, settings: &AppSettings, id: EngineId) -> Result<PathBuf, String> {
+    let exe_path = resolve_exe(app, settings, id)?;
+    println!("[ENGINE] {} 可执行文件: {:?}, 是否存在={}", spec(id).display_name, exe_path, exe_path.exists());
+    if exe_path.is_file() {
+        Ok(exe_path)
+    } else if settings.engine_paths.get(id).is_some() {
+        Err(format!("自定义的{}路径不存在: {}", spec(id).display_name, exe_path.display()))
+    } else {
+        Err(format!("找不到{}，请在设置页面下载。", spec(id).display_name))
+    }
+}
+
+// --- 引擎清单 ---
+
+// 随程序打包的默认清单
+const BUNDLED_MANIFEST: &str = include_str!("../engines.json");
+// 从 URL 获取清单的超时
+const MANIFEST_FETCH_TIMEOUT_SECS: u64 = 15;
+// 安装记录文件名，位于应用数据目录
+const INSTALLED_RECORD_FILE: &str = "installed.json";
+// 引擎安装目录，位于应用数据目录
+pub const ENGINES_DIR: &str = "engines";
+// 使用记录文件名，位于应用数据目录
+const USAGE_RECORD_FILE: &str = "engine-usage.json";
+// 两次写入使用记录的最小间隔（秒），避免每次识别都写文件
+const USAGE_WRITE_INTERVAL_SECS: u64 = 60;
+
+/// 引擎清单：每个引擎的最新版本及各平台的发行包
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EngineManifest {
+    pub engines: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestEntry {
+    pub id: EngineId,
+    pub version: String,
+    // 键与 `std::env::consts::OS` 对应
+    #[serde(default)]
+    pub platforms: HashMap<String, ManifestAsset>,
+}
+
+/// 某个平台上的引擎发行包
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManifestAsset {
+    pub url: String,
+    // 发行包的字节数与 SHA-256（十六进制），用于校验下载结果
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    // 同一版本其它格式引擎包 (7z / zip / tar.gz) 的 SHA-256，用于校验离线安装的引擎包
+    #[serde(default)]
+    pub archive_sha256: HashMap<String, String>,
+    // 可执行文件相对引擎包根目录的路径，未指定时使用注册表中的默认路径
+    #[serde(default)]
+    pub exe_path: Option<String>,
+}
+
+impl EngineManifest {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|e| format!("解析引擎清单失败: {}", e))
+    }
+
+    pub fn entry(&self, id: EngineId) -> Result<&ManifestEntry, String> {
+        self.engines.iter().find(|e| e.id == id)
+            .ok_or_else(|| format!("引擎清单中没有{}", spec(id).display_name))
+    }
+
+    /// 当前平台的发行包。清单中没有该平台时返回可直接展示给用户的错误信息。
+    pub fn asset(&self, id: EngineId) -> Result<&ManifestAsset, String> {
+        self.entry(id)?.platforms.get(std::env::consts::OS)
+            .ok_or_else(|| format!(
+                "当前平台 ({}) 暂无可下载的{}，请自行安装后在设置中指定可执行文件路径。",
+                std::env::consts::OS, spec(id).display_name,
+            ))
+    }
+}
+
+impl ManifestAsset {
+    /// 可执行文件相对引擎包根目录的路径
+    pub fn exe_path(&self, id: EngineId) -> Result<String, String> {
+        if let Some(path) = &self.exe_path {
+            return Ok(path.clone());
+        }
+        current_binary(id)
+            .map(|b| b.exe_path.to_string())
+            .ok_or_else(|| format!("{} 不支持当前平台 ({})", spec(id).display_name, std::env::consts::OS))
+    }
+}
+
+/// 加载引擎清单：设置中指定了来源时从本地文件或 http(s) URL 读取，否则使用内置清单
+pub async fn load_manifest(settings: &AppSettings) -> Result<EngineManifest, String> {
+    let source = match settings.engine_manifest_url.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(source) => source,
+        None => return EngineManifest::parse(BUNDLED_MANIFEST),
+    };
+    println!("[ENGINE] 加载引擎清单: {}", source);
+
+    let content = if source.starts_with("http://") || source.starts_with("https://") {
+        let client = crate::network::build_client(settings, Some(std::time::Duration::from_secs(MANIFEST_FETCH_TIMEOUT_SECS)))?;
+        let response = client.get(source).send().await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("获取引擎清单失败: {}", e))?;
+        response.text().await.map_err(|e| format!("读取引擎清单失败: {}", e))?
+    } else {
+        let path = source.strip_prefix("file://").unwrap_or(source);
+        fs::read_to_string(path).map_err(|e| format!("读取引擎清单文件 {} 失败: {}", path, e))?
+    };
+    EngineManifest::parse(&content)
+}
+
+// --- 安装记录 ---
+
+/// 已安装引擎的版本记录，由下载安装流程在解压完成后写入
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstalledEngine {
+    pub version: String,
+    // 可执行文件相对应用数据目录的路径
+    pub exe_path: String,
+    // 安装时间 (Unix 秒)
+    pub installed_at: u64,
+    // 保留在 `.previous` 目录中、可以回滚的上一个版本
+    #[serde(default)]
+    pub previous: Option<PreviousEngine>,
+}
+
+/// 上一个版本的记录。`exe_path` 为回滚（目录换回原位）后的路径。
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreviousEngine {
+    pub version: String,
+    pub exe_path: String,
+}
+
+/// 引擎的安装目录 `engines/<id>`
+pub fn install_dir(app: &AppHandle, id: EngineId) -> Result<PathBuf, String> {
+    let local_data_dir = app.path_resolver().app_local_data_dir().ok_or("无法获取本地数据目录")?;
+    Ok(local_data_dir.join(ENGINES_DIR).join(id.as_str()))
+}
+
+/// 上一个版本的保留目录 `engines/<id>.previous`
+pub fn previous_dir(app: &AppHandle, id: EngineId) -> Result<PathBuf, String> {
+    let local_data_dir = app.path_resolver().app_local_data_dir().ok_or("无法获取本地数据目录")?;
+    Ok(local_data_dir.join(ENGINES_DIR).join(format!("{}.previous", id.as_str())))
+}
+
+/// 安装目录中可执行文件相对应用数据目录的路径，用于写入安装记录
+pub fn installed_exe_path(id: EngineId, exe_path: &str) -> String {
+    format!("{}/{}/{}", ENGINES_DIR, id.as_str(), exe_path.trim_start_matches(['/', '\\']))
+}
+
+fn installed_record_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver().app_local_data_dir().map(|d| d.join(INSTALLED_RECORD_FILE))
+}
+
+/// 读取安装记录。文件不存在或损坏时返回空表（视为未记录版本）。
+pub fn read_installed(app: &AppHandle) -> HashMap<EngineId, InstalledEngine> {
+    installed_record_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 覆盖写入全部安装记录
+pub fn write_installed(app: &AppHandle, records: &HashMap<EngineId, InstalledEngine>) -> Result<(), String> {
+    let path = installed_record_path(app).ok_or("无法获取本地数据目录")?;
+    let content = serde_json::to_string_pretty(records).map_err(|e| format!("序列化安装记录失败: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("写入安装记录失败: {}", e))
+}
+
+/// 写入（覆盖）某个引擎的安装记录。`keep_previous` 为 true 时，原记录作为可回滚的上一个版本保留。
+pub fn record_installed(app: &AppHandle, id: EngineId, version: &str, exe_path: &str, keep_previous: bool) -> Result<(), String> {
+    let mut records = read_installed(app);
+    let previous = records.remove(&id)
+        .filter(|_| keep_previous)
+        .map(|old| PreviousEngine { version: old.version, exe_path: old.exe_path });
+    records.insert(id, InstalledEngine {
+        version: version.to_string(),
+        exe_path: exe_path.to_string(),
+        installed_at: now_secs(),
+        previous,
+    });
+    write_installed(app, &records)?;
+    println!("[ENGINE] 已记录 {} 版本 {}", spec(id).display_name, version);
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// --- 使用记录与磁盘占用 ---
+
+fn usage_record_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver().app_local_data_dir().map(|d| d.join(USAGE_RECORD_FILE))
+}
+
+/// 读取各引擎最近一次使用的时间 (Unix 秒)
+fn read_usage(app: &AppHandle) -> HashMap<EngineId, u64> {
+    usage_record_path(app)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 记录引擎被使用，在实际调用引擎识别或翻译时调用。写入失败只记录日志。
+pub fn mark_used(app: &AppHandle, id: EngineId) {
+    let path = match usage_record_path(app) {
+        Some(path) => path,
+        None => return,
+    };
+    let mut usage = read_usage(app);
+    let now = now_secs();
+    if usage.get(&id).is_some_and(|&last| now.saturating_sub(last) < USAGE_WRITE_INTERVAL_SECS) {
+        return;
+    }
+    usage.insert(id, now);
+    let result = serde_json::to_string_pretty(&usage)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(&path, content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("[ENGINE] 写入使用记录失败: {}", e);
+    }
+}
+
+/// 应用数据目录中属于该引擎的文件和目录：安装目录、保留的上一个版本，
+/// 以及旧版本程序直接解压在应用数据目录下的位置（仅包含实际存在的路径）。
+///
+/// 旧版本的翻译引擎与其它文件混在应用数据目录根下，这里只能找到它的可执行文件。
+pub fn managed_paths(app: &AppHandle, id: EngineId) -> Vec<PathBuf> {
+    let local_data_dir = match app.path_resolver().app_local_data_dir() {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+    let engines_dir = local_data_dir.join(ENGINES_DIR);
+    let mut paths = vec![engines_dir.join(id.as_str()), engines_dir.join(format!("{}.previous", id.as_str()))];
+    if let Some(first) = current_binary(id).and_then(|b| Path::new(b.exe_path).components().next()) {
+        paths.push(local_data_dir.join(first));
+    }
+    paths.into_iter().filter(|p| p.exists()).collect()
+}
+
+/// 文件或目录占用的字节数（不跟随符号链接）
+pub fn disk_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|entry| disk_size(&entry.path())).sum())
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// 引擎的安装信息，供设置页面展示
+#[derive(Serialize, Debug, Clone)]
+pub struct EngineInfo {
+    pub id: EngineId,
+    pub display_name: &'static str,
+    pub installed: bool,
+    pub custom_path: bool,
+    // 没有安装记录（旧版本程序安装或自定义路径）时为 None
+    pub version: Option<String>,
+    // 可执行文件路径，未安装时为预期的位置
+    pub path: Option<String>,
+    // 应用数据目录中该引擎占用的字节数，包含保留用于回滚的上一个版本；不含自定义路径
+    pub size_on_disk: u64,
+    // 可以回滚到的版本
+    pub previous_version: Option<String>,
+    // 最近一次使用的时间 (Unix 秒)，从未使用过时为 None
+    pub last_used: Option<u64>,
+}
+
+/// 汇总引擎的安装信息。需要遍历安装目录，应在阻塞线程中调用。
+pub fn engine_info(app: &AppHandle, settings: &AppSettings, id: EngineId) -> EngineInfo {
+    let record = read_installed(app).remove(&id);
+    let path = resolve_exe(app, settings, id).ok();
+    EngineInfo {
+        id,
+        display_name: spec(id).display_name,
+        installed: path.as_ref().is_some_and(|p| p.is_file()),
+        custom_path: settings.engine_paths.get(id).is_some(),
+        version: record.as_ref().map(|r| r.version.clone()),
+        path: path.map(|p| p.to_string_lossy().into_owned()),
+        size_on_disk: managed_paths(app, id).iter().map(|p| disk_size(p)).sum(),
+        previous_version: record.and_then(|r| r.previous).map(|p| p.version),
+        last_used: read_usage(app).remove(&id),
+    }
+}
+
+/// 删除应用数据目录中该引擎的全部文件、安装记录与使用记录，返回释放的字节数。
+///
+/// 使用自定义路径的引擎由用户自行管理，其文件不会被删除。
+pub fn uninstall(app: &AppHandle, id: EngineId) -> Result<u64, String> {
+    let mut freed = 0;
+    for path in managed_paths(app, id) {
+        let size = disk_size(&path);
+        let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        result.map_err(|e| format!("删除 {} 失败（引擎可能正在运行）: {}", path.display(), e))?;
+        freed += size;
+    }
+
+    let mut records = read_installed(app);
+    if records.remove(&id).is_some() {
+        write_installed(app, &records)?;
+    }
+    let mut usage = read_usage(app);
+    if usage.remove(&id).is_some() {
+        if let (Some(path), Ok(content)) = (usage_record_path(app), serde_json::to_string_pretty(&usage)) {
+            let _ = fs::write(path, content);
+        }
+    }
+    println!("[ENGINE] 已卸载 {}，释放 {} bytes", spec(id).display_name, freed);
+    Ok(freed)
+}
+
+// --- 检查更新 ---
+
+/// 单个引擎的更新检查结果
+#[derive(Serialize, Debug, Clone)]
+pub struct EngineUpdateInfo {
+    pub id: EngineId,
+    pub display_name: &'static str,
+    pub installed: bool,
+    // 使用自定义路径的引擎由用户自行管理，不提示更新
+    pub custom_path: bool,
+    // 在有安装记录之前安装的引擎版本未知，为 None
+    pub installed_version: Option<String>,
+    pub latest_version: String,
+    pub update_available: bool,
+}
+
+/// 对比已安装的引擎与清单中的版本
+pub fn check_updates(app: &AppHandle, settings: &AppSettings, manifest: &EngineManifest) -> Vec<EngineUpdateInfo> {
+    let mut records = read_installed(app);
+    manifest.engines.iter().map(|entry| {
+        let id = entry.id;
+        let custom_path = settings.engine_paths.get(id).is_some();
+        let installed = find_exe(app, settings, id).is_ok();
+        update_info(entry, installed, custom_path, records.remove(&id).map(|r| r.version))
+    }).collect()
+}
+
+/// 根据清单条目与本机安装情况得出单个引擎的更新检查结果
+fn update_info(entry: &ManifestEntry, installed: bool, custom_path: bool, installed_version: Option<String>) -> EngineUpdateInfo {
+    let update_available = installed && !custom_path && entry.platforms.contains_key(std::env::consts::OS)
+        && installed_version.as_deref()
+            .map_or(true, |v| compare_versions(&entry.version, v) == Ordering::Greater);
+    EngineUpdateInfo {
+        id: entry.id,
+        display_name: spec(entry.id).display_name,
+        installed,
+        custom_path,
+        installed_version,
+        latest_version: entry.version.clone(),
+        update_available,
+    }
+}
+
+/// 按语义化版本规则比较两个版本号（忽略前缀 v 和 `+` 之后的构建信息）。
+///
+/// 主版本部分逐段比较，缺少的段视为 0，无法解析为数字的段按字符串比较。
+/// 主版本相同时，带预发布标记的版本（如 `1.2.0-beta.1`）低于正式版本，
+/// 两个预发布标记按 `.` 分段比较：数字段按数值比较且低于非数字段，前缀相同时段数少的较低。
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_core, a_pre) = split_version(a);
+    let (b_core, b_pre) = split_version(b);
+    let (a_parts, b_parts): (Vec<&str>, Vec<&str>) = (a_core.split('.').collect(), b_core.split('.').collect());
+    for i in 0..a_parts.len().max(b_parts.len()) {
+        let x = a_parts.get(i).copied().unwrap_or("0");
+        let y = b_parts.get(i).copied().unwrap_or("0");
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre), Some(b_pre)) => {
+            let (mut a_ids, mut b_ids) = (a_pre.split('.'), b_pre.split('.'));
+            loop {
+                let ordering = match (a_ids.next(), b_ids.next()) {
+                    (None, None) => return Ordering::Equal,
+                    (None, Some(_)) => return Ordering::Less,
+                    (Some(_), None) => return Ordering::Greater,
+                    (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                        (Ok(x), Ok(y)) => x.cmp(&y),
+                        (Ok(_), Err(_)) => Ordering::Less,
+                        (Err(_), Ok(_)) => Ordering::Greater,
+                        (Err(_), Err(_)) => x.cmp(y),
+                    },
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+/// 拆分出主版本部分与预发布标记
+fn split_version(version: &str) -> (&str, Option<&str>) {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let version = version.split_once('+').map_or(version, |(v, _)| v);
+    match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const TEST_MANIFEST: &str = r#"{
+        "engines": [
+            { "id": "ocr", "version": "1.3.0-beta.2", "platforms": {} },
+            { "id": "translator", "version": "0.3.0", "platforms": {
+                "windows": { "url": "https://example.com/t.7z" },
+                "linux": { "url": "https://example.com/t.tar.gz" },
+                "macos": { "url": "https://example.com/t.zip" }
+            } }
+        ]
+    }"#;
+
+    fn settings_with_manifest(source: String) -> AppSettings {
+        AppSettings { engine_manifest_url: Some(source), ..AppSettings::default() }
+    }
+
+    #[test]
+    fn compares_release_versions() {
+        assert_eq!(compare_versions("0.2.0", "0.10.0"), Ordering::Less);
+        assert_eq!(compare_versions("v1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.1", "V1.2"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0+build.5", "1.0.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn prerelease_sorts_before_release() {
+        assert_eq!(compare_versions("1.2.0-beta", "1.2.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0", "1.2.0-rc.1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2.1-alpha", "1.2.0"), Ordering::Greater);
+
+        // semver.org 给出的预发布版本顺序
+        let ordered = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0"];
+        for pair in ordered.windows(2) {
+            assert_eq!(compare_versions(pair[0], pair[1]), Ordering::Less, "{} < {}", pair[0], pair[1]);
+            assert_eq!(compare_versions(pair[1], pair[0]), Ordering::Greater, "{} > {}", pair[1], pair[0]);
+        }
+    }
+
+    #[test]
+    fn bundled_manifest_is_valid() {
+        let manifest = EngineManifest::parse(BUNDLED_MANIFEST).unwrap();
+        assert!(manifest.entry(EngineId::Ocr).is_ok());
+        assert!(manifest.entry(EngineId::Translator).is_ok());
+    }
+
+    #[test]
+    fn bundled_manifest_has_hashes_for_every_asset() {
+        // 没有 SHA-256 的引擎包会被拒绝安装，修改下载地址后需运行 script/update_engine_hashes.py
+        let manifest = EngineManifest::parse(BUNDLED_MANIFEST).unwrap();
+        for entry in &manifest.engines {
+            for (platform, asset) in &entry.platforms {
+                let sha256 = asset.sha256.as_deref().unwrap_or_default();
+                assert!(
+                    sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit()),
+                    "{:?} ({}) 缺少有效的 SHA-256: {:?}", entry.id, platform, asset.sha256,
+                );
+                assert!(asset.size.is_some(), "{:?} ({}) 缺少文件大小", entry.id, platform);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn loads_manifest_from_local_file() {
+        let path = std::env::temp_dir().join(format!("engine-manifest-test-{}.json", std::process::id()));
+        fs::write(&path, TEST_MANIFEST).unwrap();
+
+        let manifest = load_manifest(&settings_with_manifest(path.to_string_lossy().to_string())).await.unwrap();
+        assert_eq!(manifest.entry(EngineId::Translator).unwrap().version, "0.3.0");
+        let manifest = load_manifest(&settings_with_manifest(format!("file://{}", path.display()))).await.unwrap();
+        assert_eq!(manifest.entry(EngineId::Ocr).unwrap().version, "1.3.0-beta.2");
+        fs::remove_file(&path).unwrap();
+
+        let error = load_manifest(&settings_with_manifest(path.to_string_lossy().to_string())).await.unwrap_err();
+        assert!(error.contains("读取引擎清单文件"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn loads_manifest_from_http_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                TEST_MANIFEST.len(), TEST_MANIFEST,
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let manifest = load_manifest(&settings_with_manifest(format!("http://{}/engines.json", address))).await.unwrap();
+        assert_eq!(manifest.engines.len(), 2);
+        assert!(manifest.asset(EngineId::Translator).is_ok());
+        assert!(manifest.asset(EngineId::Ocr).is_err());
+    }
+
+    #[test]
+    fn update_check_compares_installed_version() {
+        let manifest = EngineManifest::parse(TEST_MANIFEST).unwrap();
+        let translator = manifest.entry(EngineId::Translator).unwrap();
+
+        assert!(update_info(translator, true, false, Some("0.2.0".to_string())).update_available);
+        assert!(!update_info(translator, true, false, Some("0.3.0".to_string())).update_available);
+        assert!(!update_info(translator, true, false, Some("0.4.0-rc.1".to_string())).update_available);
+        assert!(update_info(translator, true, false, Some("0.3.0-rc.1".to_string())).update_available);
+        // 版本未知的旧安装提示更新；未安装或使用自定义路径时不提示
+        assert!(update_info(translator, true, false, None).update_available);
+        assert!(!update_info(translator, false, false, None).update_available);
+        assert!(!update_info(translator, true, true, Some("0.1.0".to_string())).update_available);
+
+        // 当前平台没有发行包时不提示更新
+        let ocr = manifest.entry(EngineId::Ocr).unwrap();
+        let info = update_info(ocr, true, false, Some("1.2.0".to_string()));
+        assert_eq!((info.latest_version.as_str(), info.update_available), ("1.3.0-beta.2", false));
+    }
+}
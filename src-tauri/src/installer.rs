@@ -0,0 +1,523 @@
+// --- 文件: src-tauri/src/installer.rs ---
+
+//! 引擎安装器
+//!
+//! 所有引擎共用同一套安装流程：按引擎清单下载（或使用本地提供的引擎包）→ 校验 → 解压 → 写入安装记录，
+//! 并通过统一的 `engine-download-progress` 事件报告进度（payload 中带有引擎 id）。
+//! 新增引擎只需在 `engine.rs` 的注册表和 `engines.json` 中添加条目。
+//! 解压先在暂存目录中完成，确认可执行文件存在后才整体换入安装目录；上一个版本保留用于 `rollback_engine` 回滚。
+//!
+//! 同一引擎同时只允许一个下载任务，正在下载的任务登记在 `AppState.engine_downloads` 中，
+//! 可以通过 `cancel_download` 取消。卸载与回滚同样需要登记，避免与正在进行的安装冲突。
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State, Window};
+use tokio::sync::watch;
+
+use crate::archive;
+use crate::download;
+use crate::engine::{self, EngineId, ManifestAsset, ManifestEntry};
+use crate::network;
+use crate::translator;
+use crate::settings::AppState;
+use crate::worker;
+
+// 前端监听的进度事件名
+pub const PROGRESS_EVENT: &str = "engine-download-progress";
+
+// --- 事件 Payload 定义 ---
+#[derive(Clone, Serialize)]
+pub struct DownloadProgressPayload {
+    pub engine_id: EngineId,
+    pub progress: u64,
+    pub total: u64,
+    // downloading / verifying / extracting / completed / cancelled
+    pub status: String,
+}
+
+/// 负责单个引擎的下载与安装
+pub struct EngineInstaller {
+    app: AppHandle,
+    window: Window,
+    id: EngineId,
+    local_data_dir: PathBuf,
+    cancel: watch::Receiver<bool>,
+}
+
+impl EngineInstaller {
+    pub fn new(app: &AppHandle, id: EngineId, cancel: watch::Receiver<bool>) -> Result<Self, String> {
+        let window = app.get_window("main").ok_or("找不到主窗口")?;
+        let local_data_dir = app.path_resolver().app_local_data_dir().ok_or("无法获取本地数据目录")?;
+        Ok(Self { app: app.clone(), window, id, local_data_dir, cancel })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+
+    fn name(&self) -> &'static str {
+        engine::spec(self.id).display_name
+    }
+
+    fn emit(&self, status: &str, progress: u64, total: u64) {
+        emit_progress(&self.window, self.id, status, progress, total);
+    }
+
+    /// 下载的引擎包保存位置
+    fn archive_path(&self) -> PathBuf {
+        self.local_data_dir.join(format!("{}.7z", self.id.as_str()))
+    }
+
+    /// 在线安装：下载引擎包后解压安装
+    pub async fn install(&self) -> Result<(), String> {
+        println!("[INSTALLER] 开始安装{}...", self.name());
+        self.prepare_dir()?;
+        let settings = self.app.state::<AppState>().settings.lock().unwrap().clone();
+        let manifest = engine::load_manifest(&settings).await?;
+        let entry = manifest.entry(self.id)?;
+        let asset = manifest.asset(self.id)?;
+        // 没有哈希的引擎包下载后也无法安装，提前拒绝
+        download::expected_sha256(asset)?;
+        let archive_path = self.archive_path();
+
+        // 1. 下载文件（断点续传，失败自动重试，按设置依次尝试镜像）
+        println!("[INSTALLER] 正在从 URL 下载 {} v{}: {}", self.name(), entry.version, asset.url);
+        let client = network::build_client(&settings, None)?;
+        let urls = network::candidate_urls(&settings, &asset.url);
+        let window = self.window.clone();
+        let id = self.id;
+        download::download_file(&client, &urls, &archive_path, &entry.version, asset, self.cancel.clone(), move |progress, total| {
+            emit_progress(&window, id, "downloading", progress, total);
+        }).await.map_err(|e| {
+            eprintln!("[INSTALLER] {}下载失败: {}", self.name(), e);
+            e
+        })?;
+
+        let result = self.install_archive(&archive_path, entry, asset).await;
+        // 下载的引擎包只是中间文件，无论成功与否都删除
+        let _ = fs::remove_file(&archive_path);
+        result
+    }
+
+    /// 离线安装：使用本地提供的引擎包（.7z / .zip / .tar.gz），校验与解压流程和在线安装相同。
+    ///
+    /// 引擎包必须能校验哈希：引擎包旁边的 `<文件名>.sha256`，或引擎清单中当前平台对应格式的 SHA-256。
+    /// 清单中没有当前平台的发行包时，只要提供了 `.sha256` 文件同样可以安装。
+    pub async fn install_from_file(&self, archive_path: &Path) -> Result<(), String> {
+        println!("[INSTALLER] 从本地文件安装{}: {:?}", self.name(), archive_path);
+        if !archive_path.is_file() {
+            return Err(format!("找不到引擎包: {}", archive_path.display()));
+        }
+        self.prepare_dir()?;
+        let settings = self.app.state::<AppState>().settings.lock().unwrap().clone();
+        let manifest = engine::load_manifest(&settings).await?;
+        let entry = manifest.entry(self.id)?;
+        let platform_asset = entry.platforms.get(std::env::consts::OS);
+        let asset = ManifestAsset {
+            url: archive_path.display().to_string(),
+            size: None,
+            sha256: Some(offline_sha256(archive_path, platform_asset)?),
+            archive_sha256: Default::default(),
+            exe_path: platform_asset.and_then(|a| a.exe_path.clone()),
+        };
+        self.install_archive(archive_path, entry, &asset).await
+    }
+
+    fn prepare_dir(&self) -> Result<(), String> {
+        if !self.local_data_dir.exists() {
+            fs::create_dir_all(&self.local_data_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// 校验并解压引擎包，写入安装记录。不会删除 `archive_path`。
+    ///
+    /// 先解压到暂存目录并确认可执行文件存在，再整体换入 `engines/<id>`，
+    /// 原有版本移到 `engines/<id>.previous` 保留，任何一步失败都不会影响已安装的版本。
+    async fn install_archive(&self, archive_path: &Path, entry: &ManifestEntry, asset: &ManifestAsset) -> Result<(), String> {
+        let total = fs::metadata(archive_path).map(|m| m.len()).unwrap_or(0);
+        let exe_path = asset.exe_path(self.id)?;
+
+        // 2. 校验文件完整性
+        self.emit("verifying", total, total);
+        let (path, checked_asset) = (archive_path.to_path_buf(), asset.clone());
+        tokio::task::spawn_blocking(move || download::verify_archive(&path, &checked_asset))
+            .await
+            .map_err(|e| format!("校验任务异常: {}", e))??;
+
+        // 解压开始后不再响应取消，避免留下不完整的引擎文件
+        if self.is_cancelled() {
+            return Err(download::CANCELLED_MESSAGE.to_string());
+        }
+
+        // 常驻进程占用着旧版本的文件（Windows 上无法移动），换入完成之前也不能启动新进程
+        let _worker = match self.id {
+            EngineId::Translator => {
+                let guard = worker::stop_for_update(&self.app).await?;
+                translator::reset_transport();
+                Some(guard)
+            }
+            EngineId::Ocr => None,
+        };
+
+        // 3. 解压到暂存目录，并确认引擎包中确实包含可执行文件
+        self.emit("extracting", total, total);
+        let install_dir = engine::install_dir(&self.app, self.id)?;
+        let previous_dir = engine::previous_dir(&self.app, self.id)?;
+        let staging_dir = install_dir.with_file_name(format!(".staging-{}", self.id.as_str()));
+        remove_dir_if_exists(&staging_dir)?;
+        let (path, staging) = (archive_path.to_path_buf(), staging_dir.clone());
+        let extracted = tokio::task::spawn_blocking(move || archive::extract(&path, &staging))
+            .await
+            .map_err(|e| format!("解压任务异常: {}", e))
+            .and_then(|result| result)
+            .and_then(|_| match staging_dir.join(&exe_path).is_file() {
+                true => Ok(()),
+                false => Err(format!("引擎包中缺少可执行文件 {}，可能不是当前平台的{}。", exe_path, self.name())),
+            });
+        if let Err(e) = extracted {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(e);
+        }
+
+        // 4. 换入安装目录，原有版本保留用于回滚
+        let had_previous = swap_into_place(&staging_dir, &install_dir, &previous_dir).map_err(|e| {
+            let _ = fs::remove_dir_all(&staging_dir);
+            e
+        })?;
+        engine::record_installed(&self.app, self.id, &entry.version, &engine::installed_exe_path(self.id, &exe_path), had_previous)?;
+
+        // 5. 通知完成
+        println!("[INSTALLER] {} v{} 安装完成: {:?}", self.name(), entry.version, install_dir);
+        self.emit("completed", total, total);
+        Ok(())
+    }
+}
+
+/// 离线安装的引擎包应有的 SHA-256：优先读取引擎包旁边的 `<文件名>.sha256`（`sha256sum` 的输出格式），
+/// 其次使用清单中与引擎包格式相同的发行包的哈希。都没有时拒绝安装。
+fn offline_sha256(archive_path: &Path, asset: Option<&ManifestAsset>) -> Result<String, String> {
+    let mut sidecar = archive_path.as_os_str().to_os_string();
+    sidecar.push(".sha256");
+    if let Ok(content) = fs::read_to_string(&sidecar) {
+        let hash = content.split_whitespace().next().unwrap_or_default();
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("哈希文件 {} 的内容不是有效的 SHA-256", Path::new(&sidecar).display()));
+        }
+        return Ok(hash.to_string());
+    }
+
+    let format = archive::ArchiveFormat::detect(archive_path)?;
+    let from_manifest = asset.and_then(|asset| {
+        let online_format = archive::ArchiveFormat::detect(Path::new(&asset.url)).ok();
+        match online_format == Some(format) {
+            true => asset.sha256.clone(),
+            false => asset.archive_sha256.get(format.as_str()).cloned(),
+        }
+    });
+    from_manifest.map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).ok_or_else(|| format!(
+        "无法校验引擎包：引擎清单中没有当前平台 {} 格式引擎包的 SHA-256。请把记录了哈希的 {}.sha256 文件放在引擎包旁边后重试。",
+        format.as_str(),
+        archive_path.file_name().unwrap_or_default().to_string_lossy(),
+    ))
+}
+
+fn remove_dir_if_exists(dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| format!("删除目录 {:?} 失败: {}", dir, e))?;
+    }
+    Ok(())
+}
+
+/// 用 `staging` 替换 `target`：原有的 `target` 移到 `previous`（覆盖更早的版本）。
+/// 替换失败时把原版本移回原位。返回是否保留了上一个版本。
+fn swap_into_place(staging: &Path, target: &Path, previous: &Path) -> Result<bool, String> {
+    let had_previous = target.exists();
+    if had_previous {
+        remove_dir_if_exists(previous)?;
+        fs::rename(target, previous).map_err(|e| format!("移动旧版本失败（引擎可能正在运行）: {}", e))?;
+    }
+    if let Err(e) = fs::rename(staging, target) {
+        if had_previous {
+            let _ = fs::rename(previous, target);
+        }
+        return Err(format!("安装引擎失败: {}", e));
+    }
+    Ok(had_previous)
+}
+
+/// 回滚时互换 `target` 与 `previous`：当前版本 → `swap` → `previous`，`previous` → 当前。
+/// 失败时把当前版本移回原位。
+fn swap_with_previous(target: &Path, previous: &Path, swap: &Path) -> Result<(), String> {
+    remove_dir_if_exists(swap)?;
+    fs::rename(target, swap).map_err(|e| format!("移动当前版本失败（引擎可能正在运行）: {}", e))?;
+    if let Err(e) = fs::rename(previous, target) {
+        let _ = fs::rename(swap, target);
+        return Err(format!("回滚失败: {}", e));
+    }
+    let _ = fs::rename(swap, previous);
+    Ok(())
+}
+
+fn emit_progress(window: &Window, engine_id: EngineId, status: &str, progress: u64, total: u64) {
+    window.emit(PROGRESS_EVENT, DownloadProgressPayload {
+        engine_id, progress, total, status: status.to_string(),
+    }).unwrap_or(());
+}
+
+/// 登记一个下载任务，任务结束（包括失败和取消）时自动注销
+struct DownloadRegistration {
+    app: AppHandle,
+    id: EngineId,
+}
+
+impl DownloadRegistration {
+    /// 同一引擎已有下载任务时返回错误
+    fn register(app: &AppHandle, id: EngineId) -> Result<(Self, watch::Receiver<bool>), String> {
+        let state: State<AppState> = app.state();
+        let mut downloads = state.engine_downloads.lock().unwrap();
+        if downloads.contains_key(&id) {
+            return Err(format!("{}正在下载或安装中，请等待完成或先取消。", engine::spec(id).display_name));
+        }
+        let (sender, receiver) = watch::channel(false);
+        downloads.insert(id, sender);
+        Ok((Self { app: app.clone(), id }, receiver))
+    }
+}
+
+impl Drop for DownloadRegistration {
+    fn drop(&mut self) {
+        let state: State<AppState> = self.app.state();
+        state.engine_downloads.lock().unwrap().remove(&self.id);
+    }
+}
+
+// --- Tauri 命令定义 ---
+
+/// 下载并安装指定引擎
+#[tauri::command]
+pub async fn download_engine(app: AppHandle, engine_id: EngineId) -> Result<(), String> {
+    let (_registration, cancel) = DownloadRegistration::register(&app, engine_id)?;
+    let installer = EngineInstaller::new(&app, engine_id, cancel)?;
+    let result = installer.install().await;
+    if result.is_err() && installer.is_cancelled() {
+        println!("[INSTALLER] {}下载已取消。", installer.name());
+        installer.emit("cancelled", 0, 0);
+    }
+    result
+}
+
+/// 从本地引擎包安装指定引擎，用于无法联网的机器
+#[tauri::command]
+pub async fn install_engine_from_file(app: AppHandle, engine_id: EngineId, path: String) -> Result<(), String> {
+    let (_registration, cancel) = DownloadRegistration::register(&app, engine_id)?;
+    EngineInstaller::new(&app, engine_id, cancel)?
+        .install_from_file(Path::new(path.trim()))
+        .await
+}
+
+/// 回滚到上一个版本：与 `.previous` 目录中保留的版本互换
+#[tauri::command]
+pub async fn rollback_engine(app: AppHandle, engine_id: EngineId) -> Result<(), String> {
+    let (_registration, _cancel) = DownloadRegistration::register(&app, engine_id)?;
+    let name = engine::spec(engine_id).display_name;
+    let mut records = engine::read_installed(&app);
+    let current = records.remove(&engine_id).ok_or_else(|| format!("{}没有安装记录", name))?;
+    let previous = current.previous.clone().ok_or_else(|| format!("{}没有可以回滚的版本", name))?;
+    let install_dir = engine::install_dir(&app, engine_id)?;
+    let previous_dir = engine::previous_dir(&app, engine_id)?;
+    if !previous_dir.is_dir() {
+        return Err(format!("{}的上一个版本已不存在", name));
+    }
+
+    let _worker = match engine_id {
+        EngineId::Translator => {
+            let guard = worker::stop_for_update(&app).await?;
+            translator::reset_transport();
+            Some(guard)
+        }
+        EngineId::Ocr => None,
+    };
+    let swap_dir = install_dir.with_file_name(format!(".rollback-{}", engine_id.as_str()));
+    swap_with_previous(&install_dir, &previous_dir, &swap_dir)?;
+
+    records.insert(engine_id, engine::InstalledEngine {
+        version: previous.version.clone(),
+        exe_path: previous.exe_path,
+        installed_at: current.installed_at,
+        previous: Some(engine::PreviousEngine { version: current.version, exe_path: current.exe_path }),
+    });
+    engine::write_installed(&app, &records)?;
+    println!("[INSTALLER] {}已回滚到 v{}", name, previous.version);
+    Ok(())
+}
+
+/// 查询引擎的安装信息：版本、路径、占用空间与最近使用时间
+#[tauri::command]
+pub async fn get_engine_info(app: AppHandle, state: State<'_, AppState>, engine_id: EngineId) -> Result<engine::EngineInfo, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    tokio::task::spawn_blocking(move || engine::engine_info(&app, &settings, engine_id))
+        .await
+        .map_err(|e| format!("查询引擎信息任务异常: {}", e))
+}
+
+/// 卸载引擎，删除应用数据目录中的引擎文件（包括保留的上一个版本），返回释放的字节数
+#[tauri::command]
+pub async fn uninstall_engine(app: AppHandle, engine_id: EngineId) -> Result<u64, String> {
+    let (_registration, _cancel) = DownloadRegistration::register(&app, engine_id)?;
+    let _worker = match engine_id {
+        EngineId::Translator => {
+            let guard = worker::stop_for_update(&app).await?;
+            translator::reset_transport();
+            Some(guard)
+        }
+        EngineId::Ocr => None,
+    };
+    let app_for_task = app.clone();
+    tokio::task::spawn_blocking(move || engine::uninstall(&app_for_task, engine_id))
+        .await
+        .map_err(|e| format!("卸载引擎任务异常: {}", e))?
+}
+
+/// 取消指定引擎正在进行的下载
+#[tauri::command]
+pub fn cancel_download(state: State<AppState>, engine_id: EngineId) -> Result<(), String> {
+    let downloads = state.engine_downloads.lock().unwrap();
+    let sender = downloads.get(&engine_id)
+        .ok_or_else(|| format!("{}没有正在进行的下载", engine::spec(engine_id).display_name))?;
+    println!("[INSTALLER] 请求取消{}下载。", engine::spec(engine_id).display_name);
+    sender.send_replace(true);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const HASH_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("installer-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn manifest_asset() -> ManifestAsset {
+        ManifestAsset {
+            url: "https://example.com/engine.7z".to_string(),
+            size: None,
+            sha256: Some(HASH_A.to_string()),
+            archive_sha256: [("zip".to_string(), HASH_B.to_string())].into_iter().collect(),
+            exe_path: None,
+        }
+    }
+
+    /// 在 `dir` 下创建只包含一个 `version.txt` 的目录
+    fn make_version_dir(dir: &Path, version: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("version.txt"), version).unwrap();
+    }
+
+    fn read_version(dir: &Path) -> String {
+        fs::read_to_string(dir.join("version.txt")).unwrap()
+    }
+
+    #[test]
+    fn swap_installs_into_an_empty_target() {
+        let dir = test_dir("swap-fresh");
+        let (staging, target, previous) = (dir.join(".staging"), dir.join("engine"), dir.join("engine.previous"));
+        make_version_dir(&staging, "1.0");
+
+        assert!(!swap_into_place(&staging, &target, &previous).unwrap());
+        assert_eq!(read_version(&target), "1.0");
+        assert!(!staging.exists() && !previous.exists());
+    }
+
+    #[test]
+    fn swap_keeps_the_replaced_version() {
+        let dir = test_dir("swap-upgrade");
+        let (staging, target, previous) = (dir.join(".staging"), dir.join("engine"), dir.join("engine.previous"));
+        make_version_dir(&target, "1.0");
+        make_version_dir(&previous, "0.9");
+        make_version_dir(&staging, "2.0");
+
+        assert!(swap_into_place(&staging, &target, &previous).unwrap());
+        assert_eq!(read_version(&target), "2.0");
+        // 更早的版本被覆盖，只保留上一个版本
+        assert_eq!(read_version(&previous), "1.0");
+        assert!(!staging.exists());
+    }
+
+    #[test]
+    fn failed_swap_restores_the_installed_version() {
+        let dir = test_dir("swap-failed");
+        let (staging, target, previous) = (dir.join(".staging"), dir.join("engine"), dir.join("engine.previous"));
+        make_version_dir(&target, "1.0");
+
+        // 暂存目录不存在，换入失败
+        assert!(swap_into_place(&staging, &target, &previous).is_err());
+        assert_eq!(read_version(&target), "1.0");
+        assert!(!previous.exists());
+    }
+
+    #[test]
+    fn rollback_swaps_current_and_previous() {
+        let dir = test_dir("rollback");
+        let (target, previous, swap) = (dir.join("engine"), dir.join("engine.previous"), dir.join(".rollback"));
+        make_version_dir(&target, "2.0");
+        make_version_dir(&previous, "1.0");
+
+        swap_with_previous(&target, &previous, &swap).unwrap();
+        assert_eq!((read_version(&target), read_version(&previous)), ("1.0".to_string(), "2.0".to_string()));
+        // 再次回滚回到原来的版本
+        swap_with_previous(&target, &previous, &swap).unwrap();
+        assert_eq!((read_version(&target), read_version(&previous)), ("2.0".to_string(), "1.0".to_string()));
+        assert!(!swap.exists());
+    }
+
+    #[test]
+    fn failed_rollback_keeps_the_current_version() {
+        let dir = test_dir("rollback-failed");
+        let (target, previous, swap) = (dir.join("engine"), dir.join("engine.previous"), dir.join(".rollback"));
+        make_version_dir(&target, "2.0");
+
+        assert!(swap_with_previous(&target, &previous, &swap).is_err());
+        assert_eq!(read_version(&target), "2.0");
+        assert!(!swap.exists());
+    }
+
+    #[test]
+    fn offline_hash_prefers_the_sidecar_file() {
+        let dir = test_dir("sidecar");
+        let archive = dir.join("translator.zip");
+        fs::write(&archive, b"PK\x03\x04").unwrap();
+        fs::write(dir.join("translator.zip.sha256"), format!("{}  translator.zip\n", HASH_A.to_uppercase())).unwrap();
+        assert_eq!(offline_sha256(&archive, Some(&manifest_asset())).unwrap(), HASH_A.to_uppercase());
+        // 清单中没有当前平台的发行包时也能安装
+        assert!(offline_sha256(&archive, None).is_ok());
+
+        fs::write(dir.join("translator.zip.sha256"), "not-a-hash").unwrap();
+        assert!(offline_sha256(&archive, None).unwrap_err().contains("不是有效的 SHA-256"));
+    }
+
+    #[test]
+    fn offline_hash_uses_the_manifest_entry_for_the_same_format() {
+        let dir = test_dir("manifest");
+        let asset = manifest_asset();
+        let seven_z = dir.join("engine.7z");
+        let zip = dir.join("engine.zip");
+        let tar_gz = dir.join("engine.tar.gz");
+        for path in [&seven_z, &zip, &tar_gz] {
+            fs::write(path, b"").unwrap();
+        }
+
+        assert_eq!(offline_sha256(&seven_z, Some(&asset)).unwrap(), HASH_A);
+        assert_eq!(offline_sha256(&zip, Some(&asset)).unwrap(), HASH_B);
+        assert!(offline_sha256(&tar_gz, Some(&asset)).unwrap_err().contains("engine.tar.gz.sha256"));
+        assert!(offline_sha256(&seven_z, None).is_err());
+    }
+}
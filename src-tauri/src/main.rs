@@ -1,9 +1,24 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod actions;
+mod archive;
 mod capture;
 mod commands;
+mod download;
+mod encoding;
+mod engine;
+mod installer;
+mod network;
+mod ocr;
+mod preprocess;
+mod regions;
+mod selftest;
 mod settings;
+mod shortcut;
+mod stitch;
 mod translator;
+mod watch;
+mod worker;
 
 use tauri::{
     AppHandle, GlobalShortcutManager, Manager, State,
@@ -20,9 +35,15 @@ use std::path::PathBuf; // 引入 PathBuf 用于处理文件路径
 #[derive(Clone, serde::Serialize)]
 struct ImageViewerPayload { image_data_url: String, image_path: String }
 #[derive(Clone, serde::Serialize)]
-struct ScreenshotPayload { image_data_url: String }
+struct ScreenshotPayload { image_data_url: String, mode: String, windows: Vec<capture::WindowInfo> }
 #[derive(Clone, serde::Serialize)]
-struct OcrPayload { original_text: Option<String>, error_message: Option<String>, image_path: String }
+struct OcrPayload {
+    original_text: Option<String>,
+    error_message: Option<String>,
+    image_path: String,
+    confidence: Option<f32>,
+    blocks: Vec<ocr::OcrBlock>,
+}
 #[derive(Clone, serde::Serialize)]
 struct TranslationUpdatePayload { translated_text: Option<String>, error_message: Option<String> }
 
@@ -91,10 +112,29 @@ fn main() {
             settings::copy_image_to_clipboard,
             settings::save_image_to_desktop,
             commands::check_ocr_status,
-            commands::download_ocr,
+            commands::list_tesseract_languages,
+            installer::download_engine,
+            installer::cancel_download,
+            installer::install_engine_from_file,
+            installer::rollback_engine,
+            installer::get_engine_info,
+            installer::uninstall_engine,
             commands::check_translator_status,
-            commands::download_translator,
-            commands::get_last_ocr_result // --- 新增注册命令 ---
+            commands::check_engine_updates,
+            commands::clear_cache,
+            selftest::self_test_engines,
+            commands::get_last_ocr_result, // --- 新增注册命令 ---
+            watch::start_watch,
+            watch::stop_watch,
+            watch::get_watch_status,
+            regions::select_region_to_save,
+            regions::save_region_from_selection,
+            regions::capture_saved_region,
+            commands::start_delayed_capture,
+            commands::list_windows,
+            commands::capture_window,
+            stitch::start_long_capture,
+            stitch::finish_long_capture
         ])
         // 应用程序初始化设置
         .setup(|app| {
@@ -106,12 +146,25 @@ fn main() {
             }
 
             let state: State<AppState> = app.state();
-            let settings = AppSettings::load(&app.path_resolver()).unwrap_or_default();
+            let (settings, load_warning) = AppSettings::load(&app.path_resolver());
+            if let Some(warning) = load_warning {
+                commands::send_notification(&app.handle(), "⚠️ 设置已重置", &warning);
+            }
+            // 能够解析但取值无效的设置（如无法识别的动作）照常加载，提示用户到设置中修改
+            if let Err(errors) = settings.validate() {
+                let error = settings::SettingsError::from(errors);
+                eprintln!("[SETTINGS] 设置校验未通过: {}", error.message);
+                commands::send_notification(&app.handle(), "⚠️ 设置有误", &error.message);
+            }
 
             *state.settings.lock().unwrap() = settings.clone();
 
             register_global_shortcut(app.handle(), &settings.shortcut).unwrap_or_else(|e| eprintln!("主快捷键注册失败: {}", e));
             register_view_image_shortcut(app.handle(), &settings.view_image_shortcut).unwrap_or_else(|e| eprintln!("查看快捷键注册失败: {}", e));
+            register_watch_shortcut(app.handle(), &settings.watch_shortcut).unwrap_or_else(|e| eprintln!("监视快捷键注册失败: {}", e));
+            register_delayed_capture_shortcut(app.handle(), &settings.delayed_capture_shortcut).unwrap_or_else(|e| eprintln!("延时截图快捷键注册失败: {}", e));
+            register_long_capture_shortcut(app.handle(), &settings.long_capture_shortcut).unwrap_or_else(|e| eprintln!("长截图快捷键注册失败: {}", e));
+            register_region_shortcuts(&app.handle(), &settings.saved_regions).unwrap_or_else(|e| eprintln!("{}", e));
 
             if let Some(main_window) = app.get_window("main") {
                 main_window.show()?;
@@ -120,7 +173,45 @@ fn main() {
         })
         .build(tauri::generate_context!())
         .expect("Tauri 构建失败")
-        .run(|_app_handle, _event| {});
+        .run(|app_handle, event| {
+            // 退出时结束翻译引擎常驻进程
+            if let tauri::RunEvent::Exit = event {
+                worker::shutdown(app_handle);
+            }
+        });
+}
+
+/// 获取结果窗口，不存在时按默认尺寸重新创建
+fn get_or_create_results_window(app: &AppHandle) -> tauri::Window {
+    if let Some(w) = app.get_window("results") {
+        w
+    } else {
+        tauri::WindowBuilder::new(app, "results", tauri::WindowUrl::App("results.html".into()))
+            .inner_size(500.0, 700.0)
+            .decorations(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .build()
+            .expect("无法创建结果窗口")
+    }
+}
+
+/// 将缓存的结果数据发送给结果窗口
+fn emit_cached_result(window: &tauri::Window, data: settings::LastOcrResult) {
+    window.emit("ocr_result", OcrPayload {
+        original_text: data.original_text,
+        error_message: None,
+        image_path: data.image_path,
+        confidence: data.confidence,
+        blocks: data.blocks,
+    }).unwrap();
+
+    if let Some(trans) = data.translated_text {
+        window.emit("translation_update", TranslationUpdatePayload {
+            translated_text: Some(trans),
+            error_message: None,
+        }).unwrap();
+    }
 }
 
 /// 显示结果窗口并填充缓存的数据
@@ -129,34 +220,94 @@ pub fn show_results_window_with_cache(app: &AppHandle) {
     let cache_opt = state.last_ocr_result.lock().unwrap().clone();
 
     if let Some(data) = cache_opt {
-        let window = if let Some(w) = app.get_window("results") {
-            w
-        } else {
-            tauri::WindowBuilder::new(app, "results", tauri::WindowUrl::App("results.html".into()))
-                .inner_size(500.0, 700.0)
-                .decorations(false)
-                .always_on_top(true)
-                .skip_taskbar(true)
-                .build()
-                .expect("无法创建结果窗口")
-        };
+        let window = get_or_create_results_window(app);
 
         window.show().unwrap();
         window.set_focus().unwrap();
 
-        window.emit("ocr_result", OcrPayload {
-            original_text: data.original_text,
-            error_message: None,
-            image_path: data.image_path,
-        }).unwrap();
+        emit_cached_result(&window, data);
+    }
+}
+
+/// 监视模式开始时，将结果窗口缩小为置顶的小窗口
+pub fn prepare_watch_results_window(app: &AppHandle) {
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let window = get_or_create_results_window(&handle);
+        let _ = window.set_size(Size::Logical(tauri::LogicalSize { width: 420.0, height: 260.0 }));
+        let _ = window.set_always_on_top(true);
+        let _ = window.show();
+    }).unwrap_or_else(|e| eprintln!("[WATCH] 无法在主线程上准备结果窗口: {}", e));
+}
 
-        if let Some(trans) = data.translated_text {
-            window.emit("translation_update", TranslationUpdatePayload {
-                translated_text: Some(trans),
-                error_message: None,
-            }).unwrap();
+/// 将缓存的结果推送到结果窗口，但不抢占焦点（监视模式下用户仍在操作其它程序）
+pub fn push_results_window_update(app: &AppHandle) {
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let state: State<AppState> = handle.state();
+        let cache_opt = state.last_ocr_result.lock().unwrap().clone();
+        if let Some(data) = cache_opt {
+            let window = get_or_create_results_window(&handle);
+            let _ = window.show();
+            emit_cached_result(&window, data);
         }
+    }).unwrap_or_else(|e| eprintln!("[WATCH] 无法在主线程上推送结果: {}", e));
+}
+
+/// 截取全屏并打开选区遮罩窗口
+///
+/// `mode` 会随初始化事件发送给前端，决定选区完成后调用哪个命令
+/// （"capture" 为普通截图，"watch" 为开始区域监视，"region" 为保存命名区域，"long" 为滚动长截图）。
+///
+/// # 返回
+/// `bool`: 如果已有截图正在进行而未能开始，返回 `false`。
+pub fn begin_screen_selection(app_handle: &AppHandle, mode: &str) -> bool {
+    let state: State<AppState> = app_handle.state();
+    if state.is_capturing.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        return false;
     }
+    let handle = app_handle.clone();
+    let mode = mode.to_string();
+
+    app_handle.run_on_main_thread(move || {
+        let inner_state: State<AppState> = handle.state();
+        match crate::capture::capture_fullscreen() {
+            Ok(image) => {
+                let img_width = image.width();
+                let img_height = image.height();
+
+                *inner_state.fullscreen_capture.lock().unwrap() = Some(image.clone());
+                let data_url = crate::capture::encode_image_to_data_url(&image).unwrap();
+                // 可见窗口的矩形，供选区遮罩吸附到鼠标下方的窗口
+                let windows = crate::capture::list_windows_on_primary_monitor(img_width).unwrap_or_else(|e| {
+                    eprintln!("枚举窗口失败，选区将无法吸附窗口: {}", e);
+                    Vec::new()
+                });
+
+                if let Some(w) = handle.get_window("screenshot") {
+                    w.set_size(Size::Physical(PhysicalSize { width: img_width, height: img_height })).unwrap();
+                    w.set_position(Position::Physical(PhysicalPosition { x: 0, y: 0 })).unwrap();
+                    w.emit("initialize-screenshot", ScreenshotPayload{image_data_url: data_url, mode, windows}).unwrap();
+                    w.show().unwrap();
+                    w.set_focus().unwrap();
+                } else {
+                    let w = tauri::WindowBuilder::new(&handle, "screenshot", tauri::WindowUrl::App("screenshot.html".into()))
+                        .title("").decorations(false).transparent(true).visible(false).skip_taskbar(true)
+                        .always_on_top(true).resizable(false).build().unwrap();
+                    w.set_size(Size::Physical(PhysicalSize { width: img_width, height: img_height })).unwrap();
+                    w.set_position(Position::Physical(PhysicalPosition { x: 0, y: 0 })).unwrap();
+                    w.emit("initialize-screenshot", ScreenshotPayload{image_data_url: data_url, mode, windows}).unwrap();
+                    w.show().unwrap();
+                    w.set_focus().unwrap();
+                }
+            },
+            Err(e) => {
+                eprintln!("全屏截图失败: {}", e);
+                inner_state.is_capturing.store(false, Ordering::SeqCst);
+            }
+        }
+    }).unwrap();
+    true
 }
 
 /// 注册主截图功能的全局快捷键
@@ -166,51 +317,111 @@ pub fn register_global_shortcut(app_handle: AppHandle, shortcut: &str) -> Result
 
     let shortcut_clone = shortcut.to_string();
 
+    manager.register(shortcut, move || {
+        if begin_screen_selection(&app_handle, "capture") {
+            println!("[SHORTCUT] 触发截图: {}", shortcut_clone);
+        } else {
+            println!("[SHORTCUT] 截图正在进行中，忽略快捷键: {}", shortcut_clone);
+        }
+    }).map_err(Into::into)
+}
+
+/// 注册区域监视的全局快捷键
+///
+/// 未在监视时按下：打开选区遮罩，框选要监视的区域；正在监视时按下：停止监视。
+pub fn register_watch_shortcut(app_handle: AppHandle, shortcut: &str) -> Result<(), tauri::Error> {
+    let mut manager = app_handle.global_shortcut_manager();
+    if manager.is_registered(shortcut)? { manager.unregister(shortcut)?; }
+
+    let shortcut_clone = shortcut.to_string();
+
     manager.register(shortcut, move || {
         let state: State<AppState> = app_handle.state();
-        if state.is_capturing.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        if watch::stop_watch_session(&state) {
+            println!("[SHORTCUT] 停止区域监视: {}", shortcut_clone);
+            commands::send_notification(&app_handle, "⏹️ 监视已停止", "区域监视已结束。");
+        } else if begin_screen_selection(&app_handle, "watch") {
+            println!("[SHORTCUT] 选择监视区域: {}", shortcut_clone);
+        } else {
             println!("[SHORTCUT] 截图正在进行中，忽略快捷键: {}", shortcut_clone);
-            return;
         }
-        println!("[SHORTCUT] 触发截图: {}", shortcut_clone);
-        let handle = app_handle.clone();
+    }).map_err(Into::into)
+}
 
-        app_handle.run_on_main_thread(move || {
-            let inner_state: State<AppState> = handle.state();
-            match crate::capture::capture_fullscreen() {
-                Ok(image) => {
-                    let img_width = image.width();
-                    let img_height = image.height();
-
-                    *inner_state.fullscreen_capture.lock().unwrap() = Some(image.clone());
-                    let data_url = crate::capture::encode_image_to_data_url(&image).unwrap();
-
-                    if let Some(w) = handle.get_window("screenshot") {
-                        w.set_size(Size::Physical(PhysicalSize { width: img_width, height: img_height })).unwrap();
-                        w.set_position(Position::Physical(PhysicalPosition { x: 0, y: 0 })).unwrap();
-                        w.emit("initialize-screenshot", ScreenshotPayload{image_data_url: data_url}).unwrap();
-                        w.show().unwrap();
-                        w.set_focus().unwrap();
-                    } else {
-                        let w = tauri::WindowBuilder::new(&handle, "screenshot", tauri::WindowUrl::App("screenshot.html".into()))
-                            .title("").decorations(false).transparent(true).visible(false).skip_taskbar(true)
-                            .always_on_top(true).resizable(false).build().unwrap();
-                        w.set_size(Size::Physical(PhysicalSize { width: img_width, height: img_height })).unwrap();
-                        w.set_position(Position::Physical(PhysicalPosition { x: 0, y: 0 })).unwrap();
-                        w.emit("initialize-screenshot", ScreenshotPayload{image_data_url: data_url}).unwrap();
-                        w.show().unwrap();
-                        w.set_focus().unwrap();
-                    }
-                },
-                Err(e) => {
-                    eprintln!("全屏截图失败: {}", e);
-                    inner_state.is_capturing.store(false, Ordering::SeqCst);
-                }
-            }
-        }).unwrap();
+/// 注册延时截图的全局快捷键
+///
+/// 按下后等待 `capture_delay_secs` 秒再截取全屏并打开选区遮罩，便于截取按键时会消失的菜单和提示。
+pub fn register_delayed_capture_shortcut(app_handle: AppHandle, shortcut: &str) -> Result<(), tauri::Error> {
+    let mut manager = app_handle.global_shortcut_manager();
+    if manager.is_registered(shortcut)? { manager.unregister(shortcut)?; }
+
+    let shortcut_clone = shortcut.to_string();
+
+    manager.register(shortcut, move || {
+        let state: State<AppState> = app_handle.state();
+        let delay = state.settings.lock().unwrap().capture_delay_secs;
+        println!("[SHORTCUT] 触发延时截图: {}", shortcut_clone);
+        if let Err(e) = commands::schedule_delayed_capture(&app_handle, delay, None) {
+            println!("[SHORTCUT] 忽略延时截图快捷键: {}", e);
+        }
+    }).map_err(Into::into)
+}
+
+/// 注册滚动长截图的全局快捷键
+///
+/// 未在长截图时按下：打开选区遮罩，框选要连续截取的区域；正在长截图时按下：结束并拼接。
+pub fn register_long_capture_shortcut(app_handle: AppHandle, shortcut: &str) -> Result<(), tauri::Error> {
+    let mut manager = app_handle.global_shortcut_manager();
+    if manager.is_registered(shortcut)? { manager.unregister(shortcut)?; }
+
+    let shortcut_clone = shortcut.to_string();
+
+    manager.register(shortcut, move || {
+        let state: State<AppState> = app_handle.state();
+        if stitch::finish_long_capture_session(&state) {
+            println!("[SHORTCUT] 结束长截图: {}", shortcut_clone);
+        } else if begin_screen_selection(&app_handle, "long") {
+            println!("[SHORTCUT] 选择长截图区域: {}", shortcut_clone);
+        } else {
+            println!("[SHORTCUT] 截图正在进行中，忽略快捷键: {}", shortcut_clone);
+        }
     }).map_err(Into::into)
 }
 
+/// 为所有绑定了快捷键的保存区域注册全局快捷键
+///
+/// 快捷键触发时按区域名称重新查找配置，因此修改区域的动作或语言后无需重新注册。
+pub fn register_region_shortcuts(app_handle: &AppHandle, saved_regions: &[regions::SavedRegion]) -> Result<(), String> {
+    let mut manager = app_handle.global_shortcut_manager();
+
+    for region in saved_regions {
+        let Some(shortcut) = region.shortcut.as_deref().filter(|s| !s.trim().is_empty()) else { continue };
+        if manager.is_registered(shortcut).unwrap_or(false) { let _ = manager.unregister(shortcut); }
+
+        let handle = app_handle.clone();
+        let region_name = region.name.clone();
+        manager.register(shortcut, move || {
+            println!("[SHORTCUT] 触发保存区域截图: {}", region_name);
+            let handle = handle.clone();
+            let region_name = region_name.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = regions::run_saved_region(&handle, &region_name).await {
+                    eprintln!("[SHORTCUT] 保存区域「{}」处理失败: {}", region_name, e);
+                }
+            });
+        }).map_err(|e| format!("注册区域「{}」的快捷键 {} 失败: {}", region.name, shortcut, e))?;
+    }
+    Ok(())
+}
+
+/// 注销所有保存区域的全局快捷键
+pub fn unregister_region_shortcuts(app_handle: &AppHandle, saved_regions: &[regions::SavedRegion]) {
+    let mut manager = app_handle.global_shortcut_manager();
+    for shortcut in saved_regions.iter().filter_map(|r| r.shortcut.as_deref()) {
+        let _ = manager.unregister(shortcut);
+    }
+}
+
 /// 注册查看上一次截图/结果的全局快捷键
 pub fn register_view_image_shortcut(app_handle: AppHandle, shortcut: &str) -> Result<(), tauri::Error> {
     let mut manager = app_handle.global_shortcut_manager();
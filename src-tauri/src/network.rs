@@ -0,0 +1,162 @@
+// --- 文件: src-tauri/src/network.rs ---
+
+//! 网络访问设置
+//!
+//! 部分用户所在的网络无法直接访问 GitHub，这里根据设置统一创建 HTTP 客户端：
+//! 可以指定 HTTP / HTTPS / SOCKS5 代理（未指定时沿用系统环境变量中的代理），
+//! 以及企业网络中 HTTPS 检查代理使用的自定义根证书。
+//! 引擎清单、引擎下载以及需要联网的翻译后端都应通过 `build_client` 创建客户端。
+//!
+//! 下载引擎包时按设置中的镜像地址依次尝试，全部失败后再使用原始地址。
+
+use std::fs;
+use std::time::Duration;
+
+use crate::settings::AppSettings;
+
+// 连接超时
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+// 镜像地址中表示完整原始 URL 的占位符
+const URL_PLACEHOLDER: &str = "{url}";
+
+/// 按网络设置创建 HTTP 客户端。`timeout` 为整个请求的超时，下载大文件时传 None。
+pub fn build_client(settings: &AppSettings, timeout: Option<Duration>) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS));
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    if let Some(proxy) = non_empty(&settings.network_proxy) {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| format!("代理地址无效 ({}): {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(path) = non_empty(&settings.network_ca_cert) {
+        let content = fs::read(path).map_err(|e| format!("读取 CA 证书 {} 失败: {}", path, e))?;
+        // 支持包含多个证书的 PEM 文件，也支持单个 DER 证书
+        let certificates = reqwest::Certificate::from_pem_bundle(&content)
+            .ok()
+            .filter(|certs| !certs.is_empty())
+            .map_or_else(|| reqwest::Certificate::from_der(&content).map(|cert| vec![cert]), Ok)
+            .map_err(|e| format!("解析 CA 证书 {} 失败: {}", path, e))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder.build().map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+/// 下载 `url` 时依次尝试的地址：先是设置中的各个镜像（去掉重复和无效的地址），最后是原始地址。
+///
+/// 镜像地址中包含 `{url}` 时替换为完整的原始 URL（适用于 `https://mirror/{url}` 形式的代理下载站），
+/// 否则用镜像地址替换原始 URL 的协议和主机部分，例如镜像 `https://mirror.example.com/github`
+/// 对应 `https://github.com/owner/repo/...` 得到 `https://mirror.example.com/github/owner/repo/...`。
+pub fn candidate_urls(settings: &AppSettings, url: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    let mirrored = settings.download_mirrors.iter()
+        .map(|m| m.trim())
+        .filter(|m| !m.is_empty())
+        .filter_map(|mirror| mirror_url(mirror, url));
+    for candidate in mirrored {
+        if candidate != url && !urls.contains(&candidate) {
+            urls.push(candidate);
+        }
+    }
+    urls.push(url.to_string());
+    urls
+}
+
+/// 检查镜像地址：必须是 http(s) URL，可以包含 `{url}` 占位符
+pub fn validate_mirror(mirror: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(mirror.trim()).map_err(|e| format!("镜像地址无效 ({}): {}", mirror, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("镜像地址必须是 http:// 或 https:// 地址: {}", mirror));
+    }
+    Ok(())
+}
+
+fn mirror_url(mirror: &str, url: &str) -> Option<String> {
+    if validate_mirror(mirror).is_err() {
+        eprintln!("[NETWORK] 忽略无效的镜像地址: {}", mirror);
+        return None;
+    }
+    if mirror.contains(URL_PLACEHOLDER) {
+        return Some(mirror.replace(URL_PLACEHOLDER, url));
+    }
+    // 去掉 scheme://host，保留路径与查询参数
+    let after_scheme = url.split_once("://")?.1;
+    let path = after_scheme.find('/').map(|i| &after_scheme[i..]).unwrap_or("/");
+    Some(format!("{}{}", mirror.trim_end_matches('/'), path))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://github.com/owner/repo/releases/download/v1/engine.7z";
+
+    fn settings_with_mirrors(mirrors: &[&str]) -> AppSettings {
+        AppSettings { download_mirrors: mirrors.iter().map(|m| m.to_string()).collect(), ..AppSettings::default() }
+    }
+
+    #[test]
+    fn substitutes_the_url_placeholder() {
+        assert_eq!(
+            mirror_url("https://proxy.example.com/{url}", URL).unwrap(),
+            format!("https://proxy.example.com/{}", URL),
+        );
+    }
+
+    #[test]
+    fn replaces_the_host() {
+        let expected = "https://mirror.example.com/github/owner/repo/releases/download/v1/engine.7z";
+        assert_eq!(mirror_url("https://mirror.example.com/github", URL).unwrap(), expected);
+        assert_eq!(mirror_url("https://mirror.example.com/github/", URL).unwrap(), expected);
+        assert_eq!(mirror_url("https://mirror.example.com", "https://github.com").unwrap(), "https://mirror.example.com/");
+        assert_eq!(
+            mirror_url("http://10.0.0.1:8080", "https://github.com/a?b=1").unwrap(),
+            "http://10.0.0.1:8080/a?b=1",
+        );
+    }
+
+    #[test]
+    fn original_url_comes_last_without_duplicates() {
+        let settings = settings_with_mirrors(&[
+            "https://a.example.com",
+            "  ",
+            "https://github.com",
+            "https://a.example.com/",
+            "https://b.example.com/{url}",
+        ]);
+        assert_eq!(candidate_urls(&settings, URL), vec![
+            format!("https://a.example.com{}", "/owner/repo/releases/download/v1/engine.7z"),
+            format!("https://b.example.com/{}", URL),
+            URL.to_string(),
+        ]);
+        assert_eq!(candidate_urls(&settings_with_mirrors(&[]), URL), vec![URL.to_string()]);
+    }
+
+    #[test]
+    fn skips_invalid_mirrors() {
+        let settings = settings_with_mirrors(&["mirror.example.com", "ftp://mirror.example.com", "https://ok.example.com"]);
+        assert_eq!(candidate_urls(&settings, URL), vec![
+            "https://ok.example.com/owner/repo/releases/download/v1/engine.7z".to_string(),
+            URL.to_string(),
+        ]);
+    }
+
+    #[test]
+    fn validates_mirrors() {
+        assert!(validate_mirror("https://mirror.example.com/github").is_ok());
+        assert!(validate_mirror("https://proxy.example.com/{url}").is_ok());
+        assert!(validate_mirror("http://10.0.0.1:8080").is_ok());
+        assert!(validate_mirror("mirror.example.com").is_err());
+        assert!(validate_mirror("ftp://mirror.example.com").is_err());
+        assert!(validate_mirror("https://").is_err());
+    }
+}
@@ -0,0 +1,457 @@
+// --- 文件: src-tauri/src/ocr.rs ---
+
+//! OCR 引擎与识别结果
+//!
+//! 支持两种后端：Windows 上的 RapidOCR-json，以及跨平台的 Tesseract 命令行。
+//! 两者都输出统一的 `OcrOutput`，上层的预处理、置信度重试和结果展示与具体引擎无关。
+//!
+//! 引擎会为每个文本块给出置信度 (0.0 ~ 1.0)。整体置信度按字符数加权平均，
+//! 长文本块对结果的影响大于零散的单字。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use tauri::AppHandle;
+
+use crate::encoding::decode_engine_output;
+use crate::engine::{self, EngineId};
+use crate::settings::AppSettings;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// --- 引擎标识 ---
+pub const ENGINE_RAPIDOCR: &str = "rapidocr";
+pub const ENGINE_TESSERACT: &str = "tesseract";
+// 未指定 Tesseract 路径时从 PATH 中查找
+const DEFAULT_TESSERACT_COMMAND: &str = "tesseract";
+
+/// 一个识别出的文本块
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OcrBlock {
+    pub text: String,
+    pub score: f32,
+    // 置信度是否低于设置中的阈值，结果窗口据此标记
+    #[serde(default)]
+    pub low_confidence: bool,
+}
+
+/// 一次 OCR 的完整结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OcrOutput {
+    pub blocks: Vec<OcrBlock>,
+    // 按设置的分隔符（空格或换行）拼接后的全文
+    pub text: String,
+    pub confidence: f32,
+}
+
+impl OcrOutput {
+    /// 由文本块构造结果，并计算整体置信度
+    pub fn from_blocks(blocks: Vec<OcrBlock>, separator: &str) -> Self {
+        let text = blocks.iter().map(|b| b.text.as_str()).collect::<Vec<_>>().join(separator);
+        let confidence = weighted_confidence(&blocks);
+        Self { blocks, text, confidence }
+    }
+
+    /// 按阈值标记低置信度的文本块
+    pub fn mark_low_confidence(&mut self, threshold: f32) {
+        for block in &mut self.blocks {
+            block.low_confidence = block.score < threshold;
+        }
+    }
+
+    /// 两次识别结果中选择更好的一个：优先选择整体置信度更高的，
+    /// 置信度相同时选择识别出更多文字的。
+    pub fn better(self, other: OcrOutput) -> OcrOutput {
+        let key = |o: &OcrOutput| (o.confidence, o.text.chars().filter(|c| !c.is_whitespace()).count());
+        let (a, b) = (key(&self), key(&other));
+        if b.0 > a.0 || (b.0 == a.0 && b.1 > a.1) { other } else { self }
+    }
+}
+
+/// 按字符数加权的平均置信度。没有文本块时为 0。
+pub fn weighted_confidence(blocks: &[OcrBlock]) -> f32 {
+    let (weighted, weight) = blocks.iter().fold((0.0f64, 0usize), |(sum, count), block| {
+        let chars = block.text.chars().filter(|c| !c.is_whitespace()).count().max(1);
+        (sum + block.score as f64 * chars as f64, count + chars)
+    });
+    if weight == 0 { 0.0 } else { (weighted / weight as f64) as f32 }
+}
+
+/// 解析 RapidOCR-json 的输出。
+///
+/// 成功时格式为 `{"code": 100, "data": [{"box": [...], "score": 0.98, "text": "..."}]}`，
+/// 失败时 `data` 为错误信息字符串。
+pub fn parse_rapidocr_json(value: &serde_json::Value, separator: &str) -> Result<OcrOutput, String> {
+    if value["code"].as_i64().unwrap_or(0) != 100 {
+        return Err(value["data"].as_str().unwrap_or("未知OCR错误").to_string());
+    }
+    let blocks = value["data"].as_array().map(Vec::as_slice).unwrap_or_default().iter()
+        .filter_map(|item| {
+            let text = item["text"].as_str()?.to_string();
+            // 部分版本把 score 输出为字符串
+            let score = item["score"].as_f64()
+                .or_else(|| item["score"].as_str().and_then(|s| s.parse().ok()))
+                .unwrap_or(1.0) as f32;
+            Some(OcrBlock { text, score, low_confidence: false })
+        })
+        .collect();
+    Ok(OcrOutput::from_blocks(blocks, separator))
+}
+
+// TSV 中一行文字的位置: (page, block, par, line)
+type TsvLineKey = (u32, u32, u32, u32);
+
+/// 解析 Tesseract 的 TSV 输出。
+///
+/// TSV 每行对应一个版面元素，`level` 为 5 时是单词。按 (block, par, line) 把单词合并为行，
+/// 每行作为一个文本块，置信度取行内单词置信度 (0 ~ 100) 的平均值。
+pub fn parse_tesseract_tsv(tsv: &str, separator: &str) -> OcrOutput {
+    // BTreeMap 保证按版面顺序输出各行
+    let mut lines: BTreeMap<TsvLineKey, Vec<(String, f32)>> = BTreeMap::new();
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.split('\t').collect();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let text = columns[11].trim();
+        let conf: f32 = columns[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || conf < 0.0 {
+            continue;
+        }
+        let key_part = |i: usize| columns[i].parse::<u32>().unwrap_or(0);
+        let key = (key_part(1), key_part(2), key_part(3), key_part(4));
+        lines.entry(key).or_default().push((text.to_string(), conf / 100.0));
+    }
+
+    let blocks = lines.into_values()
+        .map(|words| {
+            let score = words.iter().map(|(_, conf)| conf).sum::<f32>() / words.len() as f32;
+            let text = join_words(words.iter().map(|(text, _)| text.as_str()));
+            OcrBlock { text, score, low_confidence: false }
+        })
+        .collect();
+    OcrOutput::from_blocks(blocks, separator)
+}
+
+/// 拼接单词：Tesseract 会把中日韩文字逐字拆开，相邻两个字都是 CJK 字符时不插入空格。
+fn join_words<'a>(words: impl Iterator<Item = &'a str>) -> String {
+    let mut joined = String::new();
+    for word in words {
+        let needs_space = match (joined.chars().last(), word.chars().next()) {
+            (Some(prev), Some(next)) => !(is_cjk(prev) && is_cjk(next)),
+            _ => false,
+        };
+        if needs_space {
+            joined.push(' ');
+        }
+        joined.push_str(word);
+    }
+    joined
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3000..=0x303F |   // CJK 标点
+        0x3040..=0x30FF |   // 平假名、片假名
+        0x3400..=0x4DBF |   // 扩展 A
+        0x4E00..=0x9FFF |   // 基本汉字
+        0xAC00..=0xD7AF |   // 韩文音节
+        0xF900..=0xFAFF |   // 兼容汉字
+        0xFF00..=0xFFEF)    // 全角符号
+}
+
+// --- 引擎定义 ---
+
+/// OCR 引擎的统一接口
+pub trait OcrEngine {
+    /// 引擎名称，用于日志
+    fn name(&self) -> &'static str;
+    /// 识别图片文件中的文字，文本块之间以 `separator` 连接
+    fn recognize(&self, image_path: &Path, separator: &str) -> Result<OcrOutput, String>;
+}
+
+/// RapidOCR-json 后端（仅提供 Windows 可执行文件）
+pub struct RapidOcrEngine {
+    exe_path: PathBuf,
+    // 输出不是 UTF-8 时使用的本地代码页
+    output_encoding: String,
+}
+
+impl OcrEngine for RapidOcrEngine {
+    fn name(&self) -> &'static str {
+        "RapidOCR"
+    }
+
+    fn recognize(&self, image_path: &Path, separator: &str) -> Result<OcrOutput, String> {
+        let ocr_dir = self.exe_path.parent().ok_or("无法获取OCR目录")?;
+        println!("[OCR] OCR 工作目录: {:?}", ocr_dir);
+
+        let mut command = StdCommand::new(&self.exe_path);
+        let arg = format!("--image_path={}", image_path.to_string_lossy());
+        command.args(&[arg.clone()]).current_dir(ocr_dir);
+        #[cfg(windows)] command.creation_flags(CREATE_NO_WINDOW);
+
+        println!("[OCR] 准备执行命令: {:?} with arg: '{}'", self.exe_path, arg);
+        let ocr_output = command.output().map_err(|e| {
+            let err_msg = format!("执行OCR进程失败: {}", e);
+            println!("[OCR] 错误: {}", err_msg);
+            err_msg
+        })?;
+
+        println!("[OCR] 进程执行完毕. Status: {:?}", ocr_output.status);
+
+        if !ocr_output.status.success() {
+            let stderr = decode_engine_output(&ocr_output.stderr, &self.output_encoding);
+            let err_msg = format!("OCR进程返回错误: {}", stderr);
+            println!("[OCR] 错误: {}", err_msg);
+            println!("[OCR] Stderr (raw bytes): {:?}", &ocr_output.stderr);
+            return Err(err_msg);
+        }
+
+        let stdout = decode_engine_output(&ocr_output.stdout, &self.output_encoding);
+        println!("[OCR] Stdout (decoded): '{}'", stdout);
+
+        let json_start = stdout.lines().find(|line| line.starts_with('{')).unwrap_or("{}");
+        println!("[OCR] 提取到的 JSON 字符串: '{}'", json_start);
+
+        let ocr_value: serde_json::Value = serde_json::from_str(json_start).map_err(|e| {
+            let err_msg = format!("解析OCR结果JSON失败: {}", e);
+            println!("[OCR] 错误: {}", err_msg);
+            err_msg
+        })?;
+
+        parse_rapidocr_json(&ocr_value, separator).map_err(|err_msg| {
+            println!("[OCR] 错误: OCR 引擎返回错误码: {}", err_msg);
+            err_msg
+        })
+    }
+}
+
+/// Tesseract 命令行后端，`langs` 为 `-l` 参数，如 `eng+chi_sim`
+pub struct TesseractEngine {
+    command: String,
+    langs: String,
+    output_encoding: String,
+}
+
+impl OcrEngine for TesseractEngine {
+    fn name(&self) -> &'static str {
+        "Tesseract"
+    }
+
+    fn recognize(&self, image_path: &Path, separator: &str) -> Result<OcrOutput, String> {
+        let mut command = tesseract_command(&self.command);
+        command.arg(image_path).arg("stdout").arg("-l").arg(&self.langs).arg("tsv");
+        println!("[OCR] 准备执行命令: {:?}", command);
+
+        let output = command.output().map_err(|e| {
+            let err_msg = format!("执行 Tesseract 失败: {}（请确认已安装 Tesseract 或在设置中指定路径）", e);
+            println!("[OCR] 错误: {}", err_msg);
+            err_msg
+        })?;
+        println!("[OCR] 进程执行完毕. Status: {:?}", output.status);
+
+        if !output.status.success() {
+            let stderr = decode_engine_output(&output.stderr, &self.output_encoding);
+            let err_msg = format!("Tesseract 返回错误: {}", stderr.trim());
+            println!("[OCR] 错误: {}", err_msg);
+            return Err(err_msg);
+        }
+
+        let stdout = decode_engine_output(&output.stdout, &self.output_encoding);
+        Ok(parse_tesseract_tsv(&stdout, separator))
+    }
+}
+
+pub(crate) fn tesseract_command(program: &str) -> StdCommand {
+    #[allow(unused_mut)]
+    let mut command = StdCommand::new(program);
+    #[cfg(windows)] command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+/// 设置中的 Tesseract 路径 (`engine_paths.tesseract`)，未设置时使用 PATH 中的 `tesseract`
+pub(crate) fn tesseract_program(settings: &AppSettings) -> String {
+    settings.engine_paths.tesseract()
+        .unwrap_or(DEFAULT_TESSERACT_COMMAND)
+        .to_string()
+}
+
+/// 当前平台默认使用的 OCR 引擎
+pub fn default_engine() -> &'static str {
+    if cfg!(windows) { ENGINE_RAPIDOCR } else { ENGINE_TESSERACT }
+}
+
+/// 根据设置创建 OCR 引擎。引擎不可用时返回可直接展示给用户的错误信息。
+pub fn get_ocr_engine(app: &AppHandle, settings: &AppSettings) -> Result<Box<dyn OcrEngine + Send + Sync>, String> {
+    match settings.ocr_engine.as_str() {
+        ENGINE_TESSERACT => Ok(Box::new(TesseractEngine {
+            command: tesseract_program(settings),
+            langs: settings.tesseract_langs.clone(),
+            output_encoding: settings.engine_output_encoding.clone(),
+        })),
+        _ => {
+            let exe_path = engine::find_exe(app, settings, EngineId::Ocr)?;
+            Ok(Box::new(RapidOcrEngine { exe_path, output_encoding: settings.engine_output_encoding.clone() }))
+        }
+    }
+}
+
+/// 检查当前设置的 OCR 引擎是否可用
+pub fn is_engine_available(app: &AppHandle, settings: &AppSettings) -> bool {
+    match settings.ocr_engine.as_str() {
+        ENGINE_TESSERACT => tesseract_command(&tesseract_program(settings))
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false),
+        _ => get_ocr_engine(app, settings).is_ok(),
+    }
+}
+
+/// 列出 Tesseract 已安装的语言包
+pub fn list_tesseract_languages(settings: &AppSettings) -> Result<Vec<String>, String> {
+    let output = tesseract_command(&tesseract_program(settings))
+        .arg("--list-langs")
+        .output()
+        .map_err(|e| format!("执行 Tesseract 失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Tesseract 返回错误: {}", decode_engine_output(&output.stderr, &settings.engine_output_encoding).trim()));
+    }
+    // 第一行是 "List of available languages in ..." 提示，其余每行一个语言代码
+    Ok(decode_engine_output(&output.stdout, &settings.engine_output_encoding)
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != "osd")
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `tesseract img stdout tsv` 的输出：两行英文、一行被拆成单字的中文，以及非单词层级的行
+    const TSV_FIXTURE: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t640\t480\t-1\t
+2\t1\t1\t0\t0\t0\t10\t10\t300\t60\t-1\t
+4\t1\t1\t1\t1\t0\t10\t10\t300\t20\t-1\t
+5\t1\t1\t1\t1\t1\t10\t10\t80\t20\t96.5\tHello
+5\t1\t1\t1\t1\t2\t100\t10\t80\t20\t91.5\tworld
+5\t1\t1\t1\t2\t1\t10\t40\t80\t20\t80\tSecond
+5\t1\t1\t1\t2\t2\t100\t40\t80\t20\t-1\t
+5\t1\t1\t1\t2\t3\t190\t40\t80\t20\t70\tline
+5\t1\t2\t1\t1\t1\t10\t80\t20\t20\t90\t你
+5\t1\t2\t1\t1\t2\t30\t80\t20\t20\t80\t好
+5\t1\t2\t1\t1\t3\t50\t80\t20\t20\t70\t，
+5\t1\t2\t1\t1\t4\t70\t80\t60\t20\t60\tOCR
+";
+
+    #[test]
+    fn parses_words_into_lines() {
+        let output = parse_tesseract_tsv(TSV_FIXTURE, "\n");
+        let texts: Vec<&str> = output.blocks.iter().map(|b| b.text.as_str()).collect();
+        assert_eq!(texts, ["Hello world", "Second line", "你好， OCR"]);
+        assert_eq!(output.text, "Hello world\nSecond line\n你好， OCR");
+    }
+
+    #[test]
+    fn line_confidence_is_the_word_average() {
+        let output = parse_tesseract_tsv(TSV_FIXTURE, " ");
+        let scores: Vec<f32> = output.blocks.iter().map(|b| b.score).collect();
+        assert!((scores[0] - 0.94).abs() < 1e-4);
+        // conf 为 -1 的空单词不参与计算
+        assert!((scores[1] - 0.75).abs() < 1e-4);
+        assert!((scores[2] - 0.75).abs() < 1e-4);
+    }
+
+    #[test]
+    fn empty_or_header_only_tsv_has_no_blocks() {
+        assert!(parse_tesseract_tsv("", " ").blocks.is_empty());
+        let header_only = TSV_FIXTURE.lines().next().unwrap();
+        let output = parse_tesseract_tsv(header_only, " ");
+        assert!(output.blocks.is_empty());
+        assert_eq!(output.confidence, 0.0);
+    }
+
+    #[test]
+    fn cjk_words_are_joined_without_spaces() {
+        assert_eq!(join_words(["日本", "語", "テスト"].into_iter()), "日本語テスト");
+        assert_eq!(join_words(["한국어", "텍스트"].into_iter()), "한국어텍스트");
+        assert_eq!(join_words(["中文", "and", "English"].into_iter()), "中文 and English");
+        assert_eq!(join_words(["x", "1"].into_iter()), "x 1");
+    }
+
+    #[test]
+    fn parses_rapidocr_json() {
+        let value = serde_json::json!({"code": 100, "data": [
+            {"text": "Hello", "score": 0.9},
+            {"text": "世界", "score": "0.6"},
+        ]});
+        let output = parse_rapidocr_json(&value, " ").unwrap();
+        assert_eq!(output.text, "Hello 世界");
+        // 按字符数加权：(0.9 * 5 + 0.6 * 2) / 7
+        assert!((output.confidence - 5.7 / 7.0).abs() < 1e-4);
+
+        let error = serde_json::json!({"code": 101, "data": "No text found in image."});
+        assert_eq!(parse_rapidocr_json(&error, " ").unwrap_err(), "No text found in image.");
+    }
+
+    fn output(blocks: &[(&str, f32)]) -> OcrOutput {
+        let blocks = blocks.iter()
+            .map(|&(text, score)| OcrBlock { text: text.to_string(), score, low_confidence: false })
+            .collect();
+        OcrOutput::from_blocks(blocks, " ")
+    }
+
+    #[test]
+    fn better_prefers_higher_confidence() {
+        let first = output(&[("Hel1o", 0.6)]);
+        let retry = output(&[("Hello", 0.9)]);
+        assert_eq!(first.clone().better(retry.clone()).text, "Hello");
+        assert_eq!(retry.better(first).text, "Hello");
+        // 置信度更高时即使文字更少也胜出
+        let longer = output(&[("Hello wor1d", 0.7)]);
+        assert_eq!(longer.better(output(&[("Hello", 0.8)])).text, "Hello");
+    }
+
+    #[test]
+    fn better_breaks_ties_by_text_length() {
+        let short = output(&[("Hello", 0.8)]);
+        let long = output(&[("Hello world", 0.8)]);
+        assert_eq!(short.clone().better(long.clone()).text, "Hello world");
+        assert_eq!(long.better(short).text, "Hello world");
+        // 只有空白不同的不算更多文字，完全相同时保留第一次的结果
+        let first = output(&[("a b", 0.8)]);
+        let spaced = output(&[("a  b ", 0.8)]);
+        assert_eq!(first.better(spaced).text, "a b");
+    }
+
+    #[test]
+    fn better_handles_empty_output() {
+        let empty = output(&[]);
+        assert_eq!(empty.confidence, 0.0);
+        assert_eq!(empty.clone().better(output(&[("text", 0.1)])).text, "text");
+        assert_eq!(output(&[("text", 0.1)]).better(empty.clone()).text, "text");
+        assert!(empty.clone().better(empty).blocks.is_empty());
+    }
+
+    #[test]
+    fn marks_blocks_below_the_threshold() {
+        let mut result = output(&[("low", 0.5), ("equal", 0.8), ("high", 0.95)]);
+        result.mark_low_confidence(0.8);
+        let marked: Vec<bool> = result.blocks.iter().map(|b| b.low_confidence).collect();
+        assert_eq!(marked, [true, false, false], "等于阈值的不标记");
+
+        // 重新按更低的阈值标记时清除之前的标记
+        result.mark_low_confidence(0.4);
+        assert!(result.blocks.iter().all(|b| !b.low_confidence));
+        result.mark_low_confidence(1.0);
+        assert!(result.blocks.iter().all(|b| b.low_confidence));
+    }
+}
@@ -0,0 +1,469 @@
+// --- 文件: src-tauri/src/preprocess.rs ---
+
+//! OCR 前的图像预处理
+//!
+//! 界面小字、深色主题和低对比度的游戏字体识别效果较差。
+//! 在调用 OCR 引擎前依次执行：放大小图 → 深色背景反色 → 对比度归一化 → 倾斜校正 → 二值化，
+//! 每一步都可以在设置中单独开关，默认全部关闭，以免改变已有用户的识别输入。
+
+use image::{imageops, DynamicImage, GrayImage, Luma};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// --- 常量定义 ---
+// 对比度拉伸时忽略两端各 1% 的像素，避免个别噪点影响拉伸范围
+const CONTRAST_CLIP_RATIO: f64 = 0.01;
+// 倾斜校正的搜索范围与步长（角度）
+const DESKEW_MAX_ANGLE: f32 = 10.0;
+const DESKEW_STEP: f32 = 0.5;
+// 估计出的倾斜角度小于此值时不做旋转，避免无谓的插值模糊
+const DESKEW_MIN_ANGLE: f32 = 0.4;
+// 估计倾斜角度时先把图像缩小到此宽度以内，以降低计算量
+const DESKEW_SAMPLE_WIDTH: u32 = 800;
+
+/// 预处理选项，持久化在 `AppSettings.ocr_preprocess` 中
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PreprocessOptions {
+    // 高度低于 `upscale_min_height` 的截图按 `upscale_factor` 放大
+    pub upscale: bool,
+    pub upscale_min_height: u32,
+    pub upscale_factor: u32,
+    // 深色背景（浅色文字）时自动反色为白底黑字
+    pub invert_dark: bool,
+    pub normalize_contrast: bool,
+    pub binarize: bool,
+    pub deskew: bool,
+    // 在自动反色判断的基础上再反转一次明暗，仅用于低置信度重试，不持久化
+    #[serde(skip)]
+    pub flip_polarity: bool,
+}
+
+impl Default for PreprocessOptions {
+    fn default() -> Self {
+        Self {
+            upscale: false,
+            upscale_min_height: 64,
+            upscale_factor: 2,
+            invert_dark: false,
+            normalize_contrast: false,
+            binarize: false,
+            deskew: false,
+            flip_polarity: false,
+        }
+    }
+}
+
+impl PreprocessOptions {
+    /// 是否启用了任意一个预处理步骤
+    pub fn is_enabled(&self) -> bool {
+        self.upscale || self.invert_dark || self.normalize_contrast || self.binarize || self.deskew || self.flip_polarity
+    }
+}
+
+/// 低置信度时依次尝试的备选预处理方式：强制放大两倍、明暗反转。
+pub fn retry_options(base: &PreprocessOptions) -> Vec<PreprocessOptions> {
+    let upscaled = PreprocessOptions {
+        upscale: true,
+        upscale_min_height: u32::MAX,
+        upscale_factor: 2,
+        ..base.clone()
+    };
+    let inverted = PreprocessOptions { flip_polarity: true, ..base.clone() };
+    vec![upscaled, inverted]
+}
+
+// --- 预处理流水线 ---
+
+/// 按选项对图像执行完整的预处理流水线，输出灰度图。
+pub fn preprocess(image: &DynamicImage, options: &PreprocessOptions) -> GrayImage {
+    let mut gray = image.to_luma8();
+
+    if options.upscale && gray.height() < options.upscale_min_height {
+        gray = upscale(&gray, options.upscale_factor);
+    }
+    let invert = options.invert_dark && is_dark_background(&gray);
+    if invert != options.flip_polarity {
+        imageops::invert(&mut gray);
+    }
+    if options.normalize_contrast {
+        normalize_contrast(&mut gray);
+    }
+    if options.deskew {
+        gray = deskew(&gray);
+    }
+    if options.binarize {
+        binarize(&mut gray);
+    }
+    gray
+}
+
+/// 对图片文件执行预处理，并把结果保存为同目录下的 `<原文件名>-ocr.png`。
+///
+/// # 返回
+/// - `Ok(Some(PathBuf))`: 预处理后的图片路径，调用方用完后应删除。
+/// - `Ok(None)`: 所有步骤均未启用，应直接使用原图。
+pub fn prepare_ocr_input(image_path: &Path, options: &PreprocessOptions) -> Result<Option<PathBuf>, String> {
+    if !options.is_enabled() {
+        return Ok(None);
+    }
+    let image = image::open(image_path).map_err(|e| format!("读取待预处理图片失败: {}", e))?;
+    let processed = preprocess(&image, options);
+
+    let stem = image_path.file_stem().unwrap_or_default().to_string_lossy();
+    let output_path = image_path.with_file_name(format!("{}-ocr.png", stem));
+    processed.save(&output_path).map_err(|e| format!("保存预处理图片失败: {}", e))?;
+    Ok(Some(output_path))
+}
+
+// --- 单步处理函数 ---
+
+/// 按整数倍放大图像。倍数限制在 1 ~ 4 之间。
+pub fn upscale(gray: &GrayImage, factor: u32) -> GrayImage {
+    let factor = factor.clamp(1, 4);
+    if factor == 1 {
+        return gray.clone();
+    }
+    imageops::resize(gray, gray.width() * factor, gray.height() * factor, imageops::FilterType::CatmullRom)
+}
+
+/// 判断图像是否为深色背景：超过一半的像素亮度低于中间值。
+pub fn is_dark_background(gray: &GrayImage) -> bool {
+    let total = gray.as_raw().len();
+    if total == 0 {
+        return false;
+    }
+    let dark = gray.as_raw().iter().filter(|&&v| v < 128).count();
+    dark * 2 > total
+}
+
+/// 对比度归一化：把 1% ~ 99% 分位之间的亮度线性拉伸到 0 ~ 255。
+pub fn normalize_contrast(gray: &mut GrayImage) {
+    let histogram = histogram(gray);
+    let total = gray.as_raw().len() as u64;
+    if total == 0 {
+        return;
+    }
+    let clip = (total as f64 * CONTRAST_CLIP_RATIO) as u64;
+
+    let low = percentile_from_start(&histogram, clip);
+    let high = 255 - percentile_from_start(&reversed(&histogram), clip);
+    if high <= low {
+        return;
+    }
+
+    let range = (high - low) as f32;
+    for pixel in gray.pixels_mut() {
+        let v = pixel.0[0].clamp(low, high);
+        pixel.0[0] = (((v - low) as f32 / range) * 255.0).round() as u8;
+    }
+}
+
+/// 使用 Otsu 方法自动选取阈值，将图像二值化为纯黑白。
+pub fn binarize(gray: &mut GrayImage) {
+    let threshold = otsu_threshold(gray);
+    for pixel in gray.pixels_mut() {
+        pixel.0[0] = if pixel.0[0] > threshold { 255 } else { 0 };
+    }
+}
+
+/// 计算 Otsu 阈值：使前景与背景两类的类间方差最大。
+pub fn otsu_threshold(gray: &GrayImage) -> u8 {
+    let histogram = histogram(gray);
+    let total = gray.as_raw().len() as f64;
+    if total == 0.0 {
+        return 127;
+    }
+    let sum_all: f64 = histogram.iter().enumerate().map(|(i, &c)| i as f64 * c as f64).sum();
+
+    let mut weight_bg = 0.0;
+    let mut sum_bg = 0.0;
+    let mut best_threshold = 127u8;
+    let mut best_variance = -1.0;
+    for (t, &count) in histogram.iter().enumerate() {
+        weight_bg += count as f64;
+        if weight_bg == 0.0 {
+            continue;
+        }
+        let weight_fg = total - weight_bg;
+        if weight_fg == 0.0 {
+            break;
+        }
+        sum_bg += t as f64 * count as f64;
+        let mean_bg = sum_bg / weight_bg;
+        let mean_fg = (sum_all - sum_bg) / weight_fg;
+        let variance = weight_bg * weight_fg * (mean_bg - mean_fg).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_threshold = t as u8;
+        }
+    }
+    best_threshold
+}
+
+/// 估计文字行的倾斜角度（度，顺时针为正）。
+///
+/// 对每个候选角度，把深色像素投影到旋转后的纵轴上，文字行与行间空白分得越清楚，
+/// 投影直方图的平方和越大。取平方和最大的角度。
+pub fn estimate_skew_angle(gray: &GrayImage) -> f32 {
+    let sample = if gray.width() > DESKEW_SAMPLE_WIDTH {
+        let height = (gray.height() as u64 * DESKEW_SAMPLE_WIDTH as u64 / gray.width() as u64).max(1) as u32;
+        imageops::resize(gray, DESKEW_SAMPLE_WIDTH, height, imageops::FilterType::Triangle)
+    } else {
+        gray.clone()
+    };
+
+    // 以多数像素为背景，收集前景（文字）像素坐标
+    let dark_background = is_dark_background(&sample);
+    let points: Vec<(f32, f32)> = sample.enumerate_pixels()
+        .filter(|(_, _, p)| if dark_background { p.0[0] >= 128 } else { p.0[0] < 128 })
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if points.is_empty() {
+        return 0.0;
+    }
+
+    let diagonal = ((sample.width().pow(2) + sample.height().pow(2)) as f32).sqrt().ceil() as usize;
+    let steps = (DESKEW_MAX_ANGLE / DESKEW_STEP).round() as i32;
+    let mut best_angle = 0.0;
+    let mut best_score = 0.0f64;
+    for step in -steps..=steps {
+        let angle = step as f32 * DESKEW_STEP;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let mut bins = vec![0u32; diagonal * 2 + 1];
+        for &(x, y) in &points {
+            let projected = y * cos - x * sin + diagonal as f32;
+            if let Some(bin) = bins.get_mut(projected.round().max(0.0) as usize) {
+                *bin += 1;
+            }
+        }
+        let score: f64 = bins.iter().map(|&c| (c as f64).powi(2)).sum();
+        if score > best_score {
+            best_score = score;
+            best_angle = angle;
+        }
+    }
+    best_angle
+}
+
+/// 倾斜校正：估计倾斜角度并反向旋转，空出的区域用背景色填充。
+pub fn deskew(gray: &GrayImage) -> GrayImage {
+    let angle = estimate_skew_angle(gray);
+    if angle.abs() < DESKEW_MIN_ANGLE {
+        return gray.clone();
+    }
+    let background = if is_dark_background(gray) { 0 } else { 255 };
+    rotate(gray, -angle, background)
+}
+
+/// 绕图像中心旋转（度，顺时针为正），保持原尺寸，使用双线性插值。
+pub fn rotate(gray: &GrayImage, angle_degrees: f32, fill: u8) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let (sin, cos) = angle_degrees.to_radians().sin_cos();
+    let cx = (width as f32 - 1.0) / 2.0;
+    let cy = (height as f32 - 1.0) / 2.0;
+
+    GrayImage::from_fn(width, height, |x, y| {
+        // 反向映射：目标像素在原图中的位置
+        let dx = x as f32 - cx;
+        let dy = y as f32 - cy;
+        let sx = dx * cos + dy * sin + cx;
+        let sy = -dx * sin + dy * cos + cy;
+        Luma([sample_bilinear(gray, sx, sy, fill)])
+    })
+}
+
+fn sample_bilinear(gray: &GrayImage, x: f32, y: f32, fill: u8) -> u8 {
+    if x < 0.0 || y < 0.0 || x > (gray.width() - 1) as f32 || y > (gray.height() - 1) as f32 {
+        return fill;
+    }
+    let x0 = x.floor() as u32;
+    let y0 = y.floor() as u32;
+    let x1 = (x0 + 1).min(gray.width() - 1);
+    let y1 = (y0 + 1).min(gray.height() - 1);
+    let fx = x - x0 as f32;
+    let fy = y - y0 as f32;
+
+    let p = |px: u32, py: u32| gray.get_pixel(px, py).0[0] as f32;
+    let top = p(x0, y0) * (1.0 - fx) + p(x1, y0) * fx;
+    let bottom = p(x0, y1) * (1.0 - fx) + p(x1, y1) * fx;
+    (top * (1.0 - fy) + bottom * fy).round() as u8
+}
+
+// --- 辅助函数 ---
+
+fn histogram(gray: &GrayImage) -> [u64; 256] {
+    let mut histogram = [0u64; 256];
+    for &v in gray.as_raw() {
+        histogram[v as usize] += 1;
+    }
+    histogram
+}
+
+fn reversed(histogram: &[u64; 256]) -> [u64; 256] {
+    let mut reversed = *histogram;
+    reversed.reverse();
+    reversed
+}
+
+/// 从直方图起点开始累加，返回累计数量首次超过 `clip` 的亮度值
+fn percentile_from_start(histogram: &[u64; 256], clip: u64) -> u8 {
+    let mut accumulated = 0;
+    for (value, &count) in histogram.iter().enumerate() {
+        accumulated += count;
+        if accumulated > clip {
+            return value as u8;
+        }
+    }
+    255
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成 `background` 底色上有一条 `text` 颜色横线（模拟一行文字）的灰度图
+    fn text_line(width: u32, height: u32, background: u8, text: u8) -> GrayImage {
+        GrayImage::from_fn(width, height, |_, y| {
+            if y >= height / 3 && y < height / 2 { Luma([text]) } else { Luma([background]) }
+        })
+    }
+
+    #[test]
+    fn defaults_leave_the_image_untouched() {
+        let options = PreprocessOptions::default();
+        assert!(!options.is_enabled());
+        let image = text_line(50, 20, 30, 220);
+        assert_eq!(preprocess(&DynamicImage::ImageLuma8(image.clone()), &options), image);
+    }
+
+    #[test]
+    fn dark_background_is_inverted() {
+        let dark = text_line(60, 30, 20, 230);
+        assert!(is_dark_background(&dark));
+        let options = PreprocessOptions { invert_dark: true, ..Default::default() };
+        let processed = preprocess(&DynamicImage::ImageLuma8(dark), &options);
+        assert!(!is_dark_background(&processed));
+        assert_eq!(processed.get_pixel(0, 0).0[0], 255 - 20, "背景变为浅色");
+        assert_eq!(processed.get_pixel(0, 10).0[0], 255 - 230, "文字变为深色");
+    }
+
+    #[test]
+    fn light_background_is_not_inverted() {
+        let light = text_line(60, 30, 240, 10);
+        assert!(!is_dark_background(&light));
+        let options = PreprocessOptions { invert_dark: true, ..Default::default() };
+        assert_eq!(preprocess(&DynamicImage::ImageLuma8(light.clone()), &options), light);
+    }
+
+    #[test]
+    fn flip_polarity_inverts_light_background() {
+        let light = text_line(60, 30, 240, 10);
+        let options = PreprocessOptions { invert_dark: true, flip_polarity: true, ..Default::default() };
+        assert!(is_dark_background(&preprocess(&DynamicImage::ImageLuma8(light), &options)));
+    }
+
+    #[test]
+    fn upscale_applies_only_below_the_height_threshold() {
+        let options = PreprocessOptions { upscale: true, upscale_min_height: 64, upscale_factor: 2, ..Default::default() };
+
+        let small = DynamicImage::ImageLuma8(text_line(100, 63, 255, 0));
+        assert_eq!(preprocess(&small, &options).dimensions(), (200, 126));
+
+        let tall_enough = DynamicImage::ImageLuma8(text_line(100, 64, 255, 0));
+        assert_eq!(preprocess(&tall_enough, &options).dimensions(), (100, 64));
+    }
+
+    #[test]
+    fn upscale_factor_is_clamped() {
+        let image = text_line(10, 10, 255, 0);
+        assert_eq!(upscale(&image, 0).dimensions(), (10, 10));
+        assert_eq!(upscale(&image, 9).dimensions(), (40, 40));
+    }
+
+    /// 白底上若干行由黑色短块组成的“文字”
+    fn text_block(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            let in_line = y >= 40 && y < height - 40 && (y - 40) % 30 < 12;
+            let in_glyph = x >= 30 && x < width - 30 && (x - 30) % 16 < 11;
+            if in_line && in_glyph { Luma([0]) } else { Luma([255]) }
+        })
+    }
+
+    #[test]
+    fn normalize_contrast_stretches_to_full_range() {
+        // 100 ~ 150 的亮度渐变，每个亮度值占一列
+        let mut image = GrayImage::from_fn(51, 10, |x, _| Luma([100 + x as u8]));
+        normalize_contrast(&mut image);
+        let values = image.as_raw();
+        assert_eq!(values.iter().min(), Some(&0));
+        assert_eq!(values.iter().max(), Some(&255));
+        assert_eq!(image.get_pixel(25, 0).0[0], 128, "中间亮度映射到中间");
+        let row: Vec<u8> = (0..51).map(|x| image.get_pixel(x, 0).0[0]).collect();
+        assert!(row.windows(2).all(|pair| pair[0] < pair[1]), "拉伸后仍保持亮度顺序");
+    }
+
+    #[test]
+    fn normalize_contrast_ignores_a_flat_image() {
+        let mut image = GrayImage::from_pixel(10, 10, Luma([90]));
+        normalize_contrast(&mut image);
+        assert!(image.as_raw().iter().all(|&v| v == 90));
+    }
+
+    #[test]
+    fn otsu_threshold_separates_two_modes() {
+        // 亮度集中在 50 ~ 70 与 190 ~ 210 两处
+        let image = GrayImage::from_fn(84, 20, |x, y| {
+            let jitter = ((x * 7 + y * 3) % 21) as u8;
+            if x < 42 { Luma([50 + jitter]) } else { Luma([190 + jitter]) }
+        });
+        let threshold = otsu_threshold(&image);
+        assert!((70..190).contains(&threshold), "阈值 {}", threshold);
+
+        let mut binary = image.clone();
+        binarize(&mut binary);
+        assert_eq!(binary.get_pixel(0, 0).0[0], 0);
+        assert_eq!(binary.get_pixel(83, 19).0[0], 255);
+    }
+
+    #[test]
+    fn rotate_keeps_size_and_turns_around_the_center() {
+        let image = GrayImage::from_fn(21, 11, |x, y| Luma([(x * 10 + y) as u8]));
+        assert_eq!(rotate(&image, 0.0, 255), image);
+        let turned = rotate(&image, 180.0, 255);
+        assert_eq!(turned.dimensions(), (21, 11));
+        assert_eq!(turned.get_pixel(0, 0), image.get_pixel(20, 10));
+        assert_eq!(turned.get_pixel(10, 5), image.get_pixel(10, 5));
+        // 转出原图范围的部分使用填充色
+        assert_eq!(rotate(&image, 90.0, 77).get_pixel(0, 0).0[0], 77);
+    }
+
+    #[test]
+    fn estimates_a_known_skew_angle() {
+        let straight = text_block(400, 220);
+        assert_eq!(estimate_skew_angle(&straight), 0.0);
+        for angle in [3.0f32, -4.5] {
+            let skewed = rotate(&straight, angle, 255);
+            let estimated = estimate_skew_angle(&skewed);
+            assert!((estimated - angle).abs() <= DESKEW_STEP, "旋转 {} 度，估计为 {} 度", angle, estimated);
+        }
+    }
+
+    #[test]
+    fn deskew_straightens_rotated_text() {
+        let straight = text_block(400, 220);
+        assert_eq!(deskew(&straight), straight, "没有倾斜时不旋转");
+
+        let corrected = deskew(&rotate(&straight, 5.0, 255));
+        assert_eq!(corrected.dimensions(), straight.dimensions());
+        assert!(estimate_skew_angle(&corrected).abs() < DESKEW_MIN_ANGLE + DESKEW_STEP);
+        assert_eq!(corrected.get_pixel(0, 0).0[0], 255, "空出的角落用背景色填充");
+    }
+
+    #[test]
+    fn binarize_outputs_only_black_and_white() {
+        let mut image = GrayImage::from_fn(32, 8, |x, _| Luma([(x * 8) as u8]));
+        binarize(&mut image);
+        assert!(image.as_raw().iter().all(|&v| v == 0 || v == 255));
+    }
+}
@@ -0,0 +1,207 @@
+// --- 文件: src-tauri/src/regions.rs ---
+
+//! 命名保存区域
+//!
+//! 用户可以把常用的屏幕区域（如“聊天框”、“字幕栏”）保存下来，
+//! 每个区域可以覆盖全局的首要动作和目标语言，并可绑定独立的全局快捷键。
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Manager, State};
+
+use crate::actions::PrimaryAction;
+use crate::commands;
+use crate::settings::{AppSettings, AppState, SettingsError};
+
+/// 用户保存的命名区域，持久化在 `AppSettings.saved_regions` 中
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedRegion {
+    pub name: String,
+    // 区域所在的显示器名称，为空时使用主显示器
+    #[serde(default)]
+    pub monitor: Option<String>,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    // 覆盖全局的首要动作，为空时沿用全局设置
+    #[serde(default)]
+    pub action: Option<PrimaryAction>,
+    // 覆盖全局的目标语言，为空时沿用全局设置
+    #[serde(default)]
+    pub target_lang: Option<String>,
+    // 可选的独立全局快捷键
+    #[serde(default)]
+    pub shortcut: Option<String>,
+}
+
+impl SavedRegion {
+    /// 在全局设置的基础上应用本区域的动作与语言覆盖
+    pub fn apply_overrides(&self, settings: &AppSettings) -> AppSettings {
+        let mut effective = settings.clone();
+        if let Some(action) = &self.action {
+            effective.primary_action = action.clone();
+        }
+        if let Some(lang) = &self.target_lang {
+            effective.target_lang = lang.clone();
+        }
+        effective
+    }
+}
+
+// --- 事件 Payload 定义 ---
+#[derive(Clone, Serialize)]
+struct SavedRegionsChangedPayload { regions: Vec<SavedRegion> }
+
+// --- Tauri 命令定义 ---
+
+/// 打开选区遮罩，框选一个新的区域并保存
+#[tauri::command]
+pub fn select_region_to_save(app: AppHandle) -> Result<(), String> {
+    if crate::begin_screen_selection(&app, "region") {
+        Ok(())
+    } else {
+        Err("截图正在进行中，请稍后再试。".to_string())
+    }
+}
+
+/// 由选区遮罩调用：将刚框选的区域保存为一个新的命名区域。
+/// 与 `set_settings` 一样先校验整份设置，选区为空等无效设置不会被保存。
+#[tauri::command]
+pub async fn save_region_from_selection(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    x: f64, y: f64, width: f64, height: f64,
+) -> Result<SavedRegion, String> {
+    // 选区已经完成，释放截图锁并丢弃缓存的全屏截图
+    state.fullscreen_capture.lock().unwrap().take();
+    state.is_capturing.store(false, Ordering::SeqCst);
+
+    let validated = {
+        let mut app_settings = state.settings.lock().unwrap();
+        let mut updated = app_settings.clone();
+        let region = SavedRegion {
+            name: next_region_name(&updated.saved_regions),
+            monitor: crate::capture::primary_monitor_name(),
+            x: x as u32,
+            y: y as u32,
+            width: width as u32,
+            height: height as u32,
+            action: None,
+            target_lang: None,
+            shortcut: None,
+        };
+        println!("[REGIONS] 保存新区域: {:?}", region);
+        updated.saved_regions.push(region);
+        match updated.validate() {
+            Ok(()) => {
+                *app_settings = updated.clone();
+                Ok(updated)
+            }
+            Err(errors) => Err(SettingsError::from(errors).message),
+        }
+    };
+    let settings = match validated {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("[REGIONS] 区域未保存: {}", message);
+            commands::send_notification(&app, "❌ 区域未保存", &message);
+            return Err(message);
+        }
+    };
+    settings.save(&app.path_resolver()).map_err(|e| format!("保存设置文件失败: {}", e))?;
+
+    if let Some(main_window) = app.get_window("main") {
+        let _ = main_window.emit("saved-regions-changed", SavedRegionsChangedPayload { regions: settings.saved_regions.clone() });
+        let _ = main_window.show();
+        let _ = main_window.set_focus();
+    }
+
+    Ok(settings.saved_regions.last().cloned().unwrap())
+}
+
+/// 立即截取指定名称的保存区域并执行其动作
+#[tauri::command]
+pub async fn capture_saved_region(app: AppHandle, name: String) -> Result<(), String> {
+    run_saved_region(&app, &name).await
+}
+
+// --- 辅助函数 ---
+
+/// 截取保存区域并按区域（或全局）的首要动作处理。
+///
+/// 与快捷键截图共用截图锁，避免与正在进行的选区操作冲突。
+pub async fn run_saved_region(app: &AppHandle, name: &str) -> Result<(), String> {
+    let state: State<AppState> = app.state();
+    let (region, settings) = {
+        let app_settings = state.settings.lock().unwrap();
+        let region = app_settings.saved_regions.iter()
+            .find(|r| r.name == name)
+            .cloned()
+            .ok_or_else(|| format!("找不到名为「{}」的保存区域", name))?;
+        let effective = region.apply_overrides(&app_settings);
+        (region, effective)
+    };
+
+    println!("[REGIONS] 截取保存区域「{}」: {:?}", name, region);
+    commands::capture_and_process_region(
+        app, region.monitor.clone(), region.x, region.y, region.width, region.height, &settings,
+    ).await
+}
+
+/// 生成一个不与现有区域重名的默认名称，如“区域 3”
+fn next_region_name(regions: &[SavedRegion]) -> String {
+    (1..)
+        .map(|i| format!("区域 {}", i))
+        .find(|candidate| !regions.iter().any(|r| &r.name == candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(name: &str) -> SavedRegion {
+        SavedRegion {
+            name: name.to_string(),
+            monitor: None,
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 50,
+            action: None,
+            target_lang: None,
+            shortcut: None,
+        }
+    }
+
+    #[test]
+    fn next_region_name_skips_used_names() {
+        assert_eq!(next_region_name(&[]), "区域 1");
+        assert_eq!(next_region_name(&[region("区域 1"), region("区域 2")]), "区域 3");
+        assert_eq!(next_region_name(&[region("区域 2"), region("字幕栏")]), "区域 1");
+        assert_eq!(next_region_name(&[region("区域 1"), region("区域 3")]), "区域 2");
+    }
+
+    #[test]
+    fn apply_overrides_replaces_only_the_set_fields() {
+        let settings = AppSettings { target_lang: "zh".to_string(), ..AppSettings::default() };
+
+        let effective = region("A").apply_overrides(&settings);
+        assert_eq!(effective.primary_action, settings.primary_action);
+        assert_eq!(effective.target_lang, "zh");
+
+        let overriding = SavedRegion {
+            action: Some(PrimaryAction::Copy),
+            target_lang: Some("ja".to_string()),
+            ..region("B")
+        };
+        let effective = overriding.apply_overrides(&settings);
+        assert_eq!(effective.primary_action, PrimaryAction::Copy);
+        assert_eq!(effective.target_lang, "ja");
+        assert_eq!(effective.shortcut, settings.shortcut, "其它设置不变");
+
+        let action_only = SavedRegion { action: Some(PrimaryAction::Save), ..region("C") };
+        assert_eq!(action_only.apply_overrides(&settings).target_lang, "zh");
+    }
+}
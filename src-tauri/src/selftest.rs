@@ -0,0 +1,631 @@
+// --- 文件: src-tauri/src/selftest.rs ---
+
+//! 引擎自检
+//!
+//! 实际运行每个引擎：OCR 引擎识别随程序打包的样例图片，翻译引擎翻译一句样例文本，
+//! 记录耗时并检查输出的 JSON 结构。失败时根据启动错误、退出码、stderr 和输出编码
+//! 给出结构化的诊断（如 Windows 上缺少 VC++ 运行库时的 0xC0000135），
+//! 用户可以把整份报告附在问题反馈中。
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command as StdCommand, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::AsyncWriteExt;
+
+use crate::encoding::decode_engine_output_detailed;
+use crate::engine::{self, EngineId};
+use crate::ocr;
+use crate::settings::{AppSettings, AppState};
+use crate::translator::is_unsupported_argument_error;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+// --- 常量定义 ---
+// 样例图片，内容为 "OCR TEST 123"
+const SAMPLE_IMAGE: &[u8] = include_bytes!("../assets/selftest.png");
+// 识别结果中应包含的文字（忽略空白与大小写）
+const SAMPLE_IMAGE_TEXT: &str = "OCRTEST123";
+const SAMPLE_TEXT: &str = "Hello, world.";
+const OCR_TIMEOUT_SECS: u64 = 60;
+// 翻译引擎首次运行需要加载模型
+const TRANSLATOR_TIMEOUT_SECS: u64 = 180;
+// 报告中保留的输出长度
+const OUTPUT_EXCERPT_CHARS: usize = 500;
+
+// Windows 进程退出码 (NTSTATUS)
+const STATUS_ACCESS_VIOLATION: u32 = 0xC000_0005;
+const STATUS_ILLEGAL_INSTRUCTION: u32 = 0xC000_001D;
+const STATUS_INVALID_IMAGE_FORMAT: u32 = 0xC000_007B;
+const STATUS_DLL_NOT_FOUND: u32 = 0xC000_0135;
+const STATUS_ENTRYPOINT_NOT_FOUND: u32 = 0xC000_0139;
+const STATUS_STACK_OVERFLOW: u32 = 0xC000_00FD;
+const STATUS_STACK_BUFFER_OVERRUN: u32 = 0xC000_0409;
+
+/// 自检结论
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Diagnosis {
+    Ok,
+    // 运行正常，但识别结果与样例不符
+    UnexpectedResult,
+    NotInstalled,
+    // 无法启动：文件损坏、平台或 CPU 架构不符、没有执行权限
+    BadExecutable,
+    // 缺少 DLL / 共享库
+    MissingDependency,
+    // 进程异常终止（访问冲突、信号等）
+    Crashed,
+    Timeout,
+    // 引擎正常退出，但报告了错误
+    EngineError,
+    // 输出无法按 UTF-8 或设置中的代码页正确解码
+    BadEncoding,
+    // 输出不是预期结构的 JSON
+    BadResponse,
+}
+
+/// 单个引擎的自检结果
+#[derive(Serialize, Clone, Debug)]
+pub struct EngineTestResult {
+    // ocr / translator / tesseract
+    pub engine: String,
+    pub display_name: &'static str,
+    pub diagnosis: Diagnosis,
+    // 引擎能够运行且输出结构正确（识别结果不符只是警告）
+    pub passed: bool,
+    // 面向用户的说明与建议
+    pub message: String,
+    pub exe_path: Option<String>,
+    pub version: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub exit_code: Option<i32>,
+    // 实际检测到的输出编码
+    pub output_encoding: Option<String>,
+    pub stdout_excerpt: String,
+    pub stderr_excerpt: String,
+}
+
+/// 完整的自检报告
+#[derive(Serialize, Clone, Debug)]
+pub struct SelfTestReport {
+    pub app_version: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    // 自检时间 (Unix 秒)
+    pub tested_at: u64,
+    pub results: Vec<EngineTestResult>,
+}
+
+impl EngineTestResult {
+    fn new(engine: &str, display_name: &'static str, exe_path: Option<&Path>) -> Self {
+        Self {
+            engine: engine.to_string(),
+            display_name,
+            diagnosis: Diagnosis::Ok,
+            passed: false,
+            message: String::new(),
+            exe_path: exe_path.map(|p| p.to_string_lossy().into_owned()),
+            version: None,
+            latency_ms: None,
+            exit_code: None,
+            output_encoding: None,
+            stdout_excerpt: String::new(),
+            stderr_excerpt: String::new(),
+        }
+    }
+
+    fn finish(mut self, diagnosis: Diagnosis, message: impl Into<String>) -> Self {
+        self.passed = matches!(diagnosis, Diagnosis::Ok | Diagnosis::UnexpectedResult);
+        self.diagnosis = diagnosis;
+        self.message = message.into();
+        println!("[SELFTEST] {}: {:?} - {}", self.display_name, self.diagnosis, self.message);
+        self
+    }
+}
+
+/// 一次引擎进程的运行结果
+struct ProcessRun {
+    latency: Duration,
+    // 超时时为 None（进程已被结束）
+    status: Option<ExitStatus>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// 运行引擎进程并等待退出，超时后结束进程
+async fn run_process(command: StdCommand, stdin: Option<&[u8]>, timeout: Duration) -> io::Result<ProcessRun> {
+    let mut command = tokio::process::Command::from(command);
+    command
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let started = Instant::now();
+    let mut child = command.spawn()?;
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), stdin) {
+        // 引擎不读取 stdin 时写入会失败，交给退出状态判断
+        let _ = pipe.write_all(input).await;
+    }
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            let output = output?;
+            Ok(ProcessRun { latency: started.elapsed(), status: Some(output.status), stdout: output.stdout, stderr: output.stderr })
+        }
+        Err(_) => Ok(ProcessRun { latency: started.elapsed(), status: None, stdout: Vec::new(), stderr: Vec::new() }),
+    }
+}
+
+/// 以可执行文件所在目录为工作目录的引擎命令
+fn engine_command(exe_path: &Path) -> StdCommand {
+    let mut command = StdCommand::new(exe_path);
+    command.current_dir(exe_path.parent().unwrap_or_else(|| Path::new(".")));
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    command
+}
+
+/// 进程无法启动时的诊断
+fn diagnose_spawn_error(e: &io::Error) -> (Diagnosis, String) {
+    // ERROR_BAD_EXE_FORMAT (Windows) / ENOEXEC (Unix)
+    let bad_format = if cfg!(windows) { 193 } else { 8 };
+    let message = match e.kind() {
+        io::ErrorKind::NotFound => "找不到可执行文件，引擎可能未完整安装。".to_string(),
+        io::ErrorKind::PermissionDenied => {
+            "没有权限运行引擎：Linux / macOS 上请检查文件的可执行权限，Windows 上可能被安全软件拦截。".to_string()
+        }
+        _ if e.raw_os_error() == Some(bad_format) => "不是当前平台可以运行的程序：平台或 CPU 架构不符，或文件已损坏，请重新下载引擎。".to_string(),
+        _ => format!("无法启动引擎: {}", e),
+    };
+    (Diagnosis::BadExecutable, message)
+}
+
+/// 进程以失败状态退出时的诊断
+fn diagnose_exit(status: ExitStatus, stderr: &str) -> (Diagnosis, String) {
+    // Linux 的动态链接器 / macOS 的 dyld 找不到共享库
+    let lower = stderr.to_lowercase();
+    if lower.contains("error while loading shared libraries") || lower.contains("library not loaded") {
+        return (Diagnosis::MissingDependency, format!("缺少共享库: {}", last_line(stderr)));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            let hint = match signal {
+                4 => "（非法指令，CPU 可能不支持引擎使用的指令集，如 AVX）",
+                11 => "（段错误）",
+                9 => "（被强制结束，可能是内存不足）",
+                _ => "",
+            };
+            return (Diagnosis::Crashed, format!("引擎被信号 {} 终止{}。", signal, hint));
+        }
+    }
+
+    match status.code() {
+        Some(code) => diagnose_exit_code(code, stderr),
+        None => (Diagnosis::Crashed, "引擎异常终止。".to_string()),
+    }
+}
+
+/// 按退出码诊断。Windows 上进程异常终止时退出码为 NTSTATUS。
+fn diagnose_exit_code(code: i32, stderr: &str) -> (Diagnosis, String) {
+    let (diagnosis, reason) = match code as u32 {
+        STATUS_DLL_NOT_FOUND => (Diagnosis::MissingDependency,
+            "缺少 DLL (STATUS_DLL_NOT_FOUND)，请安装最新的 Microsoft Visual C++ 运行库，并确认引擎目录完整。"),
+        STATUS_ENTRYPOINT_NOT_FOUND => (Diagnosis::MissingDependency,
+            "DLL 版本不匹配 (STATUS_ENTRYPOINT_NOT_FOUND)，请更新 Microsoft Visual C++ 运行库或系统。"),
+        STATUS_INVALID_IMAGE_FORMAT => (Diagnosis::BadExecutable,
+            "引擎或其 DLL 的 32 / 64 位不匹配或已损坏 (STATUS_INVALID_IMAGE_FORMAT)，请重新下载引擎。"),
+        STATUS_ILLEGAL_INSTRUCTION => (Diagnosis::Crashed,
+            "CPU 不支持引擎使用的指令集 (STATUS_ILLEGAL_INSTRUCTION)，如 AVX。"),
+        STATUS_ACCESS_VIOLATION => (Diagnosis::Crashed, "引擎崩溃 (STATUS_ACCESS_VIOLATION)。"),
+        STATUS_STACK_OVERFLOW => (Diagnosis::Crashed, "引擎崩溃 (STATUS_STACK_OVERFLOW)。"),
+        STATUS_STACK_BUFFER_OVERRUN => (Diagnosis::Crashed, "引擎崩溃 (STATUS_STACK_BUFFER_OVERRUN)。"),
+        c if c >= 0xC000_0000 => (Diagnosis::Crashed, "引擎异常终止。"),
+        _ => {
+            let detail = last_line(stderr);
+            let message = if detail.is_empty() {
+                format!("引擎返回退出码 {}。", code)
+            } else {
+                format!("引擎返回退出码 {}: {}", code, detail)
+            };
+            return (Diagnosis::EngineError, message);
+        }
+    };
+    (diagnosis, format!("{} 退出码 0x{:08X}。", reason, code as u32))
+}
+
+fn last_line(text: &str) -> &str {
+    text.trim().lines().last().unwrap_or_default().trim()
+}
+
+fn excerpt(text: &str) -> String {
+    let trimmed = text.trim();
+    match trimmed.char_indices().nth(OUTPUT_EXCERPT_CHARS) {
+        Some((index, _)) => format!("{}…", &trimmed[..index]),
+        None => trimmed.to_string(),
+    }
+}
+
+/// 记录进程的输出与退出状态。进程未能正常结束时返回诊断后的结果。
+///
+/// 返回解码后的 stdout，以及解码时是否遇到非法字节。
+fn record_run(result: &mut EngineTestResult, run: &ProcessRun, settings: &AppSettings) -> Result<(String, bool), (Diagnosis, String)> {
+    let stdout = decode_engine_output_detailed(&run.stdout, &settings.engine_output_encoding);
+    let stderr = decode_engine_output_detailed(&run.stderr, &settings.engine_output_encoding);
+    result.latency_ms = Some(run.latency.as_millis() as u64);
+    result.exit_code = run.status.and_then(|s| s.code());
+    result.output_encoding = Some(stdout.encoding.to_string());
+    result.stdout_excerpt = excerpt(&stdout.text);
+    result.stderr_excerpt = excerpt(&stderr.text);
+
+    match run.status {
+        None => Err((Diagnosis::Timeout, format!("引擎在 {} 秒内没有完成。", run.latency.as_secs()))),
+        Some(status) if !status.success() => Err(diagnose_exit(status, &stderr.text)),
+        Some(_) => Ok((stdout.text, stdout.had_errors)),
+    }
+}
+
+/// 输出无法解析为 JSON 时，区分编码问题与格式问题
+fn unparsable_output(result: &EngineTestResult, had_errors: bool) -> (Diagnosis, String) {
+    let encoding = result.output_encoding.clone().unwrap_or_default();
+    if had_errors || (encoding != "UTF-8" && !result.stdout_excerpt.is_ascii()) {
+        (Diagnosis::BadEncoding, format!(
+            "引擎输出不是 UTF-8，按 {} 解码后仍无法解析，请在设置中选择与系统语言一致的 \"引擎输出编码\"。",
+            encoding,
+        ))
+    } else if result.stdout_excerpt.is_empty() {
+        (Diagnosis::BadResponse, "引擎没有任何输出。".to_string())
+    } else {
+        (Diagnosis::BadResponse, "引擎输出不是有效的 JSON，引擎版本可能与本程序不兼容。".to_string())
+    }
+}
+
+// --- 各引擎的自检 ---
+
+/// 解析可执行文件路径，未安装时直接返回结论
+fn locate(app: &AppHandle, settings: &AppSettings, id: EngineId) -> Result<PathBuf, EngineTestResult> {
+    let name = engine::spec(id).display_name;
+    match engine::resolve_exe(app, settings, id) {
+        Ok(path) if path.is_file() => Ok(path),
+        Ok(path) => Err(EngineTestResult::new(id.as_str(), name, Some(&path))
+            .finish(Diagnosis::NotInstalled, "引擎未安装，请在设置页面下载。")),
+        Err(e) => Err(EngineTestResult::new(id.as_str(), name, None).finish(Diagnosis::NotInstalled, e)),
+    }
+}
+
+async fn test_rapidocr(app: &AppHandle, settings: &AppSettings, sample_image: &Path) -> EngineTestResult {
+    let exe_path = match locate(app, settings, EngineId::Ocr) {
+        Ok(path) => path,
+        Err(result) => return result,
+    };
+    let mut result = EngineTestResult::new(EngineId::Ocr.as_str(), engine::spec(EngineId::Ocr).display_name, Some(&exe_path));
+    result.version = engine::read_installed(app).remove(&EngineId::Ocr).map(|r| r.version);
+
+    let mut command = engine_command(&exe_path);
+    command.arg(format!("--image_path={}", sample_image.to_string_lossy()));
+    let run = match run_process(command, None, Duration::from_secs(OCR_TIMEOUT_SECS)).await {
+        Ok(run) => run,
+        Err(e) => {
+            let (diagnosis, message) = diagnose_spawn_error(&e);
+            return result.finish(diagnosis, message);
+        }
+    };
+    let (stdout, had_errors) = match record_run(&mut result, &run, settings) {
+        Ok(output) => output,
+        Err((diagnosis, message)) => return result.finish(diagnosis, message),
+    };
+
+    // 与 RapidOcrEngine 相同，取第一行 JSON
+    let value = stdout.lines()
+        .find(|line| line.trim_start().starts_with('{'))
+        .and_then(|line| serde_json::from_str::<Value>(line.trim()).ok());
+    let value = match value {
+        Some(value) => value,
+        None => {
+            let (diagnosis, message) = unparsable_output(&result, had_errors);
+            return result.finish(diagnosis, message);
+        }
+    };
+    let (diagnosis, message) = check_rapidocr_response(&value);
+    result.finish(diagnosis, message)
+}
+
+/// 检查 RapidOCR-json 的输出结构：`{"code": 100, "data": [{"box": [...], "score": .., "text": ".."}]}`
+fn check_rapidocr_response(value: &Value) -> (Diagnosis, String) {
+    let code = match value["code"].as_i64() {
+        Some(code) => code,
+        None => return (Diagnosis::BadResponse, "输出缺少整数字段 code。".to_string()),
+    };
+    if code != 100 {
+        let detail = value["data"].as_str().unwrap_or("未知错误");
+        // 101 表示没有识别到文字
+        let diagnosis = if code == 101 { Diagnosis::UnexpectedResult } else { Diagnosis::EngineError };
+        return (diagnosis, format!("引擎返回 code {}: {}", code, detail));
+    }
+    let items = match value["data"].as_array() {
+        Some(items) => items,
+        None => return (Diagnosis::BadResponse, "code 为 100 时 data 应为数组。".to_string()),
+    };
+    let malformed = items.iter().position(|item| {
+        !item["text"].is_string()
+            || !(item["score"].is_number() || item["score"].is_string())
+            || !item["box"].is_array()
+    });
+    if let Some(index) = malformed {
+        return (Diagnosis::BadResponse, format!("data[{}] 缺少 text / score / box 字段。", index));
+    }
+    let text: String = items.iter().filter_map(|item| item["text"].as_str()).collect();
+    check_sample_text(&text)
+}
+
+/// 比较识别结果与样例图片中的文字
+fn check_sample_text(text: &str) -> (Diagnosis, String) {
+    let normalized: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+    if normalized.contains(SAMPLE_IMAGE_TEXT) {
+        (Diagnosis::Ok, format!("识别正常: \"{}\"", text.trim()))
+    } else {
+        (Diagnosis::UnexpectedResult, format!("引擎运行正常，但识别结果 \"{}\" 与样例不符。", text.trim()))
+    }
+}
+
+async fn test_tesseract(settings: &AppSettings, sample_image: &Path) -> EngineTestResult {
+    let program = ocr::tesseract_program(settings);
+    let mut result = EngineTestResult::new("tesseract", "Tesseract", Some(Path::new(&program)));
+
+    let mut version_command = ocr::tesseract_command(&program);
+    version_command.arg("--version");
+    if let Ok(run) = run_process(version_command, None, Duration::from_secs(OCR_TIMEOUT_SECS)).await {
+        // 不同版本把版本号输出到 stdout 或 stderr
+        let output = [run.stdout, run.stderr].concat();
+        result.version = String::from_utf8_lossy(&output).lines().next().map(|l| l.trim().to_string());
+    }
+
+    let mut command = ocr::tesseract_command(&program);
+    command.arg(sample_image).arg("stdout").arg("-l").arg(&settings.tesseract_langs).arg("tsv");
+    let run = match run_process(command, None, Duration::from_secs(OCR_TIMEOUT_SECS)).await {
+        Ok(run) => run,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return result.finish(Diagnosis::NotInstalled, "找不到 Tesseract，请安装后将其加入 PATH 或在设置中指定路径。");
+        }
+        Err(e) => {
+            let (diagnosis, message) = diagnose_spawn_error(&e);
+            return result.finish(diagnosis, message);
+        }
+    };
+    let (stdout, _) = match record_run(&mut result, &run, settings) {
+        Ok(output) => output,
+        Err((diagnosis, message)) => return result.finish(diagnosis, message),
+    };
+    if !stdout.starts_with("level\t") {
+        return result.finish(Diagnosis::BadResponse, "Tesseract 输出不是 TSV 格式。");
+    }
+    let output = ocr::parse_tesseract_tsv(&stdout, " ");
+    let (diagnosis, message) = check_sample_text(&output.text);
+    result.finish(diagnosis, message)
+}
+
+async fn test_translator(app: &AppHandle, settings: &AppSettings) -> EngineTestResult {
+    let exe_path = match locate(app, settings, EngineId::Translator) {
+        Ok(path) => path,
+        Err(result) => return result,
+    };
+    let mut result = EngineTestResult::new(
+        EngineId::Translator.as_str(),
+        engine::spec(EngineId::Translator).display_name,
+        Some(&exe_path),
+    );
+    result.version = engine::read_installed(app).remove(&EngineId::Translator).map(|r| r.version);
+    let timeout = Duration::from_secs(TRANSLATOR_TIMEOUT_SECS);
+
+    // 优先使用 stdin 传递请求，旧版引擎不认识该参数时改用命令行参数
+    let request = json!({ "text": SAMPLE_TEXT, "source": "en", "target": "zh" }).to_string();
+    let mut command = engine_command(&exe_path);
+    command.arg("--stdin");
+    let mut run = run_process(command, Some(request.as_bytes()), timeout).await;
+    if let Ok(stdin_run) = &run {
+        let stderr = String::from_utf8_lossy(&stdin_run.stderr);
+        if stdin_run.status.is_some_and(|s| !s.success()) && is_unsupported_argument_error(&stderr) {
+            println!("[SELFTEST] 翻译引擎不支持 --stdin，改用命令行参数。");
+            let mut command = engine_command(&exe_path);
+            command.args(["--text", SAMPLE_TEXT, "--source", "en", "--target", "zh"]);
+            run = run_process(command, None, timeout).await;
+        }
+    }
+    let run = match run {
+        Ok(run) => run,
+        Err(e) => {
+            let (diagnosis, message) = diagnose_spawn_error(&e);
+            return result.finish(diagnosis, message);
+        }
+    };
+    let (stdout, had_errors) = match record_run(&mut result, &run, settings) {
+        Ok(output) => output,
+        Err((diagnosis, message)) => return result.finish(diagnosis, message),
+    };
+
+    let value = match serde_json::from_str::<Value>(stdout.trim()) {
+        Ok(value) => value,
+        Err(_) => {
+            let (diagnosis, message) = unparsable_output(&result, had_errors);
+            return result.finish(diagnosis, message);
+        }
+    };
+    let (diagnosis, message) = check_translator_response(&value);
+    result.finish(diagnosis, message)
+}
+
+/// 检查翻译引擎的输出结构：`{"code": 200, "translated_text": ".."}`，失败时带 `error_message`
+fn check_translator_response(value: &Value) -> (Diagnosis, String) {
+    match value["code"].as_i64() {
+        None => (Diagnosis::BadResponse, "输出缺少整数字段 code。".to_string()),
+        Some(200) => match value["translated_text"].as_str().map(str::trim) {
+            Some(text) if !text.is_empty() => (Diagnosis::Ok, format!("翻译正常: \"{}\" → \"{}\"", SAMPLE_TEXT, text)),
+            Some(_) => (Diagnosis::UnexpectedResult, "引擎运行正常，但返回了空的译文。".to_string()),
+            None => (Diagnosis::BadResponse, "code 为 200 时应包含字符串字段 translated_text。".to_string()),
+        },
+        Some(code) => (
+            Diagnosis::EngineError,
+            format!("引擎返回 code {}: {}", code, value["error_message"].as_str().unwrap_or("未知错误")),
+        ),
+    }
+}
+
+/// 依次自检各引擎。使用 Tesseract 时额外检查 Tesseract。
+pub async fn run(app: &AppHandle, settings: &AppSettings) -> Result<SelfTestReport, String> {
+    let cache_dir = app.path_resolver().app_cache_dir().ok_or("无法获取应用缓存目录")?.join("tmp");
+    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+    let sample_image = cache_dir.join("selftest-sample.png");
+    std::fs::write(&sample_image, SAMPLE_IMAGE).map_err(|e| format!("写入样例图片失败: {}", e))?;
+
+    let mut results = vec![test_rapidocr(app, settings, &sample_image).await];
+    if settings.ocr_engine == ocr::ENGINE_TESSERACT {
+        results.push(test_tesseract(settings, &sample_image).await);
+    }
+    results.push(test_translator(app, settings).await);
+    let _ = std::fs::remove_file(&sample_image);
+
+    Ok(SelfTestReport {
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        tested_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        results,
+    })
+}
+
+// --- Tauri 命令定义 ---
+
+/// 实际运行各引擎并返回诊断报告
+#[tauri::command]
+pub async fn self_test_engines(app: AppHandle, state: State<'_, AppState>) -> Result<SelfTestReport, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    println!("[SELFTEST] 开始引擎自检...");
+    run(&app, &settings).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnoses_ntstatus_exit_codes() {
+        let cases = [
+            (STATUS_DLL_NOT_FOUND, Diagnosis::MissingDependency, "STATUS_DLL_NOT_FOUND"),
+            (STATUS_ENTRYPOINT_NOT_FOUND, Diagnosis::MissingDependency, "STATUS_ENTRYPOINT_NOT_FOUND"),
+            (STATUS_INVALID_IMAGE_FORMAT, Diagnosis::BadExecutable, "STATUS_INVALID_IMAGE_FORMAT"),
+            (STATUS_ILLEGAL_INSTRUCTION, Diagnosis::Crashed, "STATUS_ILLEGAL_INSTRUCTION"),
+            (STATUS_ACCESS_VIOLATION, Diagnosis::Crashed, "STATUS_ACCESS_VIOLATION"),
+            (STATUS_STACK_OVERFLOW, Diagnosis::Crashed, "STATUS_STACK_OVERFLOW"),
+            (STATUS_STACK_BUFFER_OVERRUN, Diagnosis::Crashed, "STATUS_STACK_BUFFER_OVERRUN"),
+            (0xC000_0142, Diagnosis::Crashed, "0xC0000142"),
+        ];
+        for (status, expected, text) in cases {
+            let (diagnosis, message) = diagnose_exit_code(status as i32, "");
+            assert_eq!(diagnosis, expected, "0x{:08X}", status);
+            assert!(message.contains(text), "0x{:08X}: {}", status, message);
+        }
+    }
+
+    #[test]
+    fn diagnoses_ordinary_exit_codes() {
+        assert_eq!(
+            diagnose_exit_code(2, "loading model\nerror: model file missing\n"),
+            (Diagnosis::EngineError, "引擎返回退出码 2: error: model file missing".to_string()),
+        );
+        assert_eq!(diagnose_exit_code(1, "  "), (Diagnosis::EngineError, "引擎返回退出码 1。".to_string()));
+        assert_eq!(diagnose_exit_code(-1, "").0, Diagnosis::Crashed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn diagnoses_signals_and_missing_libraries() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let cases = [(4, "非法指令"), (11, "段错误"), (9, "内存不足"), (6, "信号 6")];
+        for (signal, text) in cases {
+            let (diagnosis, message) = diagnose_exit(ExitStatus::from_raw(signal), "");
+            assert_eq!(diagnosis, Diagnosis::Crashed);
+            assert!(message.contains(text), "signal {}: {}", signal, message);
+        }
+
+        // 退出码位于原始状态的高 8 位
+        let exited = ExitStatus::from_raw(127 << 8);
+        let stderr = "./engine: error while loading shared libraries: libonnxruntime.so.1: cannot open shared object file";
+        let (diagnosis, message) = diagnose_exit(exited, stderr);
+        assert_eq!(diagnosis, Diagnosis::MissingDependency);
+        assert!(message.contains("libonnxruntime.so.1"));
+        let dyld = "dyld: Library not loaded: @rpath/libonnxruntime.dylib";
+        assert_eq!(diagnose_exit(ExitStatus::from_raw(6), dyld).0, Diagnosis::MissingDependency);
+        assert_eq!(diagnose_exit(ExitStatus::from_raw(3 << 8), "").0, Diagnosis::EngineError);
+    }
+
+    #[test]
+    fn checks_rapidocr_responses() {
+        let sample = json!({ "code": 100, "data": [
+            { "box": [[0, 0], [1, 0], [1, 1], [0, 1]], "score": 0.98, "text": "OCR TEST" },
+            { "box": [[0, 0], [1, 0], [1, 1], [0, 1]], "score": "0.9", "text": "123" },
+        ] });
+        let cases = [
+            (sample, Diagnosis::Ok),
+            (json!({ "code": 100, "data": [{ "box": [], "score": 0.5, "text": "hello" }] }), Diagnosis::UnexpectedResult),
+            (json!({ "code": 101, "data": "No text found in image." }), Diagnosis::UnexpectedResult),
+            (json!({ "code": 200, "data": "Image decode failed." }), Diagnosis::EngineError),
+            (json!({ "code": "100", "data": [] }), Diagnosis::BadResponse),
+            (json!({ "data": [] }), Diagnosis::BadResponse),
+            (json!({ "code": 100, "data": "OCRTEST123" }), Diagnosis::BadResponse),
+            (json!({ "code": 100, "data": [{ "text": "OCRTEST123", "score": 0.9 }] }), Diagnosis::BadResponse),
+            (json!({ "code": 100, "data": [{ "box": [], "score": null, "text": "OCRTEST123" }] }), Diagnosis::BadResponse),
+            (json!([1, 2, 3]), Diagnosis::BadResponse),
+            (Value::Null, Diagnosis::BadResponse),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(check_rapidocr_response(&value).0, expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn checks_translator_responses() {
+        let cases = [
+            (json!({ "code": 200, "translated_text": "你好，世界。" }), Diagnosis::Ok),
+            (json!({ "code": 200, "translated_text": "  " }), Diagnosis::UnexpectedResult),
+            (json!({ "code": 200 }), Diagnosis::BadResponse),
+            (json!({ "code": 200, "translated_text": 42 }), Diagnosis::BadResponse),
+            (json!({ "code": 500, "error_message": "unsupported language" }), Diagnosis::EngineError),
+            (json!({ "code": 500 }), Diagnosis::EngineError),
+            (json!({ "code": 200.5, "translated_text": "x" }), Diagnosis::BadResponse),
+            (json!({ "translated_text": "x" }), Diagnosis::BadResponse),
+            (json!("plain text"), Diagnosis::BadResponse),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(check_translator_response(&value).0, expected, "{}", value);
+        }
+        let (_, message) = check_translator_response(&json!({ "code": 500, "error_message": "unsupported language" }));
+        assert!(message.contains("unsupported language"));
+    }
+
+    #[test]
+    fn malformed_json_is_not_mistaken_for_an_encoding_problem() {
+        let mut result = EngineTestResult::new("ocr", "识别引擎", None);
+        result.output_encoding = Some("UTF-8".to_string());
+        result.stdout_excerpt = "{\"code\": 100, \"data\": [".to_string();
+        assert_eq!(unparsable_output(&result, false).0, Diagnosis::BadResponse);
+        assert_eq!(unparsable_output(&result, true).0, Diagnosis::BadEncoding);
+
+        result.output_encoding = Some("GBK".to_string());
+        result.stdout_excerpt = "{\"code\": 100, \"data\": \"鍥剧墖\"".to_string();
+        assert_eq!(unparsable_output(&result, false).0, Diagnosis::BadEncoding);
+
+        result.stdout_excerpt = String::new();
+        assert!(unparsable_output(&result, false).1.contains("没有任何输出"));
+    }
+}
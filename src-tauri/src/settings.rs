@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{AppHandle, GlobalShortcutManager, PathResolver, State};
 use arboard::ImageData;
@@ -9,7 +11,20 @@ use image::RgbaImage;
 use tauri::api::path as tauri_path;
 use std::sync::atomic::AtomicBool;
 
-use crate::{register_global_shortcut, register_view_image_shortcut};
+use crate::{register_global_shortcut, register_view_image_shortcut, register_watch_shortcut, register_delayed_capture_shortcut};
+use crate::register_long_capture_shortcut;
+use crate::{register_region_shortcuts, unregister_region_shortcuts};
+use crate::actions::PrimaryAction;
+use crate::engine::{EngineId, EnginePaths};
+use crate::ocr::OcrBlock;
+use crate::preprocess::PreprocessOptions;
+use crate::regions::SavedRegion;
+use crate::network;
+use crate::shortcut;
+use crate::translator::SUPPORTED_TARGET_LANGS;
+use crate::stitch::LongCaptureSession;
+use crate::watch::WatchSession;
+use crate::worker::WorkerManager;
 
 //
 // 应用的全局共享状态
@@ -27,6 +42,17 @@ pub struct AppState {
     pub screenshot_history: Mutex<Vec<PathBuf>>,
     // --- 新增：当前查看的历史记录索引 (用于F3循环) ---
     pub history_index: Mutex<usize>,
+
+    // --- 新增：正在运行的区域监视会话 ---
+    pub watch_session: Mutex<Option<WatchSession>>,
+    // --- 新增：是否有延时截图正在倒计时 ---
+    pub delayed_capture_pending: AtomicBool,
+    // --- 新增：正在进行的滚动长截图会话 ---
+    pub long_capture_session: Mutex<Option<LongCaptureSession>>,
+    // --- 新增：翻译引擎常驻进程 (跨 await 持有，使用异步锁) ---
+    pub translator_worker: tokio::sync::Mutex<WorkerManager>,
+    // --- 新增：正在下载的引擎及其取消信号 ---
+    pub engine_downloads: Mutex<HashMap<EngineId, tokio::sync::watch::Sender<bool>>>,
 }
 
 // 缓存的结果结构
@@ -35,31 +61,180 @@ pub struct LastOcrResult {
     pub original_text: Option<String>,
     pub translated_text: Option<String>,
     pub image_path: String,
+    // --- 新增：识别置信度与各文本块 ---
+    pub confidence: Option<f32>,
+    pub blocks: Vec<OcrBlock>,
+}
+
+// --- 设置文件版本 ---
+// 设置结构有不兼容的变化（字段改名、类型变化）时递增，并在 MIGRATIONS 中添加对应的迁移
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// 设置文件的迁移链，`MIGRATIONS[i]` 把版本 i 的设置升级为版本 i + 1。
+/// 迁移在反序列化之前作用于原始 JSON，只需处理改名和类型变化，新增字段由 `#[serde(default)]` 补齐。
+const MIGRATIONS: [fn(&mut Map<String, Value>); SETTINGS_VERSION as usize] = [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// 版本 0：没有 `version` 字段的设置文件。
+/// `enable_ocr` / `enable_translation` 是 `primary_action` 之前的开关，缺少 `primary_action` 时据此推断；
+/// 两个开关都没有打开时与 `AppSettings::default()` 一致，使用 `ocr`。
+fn migrate_v0_to_v1(settings: &mut Map<String, Value>) {
+    if settings.contains_key("primary_action") {
+        return;
+    }
+    let flag = |key: &str| settings.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let action = if flag("enable_translation") { "ocr_translate" } else { "ocr" };
+    settings.insert("primary_action".to_string(), Value::from(action));
+}
+
+/// 版本 1 → 2：`tesseract_path` 并入自定义引擎路径 `engine_paths.tesseract`
+fn migrate_v1_to_v2(settings: &mut Map<String, Value>) {
+    let tesseract_path = match settings.remove("tesseract_path") {
+        Some(Value::String(path)) => path,
+        _ => return,
+    };
+    let engine_paths = settings.entry("engine_paths").or_insert_with(|| Value::Object(Map::new()));
+    if let Some(engine_paths) = engine_paths.as_object_mut() {
+        engine_paths.entry("tesseract").or_insert(Value::String(tesseract_path));
+    }
+}
+
+type RegisterShortcut = fn(AppHandle, &str) -> Result<(), tauri::Error>;
+
+/// 设置中某个字段的校验或应用错误，`field` 与 AppSettings 的序列化字段名一致，
+/// 保存区域的字段写作 `saved_regions[<下标>].<字段>`
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+/// `set_settings` 的错误：`message` 供直接显示，`fields` 供界面标记出错的输入项
+#[derive(Serialize, Debug, Clone)]
+pub struct SettingsError {
+    pub message: String,
+    pub fields: Vec<FieldError>,
+}
+
+impl From<Vec<FieldError>> for SettingsError {
+    fn from(fields: Vec<FieldError>) -> Self {
+        let message = fields.iter().map(|e| e.message.as_str()).collect::<Vec<_>>().join("\n");
+        Self { message, fields }
+    }
 }
 
+impl From<FieldError> for SettingsError {
+    fn from(error: FieldError) -> Self {
+        vec![error].into()
+    }
+}
+
+impl From<String> for SettingsError {
+    fn from(message: String) -> Self {
+        Self { message, fields: Vec::new() }
+    }
+}
+
+// 缺少的字段一律使用 `AppSettings::default()` 中的值，新增字段不会导致旧设置文件解析失败
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct AppSettings {
+    // 设置文件版本，加载时按 MIGRATIONS 升级到 SETTINGS_VERSION
+    pub version: u32,
     pub shortcut: String,
     pub view_image_shortcut: String,
     pub target_lang: String,
     pub preserve_line_breaks: bool,
-    pub primary_action: String,
-    #[serde(default)]
+    pub primary_action: PrimaryAction,
     pub enable_ocr: bool,
-    #[serde(default)]
     pub enable_translation: bool,
+    // --- 新增：区域监视模式 ---
+    pub watch_shortcut: String,
+    pub watch_interval_ms: u64,
+    // 画面中变化像素的比例达到此值才重新识别 (0.0 ~ 1.0)
+    pub watch_change_threshold: f64,
+    // --- 新增：延时截图 ---
+    pub delayed_capture_shortcut: String,
+    pub capture_delay_secs: u64,
+    // --- 新增：滚动长截图 ---
+    pub long_capture_shortcut: String,
+    // --- 新增：命名保存区域 ---
+    pub saved_regions: Vec<SavedRegion>,
+    // --- 新增：OCR 图像预处理 ---
+    pub ocr_preprocess: PreprocessOptions,
+    // 整体置信度低于此值时换用备选预处理重试，文本块低于此值时在结果窗口中标记
+    pub low_confidence_threshold: f32,
+    // --- 新增：OCR 引擎选择 (rapidocr / tesseract) ---
+    pub ocr_engine: String,
+    // Tesseract 的语言包，多个语言用 + 连接，如 eng+chi_sim
+    pub tesseract_langs: String,
+    // --- 新增：自定义引擎可执行文件路径 ---
+    pub engine_paths: EnginePaths,
+    // 引擎输出不是 UTF-8 时使用的本地代码页 (gbk / shift_jis / big5)
+    pub engine_output_encoding: String,
+    // 引擎清单的来源（本地文件路径或 http(s) URL），为空时使用内置清单
+    pub engine_manifest_url: Option<String>,
+    // --- 新增：翻译引擎常驻进程 ---
+    pub translator_worker_enabled: bool,
+    // 常驻进程空闲多少分钟后自动退出，0 表示不自动退出
+    pub translator_idle_timeout_mins: u64,
+    // --- 新增：网络设置 ---
+    // 代理地址 (http:// / https:// / socks5://)，为空时使用系统环境变量中的代理
+    pub network_proxy: Option<String>,
+    // 额外信任的 CA 证书文件 (PEM 或 DER)，用于企业网络的 HTTPS 检查代理
+    pub network_ca_cert: Option<String>,
+    // 下载引擎时依次尝试的镜像地址，全部失败后使用原始地址
+    pub download_mirrors: Vec<String>,
 }
 
+fn default_watch_shortcut() -> String { "F4".to_string() }
+fn default_watch_interval_ms() -> u64 { 1500 }
+fn default_watch_change_threshold() -> f64 { 0.02 }
+fn default_delayed_capture_shortcut() -> String { "Shift+F1".to_string() }
+fn default_capture_delay_secs() -> u64 { 3 }
+fn default_long_capture_shortcut() -> String { "Ctrl+F1".to_string() }
+fn default_low_confidence_threshold() -> f32 { 0.8 }
+fn default_ocr_engine() -> String { crate::ocr::default_engine().to_string() }
+fn default_tesseract_langs() -> String { "eng".to_string() }
+fn default_engine_output_encoding() -> String { crate::encoding::DEFAULT_LEGACY_ENCODING.to_string() }
+fn default_translator_worker_enabled() -> bool { true }
+fn default_translator_idle_timeout_mins() -> u64 { 5 }
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            version: SETTINGS_VERSION,
             shortcut: "F1".to_string(),
             view_image_shortcut: "F3".to_string(),
             target_lang: "zh".to_string(),
             preserve_line_breaks: false,
-            primary_action: "ocr".to_string(),
+            primary_action: PrimaryAction::Ocr,
             enable_ocr: false,
             enable_translation: false,
+            watch_shortcut: default_watch_shortcut(),
+            watch_interval_ms: default_watch_interval_ms(),
+            watch_change_threshold: default_watch_change_threshold(),
+            delayed_capture_shortcut: default_delayed_capture_shortcut(),
+            capture_delay_secs: default_capture_delay_secs(),
+            long_capture_shortcut: default_long_capture_shortcut(),
+            saved_regions: Vec::new(),
+            ocr_preprocess: PreprocessOptions::default(),
+            low_confidence_threshold: default_low_confidence_threshold(),
+            ocr_engine: default_ocr_engine(),
+            tesseract_langs: default_tesseract_langs(),
+            engine_paths: EnginePaths::default(),
+            engine_output_encoding: default_engine_output_encoding(),
+            engine_manifest_url: None,
+            translator_worker_enabled: default_translator_worker_enabled(),
+            translator_idle_timeout_mins: default_translator_idle_timeout_mins(),
+            network_proxy: None,
+            network_ca_cert: None,
+            download_mirrors: Vec::new(),
         }
     }
 }
@@ -69,18 +244,214 @@ impl AppSettings {
         path_resolver.app_config_dir().expect("致命错误：无法获取应用配置目录").join("settings.json")
     }
 
-    pub fn load(path_resolver: &PathResolver) -> Result<Self, Box<dyn std::error::Error>> {
-        let config_path = Self::get_config_path(path_resolver);
-        if config_path.exists() {
-            let content = fs::read_to_string(config_path)?;
-            Ok(serde_json::from_str(&content)?)
-        } else {
-            Ok(Self::default())
+    /// 加载设置文件，必要时迁移到当前版本并写回（迁移前的文件保留为 `settings.json.bak-v<旧版本>`）。
+    ///
+    /// 个别字段无效时只把这些字段恢复为默认值，原文件复制为 `settings.json.bad-<时间戳>` 后写回；
+    /// 整个文件无法解析时不会覆盖它：改名为 `settings.json.bad-<时间戳>` 后使用默认设置。
+    /// 两种情况都返回需要告知用户的提示。
+    pub fn load(path_resolver: &PathResolver) -> (Self, Option<String>) {
+        Self::load_from(&Self::get_config_path(path_resolver))
+    }
+
+    fn load_from(config_path: &Path) -> (Self, Option<String>) {
+        let content = match fs::read_to_string(config_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Self::default(), None),
+            Err(e) => return (Self::default(), Some(format!("读取设置文件失败 ({})，本次使用默认设置。", e))),
+        };
+        let bad_backup_path = || {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            config_path.with_file_name(format!("settings.json.bad-{}", timestamp))
+        };
+
+        match Self::parse(&content) {
+            Ok((settings, from_version, dropped_fields)) => {
+                if from_version < SETTINGS_VERSION {
+                    let backup = config_path.with_file_name(format!("settings.json.bak-v{}", from_version));
+                    if let Err(e) = fs::copy(config_path, &backup) {
+                        eprintln!("[SETTINGS] 备份迁移前的设置文件失败: {}", e);
+                    }
+                    println!("[SETTINGS] 设置已从 v{} 迁移到 v{}，原文件备份为 {:?}", from_version, SETTINGS_VERSION, backup);
+                }
+                let warning = if dropped_fields.is_empty() {
+                    None
+                } else {
+                    let backup = bad_backup_path();
+                    let message = format!("设置文件中以下字段无效，已恢复为默认值: {}", dropped_fields.join(", "));
+                    let warning = match fs::copy(config_path, &backup) {
+                        Ok(_) => format!("{}。原文件已备份为 {}。", message, backup.display()),
+                        Err(e) => format!("{}。备份原文件失败 ({})。", message, e),
+                    };
+                    eprintln!("[SETTINGS] {}", warning);
+                    Some(warning)
+                };
+                if !dropped_fields.is_empty() || from_version < SETTINGS_VERSION {
+                    if let Err(e) = settings.save_to(config_path) {
+                        eprintln!("[SETTINGS] 写入修复后的设置失败: {}", e);
+                    }
+                }
+                (settings, warning)
+            }
+            Err(e) => {
+                let backup = bad_backup_path();
+                let warning = match fs::rename(config_path, &backup) {
+                    Ok(()) => format!("设置文件无法读取 ({})，已备份为 {}，本次使用默认设置。", e, backup.display()),
+                    Err(rename_error) => format!("设置文件无法读取 ({})，备份失败 ({})，本次使用默认设置。", e, rename_error),
+                };
+                eprintln!("[SETTINGS] {}", warning);
+                (Self::default(), Some(warning))
+            }
+        }
+    }
+
+    /// 解析设置文件内容并迁移到当前版本，返回设置、文件原本的版本，以及因无效而恢复为默认值的字段。
+    ///
+    /// 只有内容不是 JSON 对象时才返回错误。个别字段无法解析时，从默认设置开始逐个叠加文件中的字段，
+    /// 跳过无法解析的字段，保留其余设置。
+    fn parse(content: &str) -> Result<(Self, u32, Vec<String>), String> {
+        let value: Value = serde_json::from_str(content).map_err(|e| format!("不是有效的 JSON: {}", e))?;
+        let mut map = match value {
+            Value::Object(map) => map,
+            _ => return Err("顶层不是 JSON 对象".to_string()),
+        };
+        let from_version = map.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+        if from_version > SETTINGS_VERSION {
+            // 由更新版本的程序写入，未知字段会被忽略
+            println!("[SETTINGS] 警告: 设置文件版本 v{} 高于当前支持的 v{}。", from_version, SETTINGS_VERSION);
+        }
+        for (version, migrate) in MIGRATIONS.iter().enumerate().skip(from_version as usize) {
+            println!("[SETTINGS] 迁移设置: v{} → v{}", version, version + 1);
+            migrate(&mut map);
+        }
+        map.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+
+        match serde_json::from_value(Value::Object(map.clone())) {
+            Ok(settings) => Ok((settings, from_version, Vec::new())),
+            Err(e) => {
+                println!("[SETTINGS] 设置文件格式错误 ({})，逐个字段恢复。", e);
+                let (settings, dropped_fields) = Self::salvage(map);
+                Ok((settings, from_version, dropped_fields))
+            }
+        }
+    }
+
+    /// 在默认设置上逐个叠加 `fields` 中能够解析的字段，返回结果和被丢弃的字段名
+    fn salvage(fields: Map<String, Value>) -> (Self, Vec<String>) {
+        let mut salvaged = match serde_json::to_value(Self::default()) {
+            Ok(Value::Object(map)) => map,
+            _ => return (Self::default(), fields.into_iter().map(|(key, _)| key).collect()),
+        };
+        let mut dropped_fields = Vec::new();
+        for (key, value) in fields {
+            let previous = salvaged.insert(key.clone(), value);
+            if serde_json::from_value::<Self>(Value::Object(salvaged.clone())).is_err() {
+                match previous {
+                    Some(previous) => salvaged.insert(key.clone(), previous),
+                    None => salvaged.remove(&key),
+                };
+                dropped_fields.push(key);
+            }
+        }
+        let settings = serde_json::from_value(Value::Object(salvaged)).unwrap_or_default();
+        (settings, dropped_fields)
+    }
+
+    /// 全局快捷键字段：(字段名, 界面名称, 快捷键, 注册函数)
+    fn shortcut_fields(&self) -> [(&'static str, &'static str, &str, RegisterShortcut); 5] {
+        [
+            ("shortcut", "截图快捷键", &self.shortcut, register_global_shortcut),
+            ("view_image_shortcut", "查看上次截图快捷键", &self.view_image_shortcut, register_view_image_shortcut),
+            ("delayed_capture_shortcut", "延时截图快捷键", &self.delayed_capture_shortcut, register_delayed_capture_shortcut),
+            ("long_capture_shortcut", "滚动长截图快捷键", &self.long_capture_shortcut, register_long_capture_shortcut),
+            ("watch_shortcut", "区域监视快捷键", &self.watch_shortcut, register_watch_shortcut),
+        ]
+    }
+
+    /// 检查整份设置：快捷键语法与重复、首要动作、目标语言、下载镜像地址以及保存区域的对应覆盖项。
+    /// 返回所有出错的字段，而不是只报告第一个。
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        // 规范化后的快捷键 -> 已占用它的设置项名称
+        let mut used_shortcuts: HashMap<String, String> = HashMap::new();
+        let mut check_shortcut = |field: String, label: String, value: &str, errors: &mut Vec<FieldError>| {
+            match shortcut::normalize(value) {
+                Ok(normalized) => match used_shortcuts.get(&normalized) {
+                    Some(other) => errors.push(FieldError::new(field, format!("{}与{}相同 ({})", label, other, value))),
+                    None => {
+                        used_shortcuts.insert(normalized, label);
+                    }
+                },
+                Err(e) => errors.push(FieldError::new(field, format!("{}无效: {}", label, e))),
+            }
+        };
+
+        for (field, label, value, _) in self.shortcut_fields() {
+            check_shortcut(field.to_string(), label.to_string(), value, &mut errors);
+        }
+
+        if let PrimaryAction::Unknown(name) = &self.primary_action {
+            errors.push(FieldError::new("primary_action", format!("不支持的首要动作 \"{}\"", name)));
+        }
+        if !SUPPORTED_TARGET_LANGS.contains(&self.target_lang.as_str()) {
+            errors.push(FieldError::new("target_lang", format!("不支持的目标语言 \"{}\"", self.target_lang)));
+        }
+
+        for mirror in self.download_mirrors.iter().filter(|m| !m.trim().is_empty()) {
+            if let Err(e) = network::validate_mirror(mirror) {
+                errors.push(FieldError::new("download_mirrors", e));
+            }
+        }
+
+        let mut region_names = std::collections::HashSet::new();
+        for (index, region) in self.saved_regions.iter().enumerate() {
+            let field = |name: &str| format!("saved_regions[{}].{}", index, name);
+            if region.width == 0 || region.height == 0 {
+                errors.push(FieldError::new(field("width"), format!("区域「{}」的大小为空", region.name)));
+            }
+            if region.name.trim().is_empty() {
+                errors.push(FieldError::new(field("name"), format!("第 {} 个保存区域的名称为空", index + 1)));
+            } else if !region_names.insert(region.name.as_str()) {
+                errors.push(FieldError::new(field("name"), format!("保存区域名称「{}」重复", region.name)));
+            }
+            if let Some(value) = region.shortcut.as_deref().filter(|s| !s.trim().is_empty()) {
+                check_shortcut(field("shortcut"), format!("区域「{}」的快捷键", region.name), value, &mut errors);
+            }
+            if let Some(PrimaryAction::Unknown(name)) = &region.action {
+                errors.push(FieldError::new(field("action"), format!("区域「{}」的动作 \"{}\" 不受支持", region.name, name)));
+            }
+            if let Some(lang) = region.target_lang.as_deref().filter(|l| !SUPPORTED_TARGET_LANGS.contains(l)) {
+                errors.push(FieldError::new(field("target_lang"), format!("区域「{}」的目标语言 \"{}\" 不受支持", region.name, lang)));
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// 注册本设置中的全部全局快捷键，遇到第一个失败的快捷键即返回
+    fn register_shortcuts(&self, app: &AppHandle) -> Result<(), FieldError> {
+        for (field, label, value, register) in self.shortcut_fields() {
+            register(app.clone(), value).map_err(|e| FieldError::new(field, format!("注册{} {} 失败: {}", label, value, e)))?;
         }
+        register_region_shortcuts(app, &self.saved_regions).map_err(|e| FieldError::new("saved_regions", e))
+    }
+
+    /// 注销本设置中的全部全局快捷键，未注册的快捷键会被忽略
+    fn unregister_shortcuts(&self, app: &AppHandle) {
+        let mut shortcut_manager = app.global_shortcut_manager();
+        for (_, _, value, _) in self.shortcut_fields() {
+            let _ = shortcut_manager.unregister(value);
+        }
+        unregister_region_shortcuts(app, &self.saved_regions);
     }
 
     pub fn save(&self, path_resolver: &PathResolver) -> Result<(), Box<dyn std::error::Error>> {
-        let config_path = Self::get_config_path(path_resolver);
+        self.save_to(&Self::get_config_path(path_resolver))
+    }
+
+    fn save_to(&self, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)?;
         }
@@ -95,37 +466,38 @@ pub fn get_settings(state: State<AppState>) -> Result<AppSettings, String> {
     Ok(state.settings.lock().unwrap().clone())
 }
 
+/// 应用并保存新设置。
+///
+/// 先校验整份设置，再注销旧快捷键并注册新快捷键，全部成功后才写入设置文件并替换内存中的设置；
+/// 任何一步失败都会恢复旧快捷键，设置文件、内存状态与实际注册的快捷键始终保持一致。
 #[tauri::command]
-pub async fn set_settings(app: AppHandle, state: State<'_, AppState>, settings: AppSettings) -> Result<(), String> {
+pub async fn set_settings(app: AppHandle, state: State<'_, AppState>, settings: AppSettings) -> Result<(), SettingsError> {
     println!("接收到新设置: {:?}", settings);
 
-    settings.save(&app.path_resolver()).map_err(|e| format!("保存设置文件失败: {}", e))?;
+    settings.validate()?;
 
-    let old_shortcut;
-    let old_view_shortcut;
-    {
-        let mut app_settings = state.settings.lock().unwrap();
-        old_shortcut = app_settings.shortcut.clone();
-        old_view_shortcut = app_settings.view_image_shortcut.clone();
-        *app_settings = settings.clone();
-    }
-
-    let mut shortcut_manager = app.global_shortcut_manager();
+    let old_settings = state.settings.lock().unwrap().clone();
+    // 先注销全部旧快捷键，避免新旧快捷键互换时因已被占用而注册失败
+    old_settings.unregister_shortcuts(&app);
 
-    if old_shortcut != settings.shortcut {
-        let _ = shortcut_manager.unregister(&old_shortcut);
-    }
-    if let Err(e) = register_global_shortcut(app.clone(), &settings.shortcut) {
-        return Err(format!("注册主快捷键失败: {}", e));
-    }
-
-    if old_view_shortcut != settings.view_image_shortcut {
-        let _ = shortcut_manager.unregister(&old_view_shortcut);
-    }
-    if let Err(e) = register_view_image_shortcut(app.clone(), &settings.view_image_shortcut) {
-        return Err(format!("注册查看快捷键失败: {}", e));
+    let applied = settings
+        .register_shortcuts(&app)
+        .map_err(SettingsError::from)
+        .and_then(|()| {
+            settings
+                .save(&app.path_resolver())
+                .map_err(|e| SettingsError::from(format!("保存设置文件失败: {}", e)))
+        });
+    if let Err(e) = applied {
+        eprintln!("[SETTINGS] 应用新设置失败，恢复原设置: {}", e.message);
+        settings.unregister_shortcuts(&app);
+        if let Err(restore_error) = old_settings.register_shortcuts(&app) {
+            eprintln!("[SETTINGS] 恢复原快捷键失败: {}", restore_error.message);
+        }
+        return Err(e);
     }
 
+    *state.settings.lock().unwrap() = settings;
     Ok(())
 }
 
@@ -157,4 +529,213 @@ pub async fn save_image_to_desktop(path: String) -> Result<(), String> {
     let dest_path = desktop_dir.join(new_filename);
     fs::copy(&path, &dest_path).map_err(|e| format!("保存文件失败: {}", e))?;
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("settings-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 目录中以 `prefix` 开头的文件名
+    fn files_with_prefix(dir: &Path, prefix: &str) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with(prefix))
+            .collect()
+    }
+
+    #[test]
+    fn empty_object_deserializes_to_defaults() {
+        let expected = serde_json::to_value(AppSettings::default()).unwrap();
+        let settings: AppSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(serde_json::to_value(settings).unwrap(), expected);
+        let (settings, _, _) = AppSettings::parse("{}").unwrap();
+        assert_eq!(serde_json::to_value(settings).unwrap(), expected);
+    }
+
+    #[test]
+    fn v0_without_flags_defaults_to_ocr() {
+        let (settings, from_version, dropped) = AppSettings::parse(r#"{"shortcut":"F2"}"#).unwrap();
+        assert_eq!(from_version, 0);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.primary_action, PrimaryAction::Ocr);
+        assert_eq!(settings.shortcut, "F2");
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn v0_flags_choose_the_primary_action() {
+        let cases = [
+            (r#"{"enable_ocr":true,"enable_translation":true}"#, PrimaryAction::OcrTranslate),
+            (r#"{"enable_translation":true}"#, PrimaryAction::OcrTranslate),
+            (r#"{"enable_ocr":true}"#, PrimaryAction::Ocr),
+            (r#"{"enable_ocr":false,"enable_translation":false}"#, PrimaryAction::Ocr),
+            (r#"{"enable_translation":true,"primary_action":"copy"}"#, PrimaryAction::Copy),
+        ];
+        for (content, expected) in cases {
+            let (settings, _, _) = AppSettings::parse(content).unwrap();
+            assert_eq!(settings.primary_action, expected, "{}", content);
+        }
+    }
+
+    #[test]
+    fn v1_moves_tesseract_path_into_engine_paths() {
+        let content = r#"{"version":1,"primary_action":"save","tesseract_path":"C:\\Tesseract\\tesseract.exe"}"#;
+        let (settings, from_version, _) = AppSettings::parse(content).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(settings.version, 2);
+        assert_eq!(settings.primary_action, PrimaryAction::Save);
+        assert_eq!(settings.engine_paths.tesseract(), Some("C:\\Tesseract\\tesseract.exe"));
+    }
+
+    #[test]
+    fn v1_keeps_an_existing_engine_path() {
+        let content = r#"{"version":1,"tesseract_path":"old.exe","engine_paths":{"tesseract":"new.exe"}}"#;
+        let (settings, _, _) = AppSettings::parse(content).unwrap();
+        assert_eq!(settings.engine_paths.tesseract(), Some("new.exe"));
+    }
+
+    #[test]
+    fn rejects_content_that_is_not_an_object() {
+        assert!(AppSettings::parse("{ not json").is_err());
+        assert!(AppSettings::parse("[1, 2, 3]").is_err());
+        assert!(AppSettings::parse("\"settings\"").is_err());
+    }
+
+    #[test]
+    fn salvages_valid_fields_when_one_is_invalid() {
+        let content = r#"{"version":2,"shortcut":"F2","watch_interval_ms":"fast","target_lang":"en","capture_delay_secs":-1}"#;
+        let (settings, _, mut dropped) = AppSettings::parse(content).unwrap();
+        dropped.sort();
+        assert_eq!(dropped, vec!["capture_delay_secs".to_string(), "watch_interval_ms".to_string()]);
+        assert_eq!(settings.shortcut, "F2");
+        assert_eq!(settings.target_lang, "en");
+        assert_eq!(settings.watch_interval_ms, default_watch_interval_ms());
+        assert_eq!(settings.capture_delay_secs, default_capture_delay_secs());
+    }
+
+    #[test]
+    fn keeps_unknown_actions_for_validation() {
+        let content = r#"{
+            "version": 2,
+            "primary_action": "teleport",
+            "saved_regions": [
+                {"name": "A", "x": 0, "y": 0, "width": 10, "height": 10, "action": "copy"},
+                {"name": "B", "x": 0, "y": 0, "width": 10, "height": 10, "action": "fax"}
+            ]
+        }"#;
+        let (settings, _, dropped) = AppSettings::parse(content).unwrap();
+        assert!(dropped.is_empty());
+        assert_eq!(settings.primary_action, PrimaryAction::Unknown("teleport".to_string()));
+        assert_eq!(settings.saved_regions[0].action, Some(PrimaryAction::Copy));
+
+        let fields: Vec<String> = settings.validate().unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["primary_action".to_string(), "saved_regions[1].action".to_string()]);
+        // 原样写回，不会被替换成其它动作
+        let saved = serde_json::to_value(&settings).unwrap();
+        assert_eq!(saved["primary_action"], "teleport");
+        assert_eq!(saved["saved_regions"][1]["action"], "fax");
+    }
+
+    #[test]
+    fn rejects_invalid_download_mirrors() {
+        let settings = AppSettings {
+            download_mirrors: vec!["https://mirror.example.com".to_string(), "mirror.example.com".to_string(), " ".to_string()],
+            ..AppSettings::default()
+        };
+        let errors = settings.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "download_mirrors");
+        assert!(errors[0].message.contains("mirror.example.com"));
+    }
+
+    #[test]
+    fn rejects_empty_saved_regions() {
+        let region = |name: &str, width: u32, height: u32| SavedRegion {
+            name: name.to_string(),
+            monitor: None,
+            x: 10,
+            y: 10,
+            width,
+            height,
+            action: None,
+            target_lang: None,
+            shortcut: None,
+        };
+        let settings = AppSettings {
+            saved_regions: vec![region("A", 100, 50), region("B", 0, 50), region("C", 100, 0)],
+            ..AppSettings::default()
+        };
+        let fields: Vec<String> = settings.validate().unwrap_err().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["saved_regions[1].width".to_string(), "saved_regions[2].width".to_string()]);
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(AppSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn load_migrates_and_keeps_a_backup() {
+        let dir = test_dir("migrate");
+        let path = dir.join("settings.json");
+        let original = r#"{"enable_translation":true}"#;
+        fs::write(&path, original).unwrap();
+
+        let (settings, warning) = AppSettings::load_from(&path);
+        assert!(warning.is_none());
+        assert_eq!(settings.primary_action, PrimaryAction::OcrTranslate);
+        assert_eq!(fs::read_to_string(dir.join("settings.json.bak-v0")).unwrap(), original);
+        let (saved, from_version, _) = AppSettings::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(from_version, SETTINGS_VERSION);
+        assert_eq!(saved.primary_action, PrimaryAction::OcrTranslate);
+    }
+
+    #[test]
+    fn load_moves_an_unreadable_file_aside() {
+        let dir = test_dir("invalid");
+        let path = dir.join("settings.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        let (settings, warning) = AppSettings::load_from(&path);
+        assert!(warning.unwrap().contains("本次使用默认设置"));
+        assert_eq!(settings.shortcut, AppSettings::default().shortcut);
+        assert!(!path.exists());
+        let backups = files_with_prefix(&dir, "settings.json.bad-");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(dir.join(&backups[0])).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn load_resets_invalid_fields_and_reports_them() {
+        let dir = test_dir("salvage");
+        let path = dir.join("settings.json");
+        let original = r#"{"version":2,"shortcut":"F2","watch_interval_ms":"fast"}"#;
+        fs::write(&path, original).unwrap();
+
+        let (settings, warning) = AppSettings::load_from(&path);
+        assert!(warning.unwrap().contains("watch_interval_ms"));
+        assert_eq!(settings.shortcut, "F2");
+        assert_eq!(settings.watch_interval_ms, default_watch_interval_ms());
+        let backups = files_with_prefix(&dir, "settings.json.bad-");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(dir.join(&backups[0])).unwrap(), original);
+        // 写回后的文件可以直接解析
+        let (_, _, dropped) = AppSettings::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn load_without_a_file_uses_defaults() {
+        let dir = test_dir("missing");
+        let (settings, warning) = AppSettings::load_from(&dir.join("settings.json"));
+        assert!(warning.is_none());
+        assert_eq!(settings.version, SETTINGS_VERSION);
+    }
+}
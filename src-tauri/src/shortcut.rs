@@ -0,0 +1,191 @@
+// --- 文件: src-tauri/src/shortcut.rs ---
+
+//! 全局快捷键字符串的解析与规范化
+//!
+//! 快捷键格式与 Tauri 的全局快捷键一致：若干修饰键加一个按键，用 `+` 连接，如 `Ctrl+Shift+A`。
+//! 保存设置前先在这里检查语法，并把别名（`Control` / `Ctrl`、`Esc` / `Escape`、`KeyA` / `A` 等）
+//! 统一为同一种写法，以便发现写法不同但实际相同的重复快捷键。
+
+// 修饰键别名 -> 规范名称，规范化后按此表中规范名称的先后顺序排列
+const MODIFIERS: &[(&str, &str)] = &[
+    ("CTRL", "Ctrl"),
+    ("CONTROL", "Ctrl"),
+    ("ALT", "Alt"),
+    ("OPTION", "Alt"),
+    ("SHIFT", "Shift"),
+    ("SUPER", "Super"),
+    ("CMD", "Super"),
+    ("COMMAND", "Super"),
+];
+
+// CmdOrCtrl 在 macOS 上是 Command 键，在其它系统上是 Ctrl
+const CMD_OR_CTRL: &[&str] = &["CMDORCTRL", "CMDORCONTROL", "COMMANDORCTRL", "COMMANDORCONTROL"];
+
+// 按键别名 -> 规范名称
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("ESC", "Escape"),
+    ("RETURN", "Enter"),
+    ("UP", "ArrowUp"),
+    ("DOWN", "ArrowDown"),
+    ("LEFT", "ArrowLeft"),
+    ("RIGHT", "ArrowRight"),
+    ("DEL", "Delete"),
+    ("INS", "Insert"),
+    ("`", "Backquote"),
+    ("\\", "Backslash"),
+    ("[", "BracketLeft"),
+    ("]", "BracketRight"),
+    (",", "Comma"),
+    ("=", "Equal"),
+    ("-", "Minus"),
+    (".", "Period"),
+    ("'", "Quote"),
+    (";", "Semicolon"),
+    ("/", "Slash"),
+    ("NUMADD", "NumpadAdd"),
+    ("NUMSUBTRACT", "NumpadSubtract"),
+    ("NUMMULTIPLY", "NumpadMultiply"),
+    ("NUMDIVIDE", "NumpadDivide"),
+    ("NUMDECIMAL", "NumpadDecimal"),
+    ("NUMENTER", "NumpadEnter"),
+];
+
+// 除字母、数字、F1~F24、小键盘数字以外可以使用的按键
+const NAMED_KEYS: &[&str] = &[
+    "Backquote", "Backslash", "BracketLeft", "BracketRight", "Comma", "Equal", "Minus", "Period",
+    "Quote", "Semicolon", "Slash", "Plus", "Backspace", "CapsLock", "Enter", "Space", "Tab",
+    "Delete", "End", "Home", "Insert", "PageDown", "PageUp", "PrintScreen", "ScrollLock", "Pause",
+    "NumLock", "Escape", "ArrowDown", "ArrowUp", "ArrowLeft", "ArrowRight", "NumpadAdd",
+    "NumpadSubtract", "NumpadMultiply", "NumpadDivide", "NumpadDecimal", "NumpadEnter", "NumpadEqual",
+];
+
+/// 检查快捷键语法，返回规范化后的写法（修饰键按固定顺序排列，按键使用规范名称）
+pub fn normalize(shortcut: &str) -> Result<String, String> {
+    let shortcut = shortcut.trim();
+    if shortcut.is_empty() {
+        return Err("快捷键不能为空".to_string());
+    }
+
+    let tokens: Vec<&str> = shortcut.split('+').map(str::trim).collect();
+    let (key, modifier_tokens) = tokens.split_last().unwrap();
+    let mut modifiers: Vec<&str> = Vec::new();
+    for token in modifier_tokens {
+        let modifier = parse_modifier(token).ok_or_else(|| format!("无法识别的修饰键 \"{}\"", token))?;
+        if modifiers.contains(&modifier) {
+            return Err(format!("修饰键 {} 重复", modifier));
+        }
+        modifiers.push(modifier);
+    }
+    modifiers.sort_by_key(|m| MODIFIERS.iter().position(|(_, name)| name == m));
+
+    let key = parse_key(key).ok_or_else(|| {
+        if parse_modifier(key).is_some() {
+            "缺少修饰键之外的按键".to_string()
+        } else {
+            format!("无法识别的按键 \"{}\"", key)
+        }
+    })?;
+    modifiers.push(&key);
+    Ok(modifiers.join("+"))
+}
+
+fn parse_modifier(token: &str) -> Option<&'static str> {
+    let upper = token.to_ascii_uppercase();
+    if CMD_OR_CTRL.contains(&upper.as_str()) {
+        return Some(if cfg!(target_os = "macos") { "Super" } else { "Ctrl" });
+    }
+    MODIFIERS.iter().find(|(alias, _)| *alias == upper).map(|(_, name)| *name)
+}
+
+fn parse_key(token: &str) -> Option<String> {
+    if token.is_empty() {
+        return None;
+    }
+    let upper = token.to_ascii_uppercase();
+    // 浏览器的 KeyboardEvent.code 写法：KeyA / Digit1
+    let bare = upper.strip_prefix("KEY").or_else(|| upper.strip_prefix("DIGIT")).unwrap_or(&upper);
+    if bare.len() == 1 && bare.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Some(bare.to_string());
+    }
+    if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        return (1..=24).contains(&n).then(|| format!("F{}", n));
+    }
+    if let Some(n) = upper.strip_prefix("NUMPAD").or_else(|| upper.strip_prefix("NUM")) {
+        if n.len() == 1 && n.chars().all(|c| c.is_ascii_digit()) {
+            return Some(format!("Numpad{}", n));
+        }
+    }
+    if let Some((_, name)) = KEY_ALIASES.iter().find(|(alias, _)| *alias == upper) {
+        return Some(name.to_string());
+    }
+    NAMED_KEYS.iter().find(|name| name.eq_ignore_ascii_case(token)).map(|name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_aliases() {
+        let cases = [
+            ("Control+A", "Ctrl+A"),
+            ("ctrl+a", "Ctrl+A"),
+            ("Option+Esc", "Alt+Escape"),
+            ("Alt+Escape", "Alt+Escape"),
+            ("Ctrl+KeyA", "Ctrl+A"),
+            ("Shift+Digit1", "Shift+1"),
+            ("Command+Return", "Super+Enter"),
+            ("Ctrl+Num5", "Ctrl+Numpad5"),
+            ("Ctrl+numpad5", "Ctrl+Numpad5"),
+            ("Ctrl+NumAdd", "Ctrl+NumpadAdd"),
+            ("Alt+/", "Alt+Slash"),
+            ("Alt+pagedown", "Alt+PageDown"),
+            (" Ctrl + Shift + A ", "Ctrl+Shift+A"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize(input).unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn cmd_or_ctrl_follows_the_platform() {
+        let expected = if cfg!(target_os = "macos") { "Super+S" } else { "Ctrl+S" };
+        assert_eq!(normalize("CmdOrCtrl+S").unwrap(), expected);
+        assert_eq!(normalize("CommandOrControl+S").unwrap(), expected);
+    }
+
+    #[test]
+    fn orders_modifiers() {
+        assert_eq!(normalize("Shift+Ctrl+A").unwrap(), "Ctrl+Shift+A");
+        assert_eq!(normalize("Super+Shift+Alt+Control+F5").unwrap(), "Ctrl+Alt+Shift+Super+F5");
+        assert_eq!(normalize("Shift+Alt+X").unwrap(), normalize("Alt+Shift+X").unwrap());
+    }
+
+    #[test]
+    fn rejects_duplicate_modifiers() {
+        assert!(normalize("Ctrl+Control+A").unwrap_err().contains("重复"));
+        assert!(normalize("Cmd+Super+A").unwrap_err().contains("重复"));
+    }
+
+    #[test]
+    fn rejects_missing_or_unknown_keys() {
+        assert!(normalize("").unwrap_err().contains("不能为空"));
+        assert!(normalize("   ").unwrap_err().contains("不能为空"));
+        assert!(normalize("Ctrl+Shift").unwrap_err().contains("缺少修饰键之外的按键"));
+        assert!(normalize("Ctrl").unwrap_err().contains("缺少修饰键之外的按键"));
+        assert!(normalize("Ctrl+").unwrap_err().contains("无法识别的按键"));
+        assert!(normalize("Ctrl+Banana").unwrap_err().contains("无法识别的按键"));
+        assert!(normalize("Hyper+A").unwrap_err().contains("无法识别的修饰键"));
+        assert!(normalize("A+B").unwrap_err().contains("无法识别的修饰键"));
+    }
+
+    #[test]
+    fn accepts_only_f1_to_f24() {
+        assert_eq!(normalize("F1").unwrap(), "F1");
+        assert_eq!(normalize("f24").unwrap(), "F24");
+        assert_eq!(normalize("Shift+F12").unwrap(), "Shift+F12");
+        assert!(normalize("F0").is_err());
+        assert!(normalize("F25").is_err());
+        assert!(normalize("F256").is_err());
+    }
+}
@@ -0,0 +1,343 @@
+// --- 文件: src-tauri/src/stitch.rs ---
+
+//! 滚动长截图
+//!
+//! 用户框选区域后，后台连续截取该区域，用户滚动页面期间通过逐行匹配找出相邻两帧的垂直重叠，
+//! 把新露出的部分拼接到长图底部。结束后整张长图按首要动作处理（通常是 OCR）。
+
+use image::RgbaImage;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands;
+use crate::settings::{AppSettings, AppState};
+use crate::watch::WatchRegion;
+
+// --- 常量定义 ---
+// 连续截图的间隔
+const CAPTURE_INTERVAL_MS: u64 = 250;
+// 拼接结果的最大高度，防止忘记停止时无限占用内存
+const MAX_STITCHED_HEIGHT: u32 = 30_000;
+// 重叠部分中逐行匹配的比例达到此值才认为找到了滚动位置（容忍少量固定表头等不随页面滚动的行）
+const MIN_MATCH_RATIO: f64 = 0.9;
+// 重叠部分中至少要有这么多“有内容”的行参与匹配，避免纯色背景造成误判
+const MIN_INFORMATIVE_ROWS: usize = 4;
+// 像素通道差不超过此值视为同一颜色（用于判断纯色行）
+const FLAT_ROW_TOLERANCE: u8 = 8;
+
+/// 正在运行的长截图会话，保存在 `AppState` 中
+pub struct LongCaptureSession {
+    pub region: WatchRegion,
+    finish_flag: Arc<AtomicBool>,
+}
+
+impl LongCaptureSession {
+    /// 通知后台循环截取最后一帧后结束并开始拼接
+    pub fn finish(&self) {
+        self.finish_flag.store(true, Ordering::SeqCst);
+    }
+}
+
+// --- 拼接算法 ---
+
+/// 每一行的摘要：行内容哈希，以及该行是否为纯色
+struct RowSignature {
+    hash: u64,
+    flat: bool,
+}
+
+fn row_signatures(image: &RgbaImage) -> Vec<RowSignature> {
+    let row_len = image.width() as usize * 4;
+    image.as_raw()
+        .chunks_exact(row_len.max(1))
+        .map(|row| {
+            let mut hasher = DefaultHasher::new();
+            row.hash(&mut hasher);
+            let first = &row[..4.min(row.len())];
+            let flat = row.chunks_exact(4)
+                .all(|px| px.iter().zip(first).all(|(a, b)| a.abs_diff(*b) <= FLAT_ROW_TOLERANCE));
+            RowSignature { hash: hasher.finish(), flat }
+        })
+        .collect()
+}
+
+/// 计算 `next` 相对 `previous` 向上滚动了多少行。
+///
+/// 返回值 `offset` 满足：`next` 的第 `r` 行与 `previous` 的第 `r + offset` 行相同。
+/// - `Some(0)`: 两帧内容相同（未滚动）。
+/// - `None`: 宽度不同，或找不到足够可信的重叠（滚动过快或内容整体变化）。
+///
+/// 有多个候选位置时选择重叠最多（滚动距离最小）的一个。所有行都相同的画面（如纯色背景、
+/// 重复的条纹）无法判断滚动距离，两帧逐行相同时直接视为未滚动。
+pub fn find_scroll_offset(previous: &RgbaImage, next: &RgbaImage) -> Option<u32> {
+    if previous.width() != next.width() || previous.height() != next.height() || previous.height() == 0 {
+        return None;
+    }
+    let prev_rows = row_signatures(previous);
+    let next_rows = row_signatures(next);
+    if prev_rows.iter().zip(&next_rows).all(|(a, b)| a.hash == b.hash) {
+        return Some(0);
+    }
+    let height = prev_rows.len();
+    // 重叠至少占帧高的十分之一，且不少于有效行数的下限
+    let min_overlap = (height / 10).max(MIN_INFORMATIVE_ROWS);
+
+    (0..height)
+        .take_while(|offset| height - offset >= min_overlap)
+        .find(|&offset| {
+            let overlap = height - offset;
+            let mut matched = 0usize;
+            let mut informative_matched = 0usize;
+            for r in 0..overlap {
+                let prev = &prev_rows[r + offset];
+                if prev.hash == next_rows[r].hash {
+                    matched += 1;
+                    if !prev.flat {
+                        informative_matched += 1;
+                    }
+                }
+            }
+            informative_matched >= MIN_INFORMATIVE_ROWS && matched as f64 / overlap as f64 >= MIN_MATCH_RATIO
+        })
+        .map(|offset| offset as u32)
+}
+
+/// 单帧拼接的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum StitchOutcome {
+    // 拼接成功，新增了这么多行
+    Appended(u32),
+    // 画面没有滚动，忽略本帧
+    Unchanged,
+    // 找不到重叠，整帧直接追加在底部（用户滚动过快时可能出现断层）
+    NoOverlap,
+    // 已达到最大高度，忽略本帧
+    LimitReached,
+}
+
+/// 增量拼接器：只保存拼接结果和上一帧，内存占用与帧数无关
+pub struct Stitcher {
+    stitched: RgbaImage,
+    last_frame: RgbaImage,
+}
+
+impl Stitcher {
+    pub fn new(first_frame: RgbaImage) -> Self {
+        Self { stitched: first_frame.clone(), last_frame: first_frame }
+    }
+
+    /// 将新的一帧拼接到底部
+    pub fn push(&mut self, frame: RgbaImage) -> StitchOutcome {
+        if frame.width() != self.stitched.width() {
+            return StitchOutcome::NoOverlap;
+        }
+        if self.stitched.height() >= MAX_STITCHED_HEIGHT {
+            return StitchOutcome::LimitReached;
+        }
+
+        let (outcome, new_rows_start) = match find_scroll_offset(&self.last_frame, &frame) {
+            Some(0) => return StitchOutcome::Unchanged,
+            Some(offset) => (StitchOutcome::Appended(offset), frame.height() - offset),
+            None => (StitchOutcome::NoOverlap, 0),
+        };
+
+        let new_rows = (frame.height() - new_rows_start).min(MAX_STITCHED_HEIGHT - self.stitched.height());
+        let mut combined = RgbaImage::new(self.stitched.width(), self.stitched.height() + new_rows);
+        image::imageops::replace(&mut combined, &self.stitched, 0, 0);
+        let tail = image::imageops::crop_imm(&frame, 0, new_rows_start, frame.width(), new_rows).to_image();
+        image::imageops::replace(&mut combined, &tail, 0, self.stitched.height() as i64);
+
+        self.stitched = combined;
+        self.last_frame = frame;
+        outcome
+    }
+
+    pub fn height(&self) -> u32 {
+        self.stitched.height()
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.stitched
+    }
+}
+
+// --- Tauri 命令定义 ---
+
+/// 由选区遮罩调用：开始对选定区域进行滚动长截图
+#[tauri::command]
+pub async fn start_long_capture(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    x: f64, y: f64, width: f64, height: f64,
+) -> Result<(), String> {
+    let region = WatchRegion { x: x as u32, y: y as u32, width: width as u32, height: height as u32 };
+    println!("[LONG] 开始长截图区域: {:?}", region);
+
+    // 截图锁在整个长截图期间保持占用，拼接处理完成后由 process_captured_image 释放
+    state.fullscreen_capture.lock().unwrap().take();
+
+    let finish_flag = Arc::new(AtomicBool::new(false));
+    *state.long_capture_session.lock().unwrap() = Some(LongCaptureSession { region, finish_flag: finish_flag.clone() });
+
+    let settings = state.settings.lock().unwrap().clone();
+    commands::send_notification(&app, "📜 长截图已开始", "请滚动页面，完成后再次按下长截图快捷键。");
+
+    tokio::spawn(run_long_capture_loop(app, region, settings, finish_flag));
+    Ok(())
+}
+
+/// 结束当前的长截图，开始拼接和处理
+#[tauri::command]
+pub fn finish_long_capture(state: State<'_, AppState>) -> bool {
+    finish_long_capture_session(&state)
+}
+
+// --- 辅助函数 ---
+
+/// 结束当前的长截图会话。如果确实有会话在运行，返回 `true`。
+pub fn finish_long_capture_session(state: &AppState) -> bool {
+    match state.long_capture_session.lock().unwrap().as_ref() {
+        Some(session) => {
+            println!("[LONG] 结束长截图: {:?}", session.region);
+            session.finish();
+            true
+        }
+        None => false,
+    }
+}
+
+async fn run_long_capture_loop(app: AppHandle, region: WatchRegion, settings: AppSettings, finish_flag: Arc<AtomicBool>) {
+    let mut stitcher: Option<Stitcher> = None;
+
+    loop {
+        // 先读取结束标记，保证结束前还会截取最后一帧
+        let finishing = finish_flag.load(Ordering::SeqCst);
+
+        let frame = tokio::task::spawn_blocking(move || {
+            let screen = crate::capture::capture_fullscreen()?;
+            crate::capture::crop_to_region(&screen, region.x, region.y, region.width, region.height)
+        }).await;
+
+        match frame {
+            Ok(Ok(frame)) => match stitcher.as_mut() {
+                None => stitcher = Some(Stitcher::new(frame)),
+                Some(s) => match s.push(frame) {
+                    StitchOutcome::Appended(rows) => println!("[LONG] 拼接 {} 行，当前高度 {}", rows, s.height()),
+                    StitchOutcome::NoOverlap => println!("[LONG] 警告: 未找到重叠，整帧追加（滚动可能过快）"),
+                    StitchOutcome::LimitReached => {
+                        println!("[LONG] 已达到最大高度 {}，自动结束。", MAX_STITCHED_HEIGHT);
+                        break;
+                    }
+                    StitchOutcome::Unchanged => {}
+                },
+            },
+            Ok(Err(e)) => eprintln!("[LONG] 截图失败: {}", e),
+            Err(e) => eprintln!("[LONG] 截图任务异常: {}", e),
+        }
+
+        if finishing {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(CAPTURE_INTERVAL_MS)).await;
+    }
+
+    let state: State<AppState> = app.state();
+    state.long_capture_session.lock().unwrap().take();
+
+    match stitcher {
+        Some(stitcher) => {
+            let image = stitcher.into_image();
+            println!("[LONG] 长截图完成，尺寸: {}x{}", image.width(), image.height());
+            if let Some(loading_window) = app.get_window("loading") {
+                let _ = loading_window.center();
+                let _ = loading_window.show();
+            }
+            commands::process_captured_image(&app, image, &settings).await;
+        }
+        None => {
+            commands::hide_loading_and_release_lock(&app);
+            commands::send_notification(&app, "❌ 长截图失败", "未能截取到任何画面。");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const WIDTH: u32 = 40;
+
+    /// 生成一张“网页”：每一行的图案都不同，便于逐行匹配
+    fn page(height: u32) -> RgbaImage {
+        RgbaImage::from_fn(WIDTH, height, |x, y| {
+            let v = (y.wrapping_mul(2_654_435_761) ^ x.wrapping_mul(40_503)) as u8;
+            Rgba([v, v.wrapping_mul(3), y as u8, 255])
+        })
+    }
+
+    /// 模拟滚动到 `scroll` 位置时可见的一帧
+    fn frame_at(page: &RgbaImage, scroll: u32, height: u32) -> RgbaImage {
+        image::imageops::crop_imm(page, 0, scroll, WIDTH, height).to_image()
+    }
+
+    #[test]
+    fn detects_known_scroll_offsets() {
+        let page = page(400);
+        let first = frame_at(&page, 0, 100);
+        for offset in [1, 7, 30, 60, 85] {
+            let next = frame_at(&page, offset, 100);
+            assert_eq!(find_scroll_offset(&first, &next), Some(offset), "offset {}", offset);
+        }
+        assert_eq!(find_scroll_offset(&first, &first.clone()), Some(0));
+    }
+
+    #[test]
+    fn stitches_scrolled_frames_to_the_page_height() {
+        let page = page(400);
+        let mut stitcher = Stitcher::new(frame_at(&page, 0, 100));
+        assert_eq!(stitcher.push(frame_at(&page, 40, 100)), StitchOutcome::Appended(40));
+        assert_eq!(stitcher.push(frame_at(&page, 40, 100)), StitchOutcome::Unchanged);
+        assert_eq!(stitcher.push(frame_at(&page, 110, 100)), StitchOutcome::Appended(70));
+        assert_eq!(stitcher.push(frame_at(&page, 300, 100)), StitchOutcome::NoOverlap);
+        let stitched = stitcher.into_image();
+        // 0..210 拼接无断层，最后一帧没有重叠，整帧追加
+        assert_eq!(stitched.height(), 210 + 100);
+        assert_eq!(frame_at(&stitched, 0, 210), frame_at(&page, 0, 210));
+    }
+
+    #[test]
+    fn frame_without_overlap_is_appended_whole() {
+        let page = page(400);
+        let first = frame_at(&page, 0, 100);
+        let unrelated = frame_at(&page, 250, 100);
+        assert_eq!(find_scroll_offset(&first, &unrelated), None);
+
+        let mut stitcher = Stitcher::new(first);
+        assert_eq!(stitcher.push(unrelated), StitchOutcome::NoOverlap);
+        assert_eq!(stitcher.height(), 200);
+    }
+
+    #[test]
+    fn frames_with_identical_rows_are_treated_as_unscrolled() {
+        // 每一行都相同的条纹图案：任何滚动距离都能匹配，无法判断是否滚动
+        let stripes = RgbaImage::from_fn(WIDTH, 100, |x, _| Rgba([(x * 6) as u8, 0, 0, 255]));
+        assert_eq!(find_scroll_offset(&stripes, &stripes.clone()), Some(0));
+
+        // 纯色画面没有可匹配的内容行，也不应被当作新内容反复追加
+        let blank = RgbaImage::from_pixel(WIDTH, 100, Rgba([255, 255, 255, 255]));
+        let mut stitcher = Stitcher::new(blank.clone());
+        assert_eq!(stitcher.push(blank), StitchOutcome::Unchanged);
+        assert_eq!(stitcher.height(), 100);
+    }
+
+    #[test]
+    fn mismatched_sizes_have_no_offset() {
+        let page = page(200);
+        assert_eq!(find_scroll_offset(&frame_at(&page, 0, 100), &frame_at(&page, 0, 90)), None);
+    }
+}
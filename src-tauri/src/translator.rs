@@ -1,14 +1,29 @@
 // 文件: src-tauri\src\translator.rs
 
-use serde::{Deserialize};
-use tauri::AppHandle;
-use std::process::Command;
-// --- 核心修复：启用 GBK 解码器 ---
-use encoding_rs::GBK;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::encoding::decode_engine_output;
+use crate::engine::{self, EngineId};
+use crate::settings::{AppSettings, AppState};
+use crate::worker::{self, WorkerError};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
+// --- 常量定义 ---
+// 单次送入引擎的最大字符数，超出时按句子切分后逐段翻译
+const MAX_CHUNK_CHARS: usize = 400;
+// 通过命令行参数传递文本时的长度上限（Windows 命令行总长度限制为 32K）
+const MAX_ARGV_CHARS: usize = 8000;
+// 本地翻译引擎支持的目标语言
+pub const SUPPORTED_TARGET_LANGS: [&str; 3] = ["zh", "en", "ja"];
+
 #[derive(Debug, Deserialize)]
 struct LocalTranslationResponse {
     code: i32,
@@ -16,6 +31,42 @@ struct LocalTranslationResponse {
     error_message: Option<String>,
 }
 
+/// 通过 stdin 或请求文件发送给引擎的翻译请求
+#[derive(Serialize)]
+struct LocalTranslationRequest<'a> {
+    text: &'a str,
+    source: &'a str,
+    target: &'a str,
+}
+
+/// 向引擎传递翻译文本的方式，按优先级排列
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Transport {
+    // `--stdin`：从标准输入读取 JSON 请求
+    Stdin,
+    // `--request-file <path>`：从临时文件读取 JSON 请求
+    RequestFile,
+    // `--text <text>`：旧版引擎只支持命令行参数
+    Argv,
+}
+
+const TRANSPORTS: [Transport; 3] = [Transport::Stdin, Transport::RequestFile, Transport::Argv];
+
+// 记住引擎实际支持的传递方式（TRANSPORTS 中的下标），避免每次翻译都重新探测
+static PREFERRED_TRANSPORT: AtomicUsize = AtomicUsize::new(0);
+
+/// 清除记住的传递方式。安装、回滚或卸载引擎后，新版本支持的方式可能不同，下次翻译时重新探测。
+pub fn reset_transport() {
+    PREFERRED_TRANSPORT.store(0, Ordering::SeqCst);
+}
+
+/// 单次调用引擎的失败原因
+enum CallError {
+    // 引擎不支持该传递方式（旧版引擎不认识新参数），应尝试下一种
+    Unsupported(String),
+    Failed(String),
+}
+
 #[async_trait::async_trait]
 pub trait Translator {
     async fn translate(
@@ -33,6 +84,118 @@ impl LocalTranslator {
     pub fn new(app_handle: AppHandle) -> Self {
         Self { app_handle }
     }
+
+    /// 翻译一段文本，按优先级尝试各种传递方式
+    fn translate_chunk(&self, exe_path: &Path, request: &LocalTranslationRequest, settings: &AppSettings) -> Result<String, String> {
+        let start = PREFERRED_TRANSPORT.load(Ordering::SeqCst);
+        for (index, transport) in TRANSPORTS.iter().enumerate().skip(start) {
+            match self.run_engine(exe_path, *transport, request, settings) {
+                Ok(translated) => {
+                    if index != start {
+                        println!("[TRANSLATOR] 引擎使用 {:?} 方式传递文本。", transport);
+                        PREFERRED_TRANSPORT.store(index, Ordering::SeqCst);
+                    }
+                    return Ok(translated);
+                }
+                Err(CallError::Unsupported(reason)) => {
+                    println!("[TRANSLATOR] 引擎不支持 {:?} 方式 ({})，尝试下一种。", transport, reason);
+                }
+                Err(CallError::Failed(e)) => return Err(e),
+            }
+        }
+        Err("翻译引擎不支持任何可用的文本传递方式，请更新翻译引擎。".to_string())
+    }
+
+    /// 以指定的传递方式调用一次引擎进程
+    fn run_engine(
+        &self,
+        exe_path: &Path,
+        transport: Transport,
+        request: &LocalTranslationRequest,
+        settings: &AppSettings,
+    ) -> Result<String, CallError> {
+        let request_json = serde_json::to_string(request)
+            .map_err(|e| CallError::Failed(format!("序列化翻译请求失败: {}", e)))?;
+
+        // 确保工作目录为可执行文件所在目录，以便加载依赖
+        let working_dir = exe_path.parent().unwrap();
+        let mut command = Command::new(exe_path);
+        command.current_dir(working_dir).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        let mut request_file = None;
+        match transport {
+            Transport::Stdin => {
+                command.arg("--stdin").stdin(Stdio::piped());
+            }
+            Transport::RequestFile => {
+                let path = self.request_file_path();
+                fs::write(&path, &request_json)
+                    .map_err(|e| CallError::Failed(format!("写入翻译请求文件失败: {}", e)))?;
+                command.arg("--request-file").arg(&path).stdin(Stdio::null());
+                request_file = Some(path);
+            }
+            Transport::Argv => {
+                if request.text.chars().count() > MAX_ARGV_CHARS {
+                    return Err(CallError::Unsupported("文本超出命令行长度限制".to_string()));
+                }
+                command.args(["--text", request.text, "--source", request.source, "--target", request.target])
+                    .stdin(Stdio::null());
+            }
+        }
+
+        #[cfg(windows)]
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        let output = command.spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // 旧版引擎不读取 stdin 时会直接退出，写入失败 (Broken pipe) 交给退出状态判断
+                    let _ = stdin.write_all(request_json.as_bytes());
+                }
+                child.wait_with_output()
+            });
+        if let Some(path) = &request_file {
+            let _ = fs::remove_file(path);
+        }
+        let output = output.map_err(|e| CallError::Failed(format!("执行翻译进程失败: {}", e)))?;
+
+        println!("[TRANSLATOR] 进程执行完毕 ({:?}). Status: {:?}", transport, output.status);
+
+        if !output.status.success() {
+            let stderr = decode_engine_output(&output.stderr, &settings.engine_output_encoding);
+            if transport != Transport::Argv && is_unsupported_argument_error(&stderr) {
+                return Err(CallError::Unsupported(stderr.trim().lines().last().unwrap_or_default().to_string()));
+            }
+            eprintln!("[TRANSLATOR] 进程执行出错, Status: {:?}, Stderr: {}", output.status, stderr);
+            return Err(CallError::Failed(format!("翻译进程执行出错: {}", stderr)));
+        }
+
+        // 引擎输出的编码因平台而异（中文 Windows 上常为 GBK），先检测编码再解析 JSON
+        let stdout = decode_engine_output(&output.stdout, &settings.engine_output_encoding);
+        println!("[TRANSLATOR] 原始输出 (decoded stdout): {}", stdout);
+
+        let response: LocalTranslationResponse = serde_json::from_str(stdout.trim())
+            .map_err(|e| CallError::Failed(format!("解析翻译结果JSON失败: {}. 原始输出: {}", e, stdout)))?;
+        println!("[TRANSLATOR] 解析到的响应: {:?}", response);
+
+        match response.code {
+            200 => response.translated_text.ok_or_else(|| CallError::Failed("翻译成功但未返回文本".to_string())),
+            _ => Err(CallError::Failed(response.error_message.unwrap_or_else(|| "翻译器返回未知错误".to_string()))),
+        }
+    }
+
+    /// 临时请求文件路径，放在应用缓存目录的 tmp 下
+    fn request_file_path(&self) -> PathBuf {
+        let dir = self.app_handle.path_resolver().app_cache_dir()
+            .map(|d| d.join("tmp"))
+            .unwrap_or_else(std::env::temp_dir);
+        let _ = fs::create_dir_all(&dir);
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        dir.join(format!("translate-request-{}.json", timestamp))
+    }
 }
 
 #[async_trait::async_trait]
@@ -42,69 +205,213 @@ impl Translator for LocalTranslator {
         text: &str,
         target_lang: &str,
     ) -> Result<String, String> {
-        // --- 修改核心逻辑：指向本地数据目录 ---
-        let local_data_dir = self.app_handle.path_resolver().app_local_data_dir()
-            .ok_or_else(|| "无法获取本地数据目录".to_string())?;
+        // 按设置中的自定义路径或当前平台的默认位置查找翻译引擎
+        let state: State<AppState> = self.app_handle.state();
+        let settings = state.settings.lock().unwrap().clone();
+        let translator_exe_path = engine::find_exe(&self.app_handle, &settings, EngineId::Translator)?;
+        engine::mark_used(&self.app_handle, EngineId::Translator);
 
-        // 可执行文件名更改为 translate_engine.exe
-        let translator_exe_path = local_data_dir.join("translate_engine.exe");
+        let source_lang = if target_lang == "en" { "zh" } else { "en" };
 
-        println!("[TRANSLATOR] 检查翻译引擎: 路径='{:?}', 是否存在={}", translator_exe_path, translator_exe_path.exists());
+        println!("[TRANSLATOR] 翻译请求: 源语言='{}', 目标语言='{}', 文本='{}...'", source_lang, target_lang, text.chars().take(50).collect::<String>());
 
-        if !translator_exe_path.exists() {
-            return Err("找不到翻译引擎，请在设置页面下载安装。".to_string());
+        let chunks = split_into_chunks(text, MAX_CHUNK_CHARS);
+        if chunks.len() > 1 {
+            println!("[TRANSLATOR] 文本较长 ({} 字)，分为 {} 段翻译。", text.chars().count(), chunks.len());
         }
 
-        let source_lang = if target_lang == "en" { "zh" } else { "en" };
+        // 逐段翻译，并保留每段首尾的空白（换行），按原顺序拼接
+        let mut translated = String::new();
+        for chunk in chunks {
+            let core = chunk.trim();
+            if core.is_empty() {
+                translated.push_str(chunk);
+                continue;
+            }
+            let leading = &chunk[..chunk.len() - chunk.trim_start().len()];
+            let trailing = &chunk[chunk.trim_end().len()..];
+            let piece = match worker::translate(&self.app_handle, &translator_exe_path, core, source_lang, target_lang, &settings).await {
+                Ok(piece) => piece,
+                Err(WorkerError::Rejected(e)) => return Err(e),
+                // 常驻进程不可用时，回退为每段启动一次引擎进程
+                Err(WorkerError::Unsupported(_)) | Err(WorkerError::Crashed(_)) => {
+                    let request = LocalTranslationRequest { text: core, source: source_lang, target: target_lang };
+                    self.translate_chunk(&translator_exe_path, &request, &settings)?
+                }
+            };
+            translated.push_str(leading);
+            translated.push_str(piece.trim());
+            translated.push_str(trailing);
+        }
+        Ok(translated)
+    }
+}
 
-        println!("[TRANSLATOR] 翻译请求: 源语言='{}', 目标语言='{}', 文本='{}...'", source_lang, target_lang, text.chars().take(50).collect::<String>());
+pub fn get_translator(app: &AppHandle) -> Box<dyn Translator + Send + Sync> {
+    Box::new(LocalTranslator::new(app.clone()))
+}
 
-        // 确保工作目录为可执行文件所在目录，以便加载依赖
-        let working_dir = translator_exe_path.parent().unwrap();
+// --- 文本切分 ---
 
-        let mut command = Command::new(&translator_exe_path);
-        command.current_dir(working_dir)
-            .args(&[
-                "--text", text,
-                "--source", source_lang,
-                "--target", target_lang,
-            ]);
+/// 判断引擎的错误输出是否表示“不认识该参数”（argparse 等命令行解析库的报错）
+pub(crate) fn is_unsupported_argument_error(stderr: &str) -> bool {
+    let lower = stderr.to_lowercase();
+    ["unrecognized arguments", "unknown option", "unknown argument", "the following arguments are required"]
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+}
 
-        #[cfg(windows)]
-        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+/// 把长文本切分为不超过 `max_chars` 个字符的片段，尽量在句子边界处切分。
+///
+/// 每个片段都包含其后的空白字符，所有片段按顺序拼接后与原文完全一致。
+/// 单个句子超过上限时，在上限内最后一个空白处切分；没有空白（如长段中文）时直接按字符数切分。
+pub fn split_into_chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0;
+    let mut chunk_chars = 0;
 
-        let output = command
-            .output()
-            .map_err(|e| format!("执行翻译进程失败: {}", e))?;
+    for sentence in split_sentences(text) {
+        let sentence_chars = sentence.chars().count();
+        if chunk_chars > 0 && chunk_chars + sentence_chars > max_chars {
+            chunks.push(&text[chunk_start..offset_of(text, sentence)]);
+            chunk_start = offset_of(text, sentence);
+            chunk_chars = 0;
+        }
+        if sentence_chars > max_chars {
+            // 超长句子单独硬切分
+            let pieces = hard_split(sentence, max_chars);
+            let (last, rest) = pieces.split_last().unwrap();
+            chunks.extend(rest.iter().copied());
+            chunk_start = offset_of(text, last);
+            chunk_chars = last.chars().count();
+        } else {
+            chunk_chars += sentence_chars;
+        }
+    }
+    if chunk_start < text.len() {
+        chunks.push(&text[chunk_start..]);
+    }
+    chunks
+}
 
-        println!("[TRANSLATOR] 进程执行完毕. Status: {:?}", output.status);
+/// 按句末标点切分句子，标点后的空白归入前一句
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        let is_boundary = match c {
+            '。' | '！' | '？' | '；' | '…' | '\n' => true,
+            // 英文句号后必须跟空白，避免把小数和缩写切开
+            '.' | '!' | '?' | ';' => next.map_or(true, char::is_whitespace),
+            _ => false,
+        };
+        if !is_boundary {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, n)) = chars.peek() {
+            if !n.is_whitespace() {
+                break;
+            }
+            end = j + n.len_utf8();
+            chars.next();
+        }
+        sentences.push(&text[start..end]);
+        start = end;
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
 
-        if !output.status.success() {
-            // 对于 stderr，我们仍然可以假设它是 UTF-8 或者使用 GBK 解码
-            let (decoded_stderr, _, _) = GBK.decode(&output.stderr);
-            let stderr = decoded_stderr.into_owned();
-            eprintln!("[TRANSLATOR] 进程执行出错, Status: {:?}, Stderr: {}", output.status, stderr);
-            return Err(format!("翻译进程执行出错: {}", stderr));
+/// 将超长句子切分为不超过 `max_chars` 个字符的片段，优先在空白处切分
+fn hard_split(sentence: &str, max_chars: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = sentence;
+    while rest.chars().count() > max_chars {
+        let limit = rest.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(rest.len());
+        let cut = rest[..limit]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .filter(|&i| i > 0)
+            .unwrap_or(limit);
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// 子串在原文中的字节偏移（子串必须切自原文）
+fn offset_of(text: &str, part: &str) -> usize {
+    part.as_ptr() as usize - text.as_ptr() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_lossless(text: &str, chunks: &[&str], max_chars: usize) {
+        assert_eq!(chunks.concat(), text);
+        for chunk in chunks {
+            assert!(chunk.chars().count() <= max_chars, "片段过长: {:?}", chunk);
         }
+    }
 
-        // --- 核心修复：使用 GBK 解码 stdout ---
-        // `translate_engine.exe` 在 Windows 上很可能以 GBK 编码输出中文字符。
-        // 我们需要先用 GBK 解码器将原始字节转换为正确的 UTF-8 字符串，再进行 JSON 解析。
-        let (decoded_stdout, _, _) = GBK.decode(&output.stdout);
-        let stdout = decoded_stdout.into_owned();
-        println!("[TRANSLATOR] 原始输出 (GBK decoded stdout): {}", stdout);
+    #[test]
+    fn splits_sentences_on_cjk_and_latin_punctuation() {
+        let text = "第一句。第二句！Third one? Pi is 3.14 here. 最后";
+        assert_eq!(
+            split_sentences(text),
+            vec!["第一句。", "第二句！", "Third one? ", "Pi is 3.14 here. ", "最后"]
+        );
+        assert_eq!(split_sentences("行一\n\n行二"), vec!["行一\n\n", "行二"]);
+        assert!(split_sentences("").is_empty());
+    }
 
-        let response: LocalTranslationResponse = serde_json::from_str(&stdout)
-            .map_err(|e| format!("解析翻译结果JSON失败: {}. 原始输出: {}", e, stdout))?;
-        println!("[TRANSLATOR] 解析到的响应: {:?}", response);
+    #[test]
+    fn short_text_stays_in_one_chunk() {
+        assert_eq!(split_into_chunks("你好。世界。", 400), vec!["你好。世界。"]);
+        assert!(split_into_chunks("", 400).is_empty());
+    }
 
-        match response.code {
-            200 => response.translated_text.ok_or_else(|| "翻译成功但未返回文本".to_string()),
-            _ => Err(response.error_message.unwrap_or_else(|| "翻译器返回未知错误".to_string())),
+    #[test]
+    fn groups_sentences_up_to_the_limit() {
+        let text = "一二三。四五六。七八九。十";
+        let chunks = split_into_chunks(text, 8);
+        assert_eq!(chunks, vec!["一二三。四五六。", "七八九。十"]);
+        assert_lossless(text, &chunks, 8);
+    }
+
+    #[test]
+    fn over_long_sentence_is_hard_split() {
+        let text = "短句。这是一个没有任何标点也没有空白的非常长的中文句子需要被硬切分。结尾。";
+        let chunks = split_into_chunks(text, 10);
+        assert_lossless(text, &chunks, 10);
+        assert_eq!(chunks[0], "短句。");
+        assert!(chunks.len() > 3);
+    }
+
+    #[test]
+    fn hard_split_prefers_whitespace() {
+        let pieces = hard_split("alpha beta gamma delta", 12);
+        assert_eq!(pieces, vec!["alpha beta ", "gamma delta"]);
+    }
+
+    #[test]
+    fn hard_split_keeps_multi_byte_characters_whole() {
+        // 上限落在多字节字符中间的字节位置时也不能把字符切开
+        let sentence = "a中文字符😀表情测试";
+        for max_chars in 1..=sentence.chars().count() {
+            let pieces = hard_split(sentence, max_chars);
+            assert_lossless(sentence, &pieces, max_chars);
         }
+        assert_eq!(hard_split("中文字符", 3), vec!["中文字", "符"]);
     }
 }
-
-pub fn get_translator(app: &AppHandle) -> Box<dyn Translator + Send + Sync> {
-    Box::new(LocalTranslator::new(app.clone()))
-}
\ No newline at end of file
@@ -0,0 +1,412 @@
+// --- 文件: src-tauri/src/watch.rs ---
+
+//! 区域监视模式
+//!
+//! 用户框选一次区域后，后台按固定间隔重新截取该区域，
+//! 只有当画面确实发生变化、且识别出的文字与上一次不同时，才重新执行 OCR 与翻译，
+//! 并将结果推送到置顶的结果窗口。
+
+use image::{imageops, GrayImage, RgbaImage};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+
+use crate::commands;
+use crate::settings::{AppSettings, AppState};
+use crate::translator;
+
+// --- 常量定义 ---
+// 指纹缩略图的最大边长，足以反映文字内容的变化，同时计算量很小
+const FINGERPRINT_SIZE: u32 = 64;
+// 单个像素的亮度差超过此值才算作变化，用于过滤光标闪烁和渲染噪点
+const PIXEL_NOISE_TOLERANCE: u8 = 24;
+// 截图间隔的下限，防止配置过小导致 CPU 占用过高
+const MIN_INTERVAL_MS: u64 = 300;
+// OCR 对同一画面偶尔会识别出个别不同的字符：不少于此字数的文本与上一次相差不超过
+// NEAR_DUPLICATE_MAX_EDITS 个字符时视为相同。短文本只按完全相同去重，以免漏掉数字、名字的变化
+const NEAR_DUPLICATE_MIN_CHARS: usize = 40;
+const NEAR_DUPLICATE_MAX_EDITS: usize = 1;
+
+/// 被监视的屏幕区域（原始截图像素坐标）
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct WatchRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 正在运行的监视会话，保存在 `AppState` 中
+pub struct WatchSession {
+    pub region: WatchRegion,
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl WatchSession {
+    /// 通知后台循环在下一次迭代时退出
+    pub fn stop(&self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+    }
+}
+
+// --- 变化检测 ---
+
+/// 计算帧指纹：缩小后的灰度图。
+///
+/// 缩放会抹掉抗锯齿和压缩带来的细微差异，只保留文字和布局层面的变化。
+pub fn fingerprint(frame: &RgbaImage) -> GrayImage {
+    let gray = imageops::grayscale(frame);
+    let (width, height) = frame.dimensions();
+    if width == 0 || height == 0 {
+        return gray;
+    }
+    let scale = (FINGERPRINT_SIZE as f64 / width.max(height) as f64).min(1.0);
+    let target_width = ((width as f64 * scale).round() as u32).max(1);
+    let target_height = ((height as f64 * scale).round() as u32).max(1);
+    imageops::resize(&gray, target_width, target_height, imageops::FilterType::Triangle)
+}
+
+/// 比较两个指纹，返回发生变化的像素比例 (0.0 ~ 1.0)。
+///
+/// 尺寸不同的指纹视为完全不同。
+pub fn frame_difference(previous: &GrayImage, current: &GrayImage) -> f64 {
+    if previous.dimensions() != current.dimensions() {
+        return 1.0;
+    }
+    let total = previous.as_raw().len();
+    if total == 0 {
+        return 0.0;
+    }
+    let changed = previous.as_raw().iter()
+        .zip(current.as_raw())
+        .filter(|(a, b)| a.abs_diff(**b) > PIXEL_NOISE_TOLERANCE)
+        .count();
+    changed as f64 / total as f64
+}
+
+/// 逐帧变化检测器
+///
+/// 只有在检测到变化时才更新基准帧，这样缓慢渐变的画面也会在累积到阈值后被识别出来。
+pub struct ChangeDetector {
+    threshold: f64,
+    baseline: Option<GrayImage>,
+}
+
+impl ChangeDetector {
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold, baseline: None }
+    }
+
+    /// 判断新的一帧相对基准帧是否发生了变化。第一帧总是视为变化。
+    pub fn has_changed(&mut self, frame: &RgbaImage) -> bool {
+        let current = fingerprint(frame);
+        let changed = match &self.baseline {
+            Some(baseline) => frame_difference(baseline, &current) >= self.threshold,
+            None => true,
+        };
+        if changed {
+            self.baseline = Some(current);
+        }
+        changed
+    }
+}
+
+/// 文本去重器：画面变化但识别结果相同时（例如光标移动、背景动画），跳过翻译和推送。
+/// 较长的文本允许一个字符的 OCR 抖动。
+#[derive(Default)]
+pub struct TextDeduplicator {
+    last_text: Option<String>,
+}
+
+impl TextDeduplicator {
+    /// 如果文本与上一次推送的内容不同（忽略空白差异和长文本中单个字符的差异），记录并返回 `true`。
+    pub fn is_new(&mut self, text: &str) -> bool {
+        let normalized = normalize_text(text);
+        if normalized.is_empty() {
+            return false;
+        }
+        if let Some(last) = &self.last_text {
+            if is_near_duplicate(last, &normalized) {
+                return false;
+            }
+        }
+        self.last_text = Some(normalized);
+        true
+    }
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_near_duplicate(last: &str, text: &str) -> bool {
+    if last == text {
+        return true;
+    }
+    let shortest = last.chars().count().min(text.chars().count());
+    shortest >= NEAR_DUPLICATE_MIN_CHARS && edit_distance(last, text) <= NEAR_DUPLICATE_MAX_EDITS
+}
+
+/// 两段文本的字符编辑距离
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // 只保留一行的动态规划
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// --- Tauri 命令定义 ---
+
+#[tauri::command]
+pub async fn start_watch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    x: f64, y: f64, width: f64, height: f64,
+) -> Result<(), String> {
+    let region = WatchRegion { x: x as u32, y: y as u32, width: width as u32, height: height as u32 };
+    println!("[WATCH] 开始监视区域: {:?}", region);
+
+    // 选区已经完成，释放截图锁并丢弃缓存的全屏截图
+    state.fullscreen_capture.lock().unwrap().take();
+    state.is_capturing.store(false, Ordering::SeqCst);
+
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut session = state.watch_session.lock().unwrap();
+        if let Some(old) = session.take() {
+            println!("[WATCH] 停止之前的监视会话: {:?}", old.region);
+            old.stop();
+        }
+        *session = Some(WatchSession { region, stop_flag: stop_flag.clone() });
+    }
+
+    let settings = state.settings.lock().unwrap().clone();
+    crate::prepare_watch_results_window(&app);
+    commands::send_notification(&app, "👀 监视已开始", "区域内容变化时会自动识别并翻译，再次按下监视快捷键即可停止。");
+
+    tokio::spawn(run_watch_loop(app, region, settings, stop_flag));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_watch(state: State<'_, AppState>) -> bool {
+    stop_watch_session(&state)
+}
+
+#[tauri::command]
+pub fn get_watch_status(state: State<'_, AppState>) -> Option<WatchRegion> {
+    state.watch_session.lock().unwrap().as_ref().map(|session| session.region)
+}
+
+// --- 辅助函数 ---
+
+/// 停止当前的监视会话。如果确实有会话在运行，返回 `true`。
+pub fn stop_watch_session(state: &AppState) -> bool {
+    match state.watch_session.lock().unwrap().take() {
+        Some(session) => {
+            println!("[WATCH] 停止监视区域: {:?}", session.region);
+            session.stop();
+            true
+        }
+        None => false,
+    }
+}
+
+async fn run_watch_loop(app: AppHandle, region: WatchRegion, settings: AppSettings, stop_flag: Arc<AtomicBool>) {
+    let interval = Duration::from_millis(settings.watch_interval_ms.max(MIN_INTERVAL_MS));
+    let mut detector = ChangeDetector::new(settings.watch_change_threshold);
+    let mut deduplicator = TextDeduplicator::default();
+
+    let frame_path = match app.path_resolver().app_cache_dir() {
+        Some(dir) => dir.join("tmp").join("watch-latest.png"),
+        None => {
+            eprintln!("[WATCH] 错误: 无法获取应用缓存目录，监视终止。");
+            return;
+        }
+    };
+    if let Some(parent) = frame_path.parent() {
+        let _ = tokio::fs::create_dir_all(parent).await;
+    }
+
+    while !stop_flag.load(Ordering::SeqCst) {
+        let frame = tokio::task::spawn_blocking(move || {
+            let screen = crate::capture::capture_fullscreen()?;
+            crate::capture::crop_to_region(&screen, region.x, region.y, region.width, region.height)
+        }).await;
+
+        match frame {
+            Ok(Ok(frame)) => {
+                if detector.has_changed(&frame) {
+                    process_changed_frame(&app, &frame, &frame_path, &settings, &mut deduplicator, &stop_flag).await;
+                }
+            }
+            Ok(Err(e)) => eprintln!("[WATCH] 截图失败: {}", e),
+            Err(e) => eprintln!("[WATCH] 截图任务异常: {}", e),
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+    println!("[WATCH] 监视循环已退出。");
+}
+
+async fn process_changed_frame(
+    app: &AppHandle,
+    frame: &RgbaImage,
+    frame_path: &std::path::Path,
+    settings: &AppSettings,
+    deduplicator: &mut TextDeduplicator,
+    stop_flag: &AtomicBool,
+) {
+    if let Err(e) = frame.save(frame_path) {
+        eprintln!("[WATCH] 保存监视帧失败: {}", e);
+        return;
+    }
+    let frame_path_str = frame_path.to_string_lossy().to_string();
+
+    // OCR 会同步等待引擎进程退出，放到阻塞线程中执行，避免占住异步运行时的工作线程
+    let ocr = tokio::task::spawn_blocking({
+        let app = app.clone();
+        let frame_path_str = frame_path_str.clone();
+        let settings = settings.clone();
+        move || commands::perform_ocr(&app, &frame_path_str, &settings)
+    }).await;
+    let ocr = match ocr {
+        Ok(Ok(ocr)) => ocr,
+        Ok(Err(e)) => {
+            println!("[WATCH] 本帧未得到识别结果: {}", e);
+            return;
+        }
+        Err(e) => {
+            eprintln!("[WATCH] 识别任务异常: {}", e);
+            return;
+        }
+    };
+    let text = ocr.text.clone();
+    if !deduplicator.is_new(&text) {
+        println!("[WATCH] 画面有变化但文字未变，跳过翻译。");
+        return;
+    }
+
+    // 先推送原文，让用户尽快看到变化
+    commands::cache_result(app, Some(ocr.clone()), None, frame_path_str.clone());
+    crate::push_results_window_update(app);
+
+    let translated = match translator::get_translator(app).translate(&text, &settings.target_lang).await {
+        Ok(translated) => translated,
+        Err(e) => format!("翻译出错: {}", e),
+    };
+    // 翻译期间用户可能已经停止了监视，此时不再推送
+    if stop_flag.load(Ordering::SeqCst) {
+        return;
+    }
+    commands::cache_result(app, Some(ocr), Some(translated), frame_path_str);
+    crate::push_results_window_update(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// 白底上画一个黑色矩形
+    fn frame_with_block(x: u32, y: u32, width: u32, height: u32) -> RgbaImage {
+        let mut frame = RgbaImage::from_pixel(200, 100, Rgba([255, 255, 255, 255]));
+        for py in y..y + height {
+            for px in x..x + width {
+                frame.put_pixel(px, py, Rgba([0, 0, 0, 255]));
+            }
+        }
+        frame
+    }
+
+    #[test]
+    fn identical_frames_are_unchanged() {
+        let mut detector = ChangeDetector::new(0.02);
+        let frame = frame_with_block(20, 20, 60, 30);
+        assert!(detector.has_changed(&frame), "第一帧总是视为变化");
+        assert!(!detector.has_changed(&frame.clone()));
+    }
+
+    #[test]
+    fn small_noise_stays_below_threshold() {
+        let mut detector = ChangeDetector::new(0.02);
+        let frame = frame_with_block(20, 20, 60, 30);
+        assert!(detector.has_changed(&frame));
+
+        // 整幅画面的轻微亮度抖动，加上一个孤立的噪点
+        let mut noisy = frame.clone();
+        for pixel in noisy.pixels_mut() {
+            pixel.0[0] = pixel.0[0].saturating_sub(10);
+            pixel.0[1] = pixel.0[1].saturating_sub(10);
+        }
+        noisy.put_pixel(150, 80, Rgba([0, 0, 0, 255]));
+        assert!(!detector.has_changed(&noisy));
+    }
+
+    #[test]
+    fn changed_frame_is_detected_and_becomes_baseline() {
+        let mut detector = ChangeDetector::new(0.02);
+        assert!(detector.has_changed(&frame_with_block(20, 20, 60, 30)));
+        let changed = frame_with_block(110, 50, 60, 30);
+        assert!(detector.has_changed(&changed));
+        assert!(!detector.has_changed(&changed));
+    }
+
+    #[test]
+    fn frames_of_different_size_differ() {
+        let a = fingerprint(&RgbaImage::new(200, 100));
+        let b = fingerprint(&RgbaImage::new(100, 100));
+        assert_eq!(frame_difference(&a, &b), 1.0);
+    }
+
+    #[test]
+    fn repeated_text_is_suppressed() {
+        let mut dedup = TextDeduplicator::default();
+        assert!(dedup.is_new("Hello world"));
+        assert!(!dedup.is_new("Hello world"));
+        assert!(!dedup.is_new("  Hello\n world "), "只有空白不同");
+        assert!(!dedup.is_new("   "), "空文本不推送");
+        assert!(dedup.is_new("Goodbye world"));
+    }
+
+    #[test]
+    fn near_duplicate_text_is_suppressed() {
+        let mut dedup = TextDeduplicator::default();
+        assert!(dedup.is_new("The quick brown fox jumps over the lazy dog"));
+        // OCR 把一个字符识别错
+        assert!(!dedup.is_new("The quick brown fox jumps over the 1azy dog"));
+        // 两个字符不同不再是抖动
+        assert!(dedup.is_new("The quick brown fox jumps over the 1azy d0g"));
+        assert!(dedup.is_new("The quick brown cat sleeps under the table"));
+    }
+
+    #[test]
+    fn one_character_change_in_short_text_is_new() {
+        let mut dedup = TextDeduplicator::default();
+        assert!(dedup.is_new("Score: 10"));
+        assert!(dedup.is_new("Score: 11"));
+        assert!(dedup.is_new("剩余时间 59 秒"));
+        assert!(dedup.is_new("剩余时间 58 秒"));
+        assert!(!dedup.is_new("剩余时间 58 秒"));
+    }
+
+    #[test]
+    fn edit_distance_of_cjk_text() {
+        assert_eq!(edit_distance("你好世界", "你好世界"), 0);
+        assert_eq!(edit_distance("你好世界", "你好世间"), 1);
+        assert_eq!(edit_distance("你好", "你好世界"), 2);
+        assert_eq!(edit_distance("", ""), 0);
+    }
+}
@@ -0,0 +1,614 @@
+// --- 文件: src-tauri/src/worker.rs ---
+
+//! 翻译引擎常驻进程
+//!
+//! 以 `--worker` 模式启动翻译引擎后，模型只加载一次，之后通过 stdin/stdout 按行交换 JSON：
+//! - 请求: `{"id": 1, "text": "...", "source": "en", "target": "zh"}`
+//! - 响应: `{"id": 1, "code": 200, "translated_text": "..."}`
+//! - 健康检查: `{"id": 2, "command": "ping"}`，引擎回复 `{"id": 2, "code": 200}`
+//!
+//! 进程在首次翻译时才启动，空闲超时后自动退出，崩溃后在下一次请求时重启。
+//! 不支持 `--worker` 的旧版引擎会被记住，之后直接回退到每次启动新进程的方式。
+
+use serde::Serialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::MutexGuard;
+use tokio::task::JoinHandle;
+
+use crate::encoding::decode_engine_output;
+use crate::settings::{AppSettings, AppState};
+
+// --- 常量定义 ---
+// 启动时加载模型可能较慢
+const STARTUP_TIMEOUT_SECS: u64 = 120;
+// 单次翻译请求的超时，超时视为进程卡死并重启
+const REQUEST_TIMEOUT_SECS: u64 = 120;
+// 设置页面查询状态时的健康检查超时
+const HEALTH_CHECK_TIMEOUT_SECS: u64 = 5;
+// 空闲检查的间隔
+const IDLE_CHECK_INTERVAL_SECS: u64 = 30;
+// 启动失败后等待进程退出、stderr 读取完毕的时间
+const EXIT_WAIT_SECS: u64 = 3;
+// 保留最近的 stderr 行数，用于判断失败原因
+const STDERR_TAIL_LINES: usize = 20;
+
+/// 一个正在运行的引擎常驻进程
+pub struct TranslatorWorker {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    exe_path: PathBuf,
+    // 每次启动递增，空闲监视任务据此判断自己负责的进程是否已被替换
+    generation: u64,
+    next_id: u64,
+    last_used: Instant,
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    stderr_reader: Option<JoinHandle<()>>,
+    output_encoding: String,
+}
+
+/// 常驻进程的管理状态，保存在 `AppState.translator_worker` 中
+#[derive(Default)]
+pub struct WorkerManager {
+    worker: Option<TranslatorWorker>,
+    // 引擎是否支持 worker 模式，None 表示尚未探测
+    supported: Option<bool>,
+    generation: u64,
+    restarts: u32,
+    last_error: Option<String>,
+}
+
+/// 常驻进程状态，由 `check_translator_status` 返回给设置页面
+#[derive(Serialize, Clone, Debug)]
+pub struct WorkerStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    // 仅在进程运行时检查，None 表示未检查
+    pub healthy: Option<bool>,
+    pub supported: Option<bool>,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    // 正在翻译（或启动进程），本次未能查询状态
+    pub busy: bool,
+}
+
+/// 常驻进程翻译失败的原因
+#[derive(Debug)]
+pub enum WorkerError {
+    // 引擎不支持 worker 模式，或设置中已关闭
+    Unsupported(String),
+    // 进程启动失败或崩溃，调用方可以回退到单次进程
+    Crashed(String),
+    // 引擎正常返回了错误（如语言不支持），不应重试
+    Rejected(String),
+}
+
+impl TranslatorWorker {
+    /// 启动常驻进程，并等待引擎完成模型加载（首次 ping 成功）
+    async fn spawn(exe_path: &Path, generation: u64, output_encoding: &str) -> Result<Self, WorkerError> {
+        let working_dir = exe_path.parent().unwrap_or_else(|| Path::new("."));
+        let mut command = Command::new(exe_path);
+        command.arg("--worker")
+            .current_dir(working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(windows)]
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        let mut child = command.spawn()
+            .map_err(|e| WorkerError::Crashed(format!("启动翻译引擎常驻进程失败: {}", e)))?;
+        println!("[WORKER] 常驻进程已启动, PID: {:?}", child.id());
+
+        let stdin = child.stdin.take().ok_or_else(|| WorkerError::Crashed("无法获取进程 stdin".to_string()))?;
+        let stdout = child.stdout.take().ok_or_else(|| WorkerError::Crashed("无法获取进程 stdout".to_string()))?;
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr_reader = child.stderr.take().map(|stderr| {
+            // 持续读取 stderr，避免缓冲区写满阻塞引擎，同时保留最近几行用于诊断
+            let tail = stderr_tail.clone();
+            let encoding = output_encoding.to_string();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stderr);
+                let mut line = Vec::new();
+                while reader.read_until(b'\n', &mut line).await.unwrap_or(0) > 0 {
+                    let text = decode_engine_output(&line, &encoding).trim_end().to_string();
+                    println!("[WORKER] stderr: {}", text);
+                    let mut tail = tail.lock().unwrap();
+                    if tail.len() >= STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(text);
+                    line.clear();
+                }
+            })
+        });
+
+        let mut worker = Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            exe_path: exe_path.to_path_buf(),
+            generation,
+            next_id: 1,
+            last_used: Instant::now(),
+            stderr_tail,
+            stderr_reader,
+            output_encoding: output_encoding.to_string(),
+        };
+
+        if let Err(e) = worker.ping(Duration::from_secs(STARTUP_TIMEOUT_SECS)).await {
+            // 旧版引擎关闭 stdout 后可能还没退出、stderr 也还没读完，先等它们结束再判断。
+            let exited = worker.wait_for_exit(Duration::from_secs(EXIT_WAIT_SECS)).await;
+            let stderr = worker.stderr_text();
+            worker.kill();
+            return Err(startup_error(exited, &stderr, &e));
+        }
+        println!("[WORKER] 常驻进程已就绪。");
+        Ok(worker)
+    }
+
+    /// 发送一条请求并等待对应 id 的响应。引擎输出的非 JSON 行（如加载日志）会被忽略。
+    async fn request(&mut self, mut message: serde_json::Value, timeout: Duration) -> Result<serde_json::Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        message["id"] = json!(id);
+
+        let mut line = serde_json::to_vec(&message).map_err(|e| format!("序列化请求失败: {}", e))?;
+        line.push(b'\n');
+        self.stdin.write_all(&line).await.map_err(|e| format!("写入常驻进程失败: {}", e))?;
+        self.stdin.flush().await.map_err(|e| format!("写入常驻进程失败: {}", e))?;
+
+        tokio::time::timeout(timeout, read_response(&mut self.stdout, id, &self.output_encoding)).await
+            .map_err(|_| format!("常驻进程在 {} 秒内未响应", timeout.as_secs()))?
+    }
+
+    async fn ping(&mut self, timeout: Duration) -> Result<(), String> {
+        let response = self.request(json!({ "command": "ping" }), timeout).await?;
+        match response["code"].as_i64() {
+            Some(200) => Ok(()),
+            _ => Err(format!("健康检查返回异常: {}", response)),
+        }
+    }
+
+    /// 等待进程退出并读完 stderr，超时返回 false
+    async fn wait_for_exit(&mut self, timeout: Duration) -> bool {
+        let exited = matches!(tokio::time::timeout(timeout, self.child.wait()).await, Ok(Ok(_)));
+        if exited {
+            if let Some(reader) = self.stderr_reader.take() {
+                let _ = tokio::time::timeout(timeout, reader).await;
+            }
+        }
+        exited
+    }
+
+    fn stderr_text(&self) -> String {
+        self.stderr_tail.lock().unwrap().iter().cloned().collect::<Vec<_>>().join("\n")
+    }
+
+    fn kill(mut self) {
+        println!("[WORKER] 结束常驻进程, PID: {:?}", self.child.id());
+        let _ = self.child.start_kill();
+    }
+
+    /// 结束进程并等待其退出，确保引擎文件不再被占用
+    async fn kill_and_wait(mut self) {
+        println!("[WORKER] 结束常驻进程并等待退出, PID: {:?}", self.child.id());
+        let _ = self.child.start_kill();
+        let _ = tokio::time::timeout(Duration::from_secs(EXIT_WAIT_SECS), self.child.wait()).await;
+    }
+}
+
+/// 从引擎输出中读取 `id` 对应的响应，跳过非 JSON 行（如加载日志）和其它请求的响应
+async fn read_response<R: AsyncBufRead + Unpin>(reader: &mut R, id: u64, encoding: &str) -> Result<serde_json::Value, String> {
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer).await
+            .map_err(|e| format!("读取常驻进程输出失败: {}", e))?;
+        if read == 0 {
+            return Err("翻译引擎常驻进程已退出".to_string());
+        }
+        let text = decode_engine_output(&buffer, encoding);
+        match serde_json::from_str::<serde_json::Value>(text.trim()) {
+            Ok(value) if value["id"].as_u64() == Some(id) => return Ok(value),
+            _ => println!("[WORKER] 忽略输出: {}", text.trim_end()),
+        }
+    }
+}
+
+/// 启动失败的原因：进程已退出且报告参数错误，说明是不支持 worker 模式的旧版引擎
+fn startup_error(exited: bool, stderr: &str, error: &str) -> WorkerError {
+    if exited && crate::translator::is_unsupported_argument_error(stderr) {
+        WorkerError::Unsupported(stderr.lines().last().unwrap_or_default().to_string())
+    } else {
+        WorkerError::Crashed(format!("常驻进程启动失败: {}", error))
+    }
+}
+
+/// 把翻译请求的响应转换为译文或引擎报告的错误
+fn translation_result(response: &serde_json::Value) -> Result<String, WorkerError> {
+    match response["code"].as_i64() {
+        Some(200) => response["translated_text"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| WorkerError::Rejected("翻译成功但未返回文本".to_string())),
+        _ => Err(WorkerError::Rejected(
+            response["error_message"].as_str().unwrap_or("翻译器返回未知错误").to_string(),
+        )),
+    }
+}
+
+impl WorkerManager {
+    /// 发送一次翻译请求。进程不存在时启动，请求失败时结束进程并重启一次后重试。
+    /// 每启动一个新进程调用一次 `on_spawn(generation)`，供调用方开始空闲监视。
+    async fn translate(
+        &mut self,
+        exe_path: &Path,
+        request: &serde_json::Value,
+        output_encoding: &str,
+        mut on_spawn: impl FnMut(u64),
+    ) -> Result<String, WorkerError> {
+        let mut crashed = false;
+        for _ in 0..2 {
+            if self.worker.is_none() {
+                self.generation += 1;
+                let generation = self.generation;
+                match TranslatorWorker::spawn(exe_path, generation, output_encoding).await {
+                    Ok(worker) => {
+                        self.worker = Some(worker);
+                        self.supported = Some(true);
+                        if crashed {
+                            self.restarts += 1;
+                        }
+                        on_spawn(generation);
+                    }
+                    Err(WorkerError::Unsupported(reason)) => {
+                        println!("[WORKER] 引擎不支持常驻进程模式: {}", reason);
+                        self.supported = Some(false);
+                        return Err(WorkerError::Unsupported(reason));
+                    }
+                    Err(e) => {
+                        if let WorkerError::Crashed(message) | WorkerError::Rejected(message) = &e {
+                            self.last_error = Some(message.clone());
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+
+            let worker = self.worker.as_mut().unwrap();
+            match worker.request(request.clone(), Duration::from_secs(REQUEST_TIMEOUT_SECS)).await {
+                Ok(response) => {
+                    worker.last_used = Instant::now();
+                    return translation_result(&response);
+                }
+                Err(e) => {
+                    eprintln!("[WORKER] 常驻进程异常: {}，准备重启。", e);
+                    self.last_error = Some(e);
+                    if let Some(worker) = self.worker.take() {
+                        worker.kill();
+                    }
+                    crashed = true;
+                }
+            }
+        }
+        Err(WorkerError::Crashed(self.last_error.clone().unwrap_or_default()))
+    }
+
+    /// 第 `generation` 次启动的进程空闲超过 `idle_timeout` 时结束它。
+    /// 返回是否需要继续监视：进程已结束或已被新进程替换时返回 false。
+    fn stop_if_idle(&mut self, generation: u64, idle_timeout: Duration) -> bool {
+        match self.worker.as_ref() {
+            Some(worker) if worker.generation == generation => {
+                if worker.last_used.elapsed() < idle_timeout {
+                    return true;
+                }
+                println!("[WORKER] 常驻进程空闲超过 {} 秒，自动结束。", idle_timeout.as_secs());
+                if let Some(worker) = self.worker.take() {
+                    worker.kill();
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+// --- 对外接口 ---
+
+/// 通过常驻进程翻译一段文本。进程不存在时自动启动，崩溃时重启一次后重试。
+pub async fn translate(
+    app: &AppHandle,
+    exe_path: &Path,
+    text: &str,
+    source: &str,
+    target: &str,
+    settings: &AppSettings,
+) -> Result<String, WorkerError> {
+    let state: State<AppState> = app.state();
+    let mut manager = state.translator_worker.lock().await;
+    if !settings.translator_worker_enabled {
+        // 关闭设置后，结束之前启动的进程
+        if let Some(worker) = manager.worker.take() {
+            worker.kill();
+        }
+        return Err(WorkerError::Unsupported("设置中已关闭常驻进程".to_string()));
+    }
+    if manager.supported == Some(false) {
+        return Err(WorkerError::Unsupported("引擎不支持常驻进程模式".to_string()));
+    }
+    // 引擎路径变化（例如修改了自定义路径）时结束旧进程
+    if manager.worker.as_ref().is_some_and(|w| w.exe_path != exe_path) {
+        println!("[WORKER] 引擎路径已变化，重启常驻进程。");
+        if let Some(old) = manager.worker.take() {
+            old.kill();
+        }
+    }
+
+    let request = json!({ "text": text, "source": source, "target": target });
+    let idle_timeout_mins = settings.translator_idle_timeout_mins;
+    manager.translate(exe_path, &request, &settings.engine_output_encoding, |generation| {
+        spawn_idle_monitor(app.clone(), generation, idle_timeout_mins)
+    }).await
+}
+
+/// 查询常驻进程状态。`check_health` 为 true 且进程在运行时，发送一次 ping 检查是否仍能响应。
+///
+/// 翻译进行中时不等待，直接返回 `busy` 状态（此时进程可能正在启动，不报告是否在运行），
+/// 避免设置页面卡在长文本翻译上。
+pub async fn status(app: &AppHandle, check_health: bool) -> WorkerStatus {
+    let state: State<AppState> = app.state();
+    let mut manager = match state.translator_worker.try_lock() {
+        Ok(manager) => manager,
+        Err(_) => {
+            return WorkerStatus {
+                running: false,
+                pid: None,
+                healthy: None,
+                supported: None,
+                restarts: 0,
+                last_error: None,
+                busy: true,
+            };
+        }
+    };
+
+    let mut healthy = None;
+    if check_health {
+        if let Some(worker) = manager.worker.as_mut() {
+            match worker.ping(Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS)).await {
+                Ok(()) => healthy = Some(true),
+                Err(e) => {
+                    eprintln!("[WORKER] 健康检查失败: {}", e);
+                    healthy = Some(false);
+                    manager.last_error = Some(e);
+                    // 无响应的进程直接结束，下次翻译时会重新启动
+                    if let Some(worker) = manager.worker.take() {
+                        worker.kill();
+                    }
+                }
+            }
+        }
+    }
+
+    WorkerStatus {
+        running: manager.worker.is_some(),
+        pid: manager.worker.as_ref().and_then(|w| w.child.id()),
+        healthy,
+        supported: manager.supported,
+        restarts: manager.restarts,
+        last_error: manager.last_error.clone(),
+        busy: false,
+    }
+}
+
+/// 安装、回滚或卸载翻译引擎前调用：结束常驻进程并等待它退出。
+///
+/// 返回的锁释放之前不会启动新的常驻进程，调用方应持有它直到引擎文件替换完成。
+/// 正在翻译时不等待，直接返回错误。
+pub async fn stop_for_update(app: &AppHandle) -> Result<MutexGuard<'_, WorkerManager>, String> {
+    let state: State<AppState> = app.state();
+    let mut manager = state.inner().translator_worker.try_lock()
+        .map_err(|_| "翻译正在进行中，请等待翻译完成后再试。".to_string())?;
+    if let Some(worker) = manager.worker.take() {
+        worker.kill_and_wait().await;
+    }
+    // 新版本是否支持常驻进程模式需要重新探测
+    manager.supported = None;
+    Ok(manager)
+}
+
+/// 应用退出时结束常驻进程
+pub fn shutdown(app: &AppHandle) {
+    let state: State<AppState> = app.state();
+    if let Ok(mut manager) = state.translator_worker.try_lock() {
+        if let Some(worker) = manager.worker.take() {
+            worker.kill();
+        }
+    }
+}
+
+/// 空闲监视：进程超过 `idle_timeout_mins` 分钟未使用时结束它。为 0 时不自动结束。
+fn spawn_idle_monitor(app: AppHandle, generation: u64, idle_timeout_mins: u64) {
+    if idle_timeout_mins == 0 {
+        return;
+    }
+    let idle_timeout = Duration::from_secs(idle_timeout_mins * 60);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(IDLE_CHECK_INTERVAL_SECS)).await;
+            let state: State<AppState> = app.state();
+            let mut manager = state.translator_worker.lock().await;
+            if !manager.stop_if_idle(generation, idle_timeout) {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按 JSON-lines 协议应答的脚本引擎：先输出一行日志，每个响应前再输出一行日志和一条其它 id 的响应
+    #[cfg(unix)]
+    const ECHO_WORKER: &str = r#"
+echo "loading model..."
+while IFS= read -r line; do
+    id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+    case "$line" in
+        *'"command":"ping"'*) printf 'ready\n{"id":%s,"code":200}\n' "$id" ;;
+        *'"target":"xx"'*) printf '{"id":%s,"code":400,"error_message":"unsupported language"}\n' "$id" ;;
+        *) printf 'translating\n{"id":0,"code":200,"translated_text":"stale"}\n{"id":%s,"code":200,"translated_text":"译文"}\n' "$id" ;;
+    esac
+done
+"#;
+
+    /// 写入可执行的脚本引擎，返回其路径
+    #[cfg(unix)]
+    fn script_engine(name: &str, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("worker-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("engine.sh");
+        std::fs::write(&path, format!("#!/bin/sh\n{}", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn request(target: &str) -> serde_json::Value {
+        json!({ "text": "hello", "source": "en", "target": target })
+    }
+
+    #[tokio::test]
+    async fn read_response_skips_logs_and_other_ids() {
+        let output = "loading model...\n{\"id\":1,\"code\":200}\nnot json {\n{\"id\":2,\"code\":200,\"translated_text\":\"好\"}\n";
+        let mut reader = output.as_bytes();
+        let response = read_response(&mut reader, 2, "gbk").await.unwrap();
+        assert_eq!(response["translated_text"], "好");
+    }
+
+    #[tokio::test]
+    async fn read_response_reports_a_closed_stream() {
+        let mut reader = "{\"id\":1,\"code\":200}\n".as_bytes();
+        let error = read_response(&mut reader, 2, "gbk").await.unwrap_err();
+        assert!(error.contains("已退出"), "{}", error);
+    }
+
+    #[test]
+    fn classifies_startup_failures() {
+        let stderr = "usage: engine [-h]\nengine: error: unrecognized arguments: --worker";
+        match startup_error(true, stderr, "常驻进程已退出") {
+            WorkerError::Unsupported(reason) => assert_eq!(reason, "engine: error: unrecognized arguments: --worker"),
+            other => panic!("应为 Unsupported: {:?}", other),
+        }
+        // 进程仍在运行，或退出原因不是参数错误，都按崩溃处理
+        assert!(matches!(startup_error(false, stderr, "超时"), WorkerError::Crashed(_)));
+        assert!(matches!(startup_error(true, "CUDA out of memory", "已退出"), WorkerError::Crashed(_)));
+    }
+
+    #[test]
+    fn classifies_translation_responses() {
+        assert_eq!(translation_result(&json!({"id": 1, "code": 200, "translated_text": "好"})).unwrap(), "好");
+        assert!(matches!(translation_result(&json!({"id": 1, "code": 200})), Err(WorkerError::Rejected(_))));
+        match translation_result(&json!({"id": 1, "code": 500, "error_message": "boom"})) {
+            Err(WorkerError::Rejected(message)) => assert_eq!(message, "boom"),
+            other => panic!("应为 Rejected: {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn translates_through_a_persistent_process() {
+        let exe = script_engine("echo", ECHO_WORKER);
+        let mut manager = WorkerManager::default();
+        let mut spawned = Vec::new();
+
+        assert_eq!(manager.translate(&exe, &request("zh"), "gbk", |g| spawned.push(g)).await.unwrap(), "译文");
+        let pid = manager.worker.as_ref().unwrap().child.id();
+        assert_eq!(manager.translate(&exe, &request("zh"), "gbk", |g| spawned.push(g)).await.unwrap(), "译文");
+        assert_eq!(manager.worker.as_ref().unwrap().child.id(), pid, "复用同一个进程");
+        assert_eq!(spawned, vec![1]);
+        assert_eq!(manager.supported, Some(true));
+
+        // 引擎报告的错误不会重启进程
+        let error = manager.translate(&exe, &request("xx"), "gbk", |g| spawned.push(g)).await.unwrap_err();
+        assert!(matches!(error, WorkerError::Rejected(ref m) if m == "unsupported language"), "{:?}", error);
+        assert_eq!(manager.worker.as_ref().unwrap().child.id(), pid);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn restarts_a_crashed_process() {
+        // 第一个进程在收到翻译请求后退出，之后启动的进程正常应答
+        let body = format!("if [ ! -f crashed ]; then\n{}\nfi\n{}", r#"
+    IFS= read -r line
+    id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+    printf '{"id":%s,"code":200}\n' "$id"
+    IFS= read -r line
+    touch crashed
+    exit 1"#, ECHO_WORKER);
+        let exe = script_engine("crash", &body);
+        let mut manager = WorkerManager::default();
+        let mut spawned = Vec::new();
+
+        assert_eq!(manager.translate(&exe, &request("zh"), "gbk", |g| spawned.push(g)).await.unwrap(), "译文");
+        assert_eq!(spawned, vec![1, 2]);
+        assert_eq!(manager.restarts, 1);
+        assert!(manager.last_error.as_deref().unwrap().contains("已退出"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn gives_up_after_one_restart() {
+        let body = r#"
+IFS= read -r line
+id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+printf '{"id":%s,"code":200}\n' "$id"
+exit 1"#;
+        let exe = script_engine("always-crash", body);
+        let mut manager = WorkerManager::default();
+        let error = manager.translate(&exe, &request("zh"), "gbk", |_| {}).await.unwrap_err();
+        assert!(matches!(error, WorkerError::Crashed(_)), "{:?}", error);
+        assert!(manager.worker.is_none());
+        assert_eq!(manager.generation, 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn detects_an_engine_without_worker_mode() {
+        let exe = script_engine("old", "echo \"engine: error: unrecognized arguments: --worker\" >&2\nexit 2");
+        let mut manager = WorkerManager::default();
+        let error = manager.translate(&exe, &request("zh"), "gbk", |_| {}).await.unwrap_err();
+        assert!(matches!(error, WorkerError::Unsupported(ref m) if m.contains("unrecognized arguments")), "{:?}", error);
+        assert_eq!(manager.supported, Some(false));
+        assert!(manager.worker.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stops_an_idle_process() {
+        let exe = script_engine("idle", ECHO_WORKER);
+        let mut manager = WorkerManager::default();
+        manager.translate(&exe, &request("zh"), "gbk", |_| {}).await.unwrap();
+        let generation = manager.generation;
+
+        assert!(manager.stop_if_idle(generation, Duration::from_secs(60)), "未超时时继续监视");
+        assert!(manager.worker.is_some());
+        assert!(!manager.stop_if_idle(generation + 1, Duration::ZERO), "进程已被替换时停止监视");
+        assert!(manager.worker.is_some(), "不结束其它监视任务负责的进程");
+
+        assert!(!manager.stop_if_idle(generation, Duration::ZERO));
+        assert!(manager.worker.is_none());
+        assert!(!manager.stop_if_idle(generation, Duration::ZERO));
+    }
+}
@@ -3,7 +3,8 @@
 // 导入 Tauri 核心 API，用于与 Rust 后端进行交互
 const { invoke } = window.__TAURI__.tauri;
 const { listen } = window.__TAURI__.event;
-const { message, confirm } = window.__TAURI__.dialog;
+const { message, confirm, open } = window.__TAURI__.dialog;
+const { writeText } = window.__TAURI__.clipboard;
 
 // --- DOM 元素获取 ---
 // 将页面上所有需要操作的 HTML 元素预先获取并存入变量，方便后续使用
@@ -11,18 +12,55 @@ const { message, confirm } = window.__TAURI__.dialog;
 // 常规设置元素
 const shortcutInput = document.getElementById('shortcut-input');
 const viewShortcutInput = document.getElementById('view-shortcut-input');
+const delayedShortcutInput = document.getElementById('delayed-shortcut-input');
+const captureDelayInput = document.getElementById('capture-delay-input');
+const longCaptureShortcutInput = document.getElementById('long-capture-shortcut-input');
+const watchShortcutInput = document.getElementById('watch-shortcut-input');
+const watchIntervalInput = document.getElementById('watch-interval-input');
 const targetLangSelect = document.getElementById('target-lang-select');
 const targetLangContainer = document.getElementById('target-lang-container');
 const lineBreakCheckbox = document.getElementById('line-break-checkbox');
+// OCR 预处理开关，data-preprocess-key 对应 `ocr_preprocess` 中的字段名
+const preprocessCheckboxes = document.querySelectorAll('input[data-preprocess-key]');
 const ocrSettingsBlock = document.getElementById('ocr-settings-block');
 const radioInputs = document.getElementsByName('primary-action');
 
+// 保存区域相关元素
+const savedRegionsList = document.getElementById('saved-regions-list');
+const addRegionBtn = document.getElementById('add-region-btn');
+
+// 识别引擎选择与 Tesseract 设置
+const ocrEngineSelect = document.getElementById('ocr-engine-select');
+const tesseractSettings = document.getElementById('tesseract-settings');
+const tesseractLangsInput = document.getElementById('tesseract-langs-input');
+const tesseractLangsHint = document.getElementById('tesseract-langs-hint');
+const tesseractPathInput = document.getElementById('tesseract-path-input');
+const ocrDownloadHint = document.getElementById('ocr-download-hint');
+// 自定义引擎路径
+const ocrCustomPathContainer = document.getElementById('ocr-custom-path-container');
+const ocrCustomPathInput = document.getElementById('ocr-custom-path-input');
+const translatorCustomPathInput = document.getElementById('translator-custom-path-input');
+const engineEncodingSelect = document.getElementById('engine-encoding-select');
+const engineManifestInput = document.getElementById('engine-manifest-input');
+// 网络设置
+const networkProxyInput = document.getElementById('network-proxy-input');
+const networkCaCertInput = document.getElementById('network-ca-cert-input');
+const downloadMirrorsInput = document.getElementById('download-mirrors-input');
+// 翻译引擎常驻进程
+const translatorWorkerCheckbox = document.getElementById('translator-worker-checkbox');
+const translatorIdleTimeoutInput = document.getElementById('translator-idle-timeout-input');
+const translatorWorkerStatus = document.getElementById('translator-worker-status');
+
 // OCR 引擎管理相关元素
 const ocrEngineStatusBadge = document.getElementById('ocr-engine-status');
 const downloadOcrBtn = document.getElementById('download-ocr-btn');
 const ocrProgressContainer = document.getElementById('ocr-progress-container');
 const ocrProgressBar = document.getElementById('ocr-download-progress');
 const ocrProgressLabel = document.getElementById('ocr-progress-label');
+const cancelOcrDownloadBtn = document.getElementById('cancel-ocr-download-btn');
+const installOcrFileBtn = document.getElementById('install-ocr-file-btn');
+const uninstallOcrBtn = document.getElementById('uninstall-ocr-btn');
+const ocrEngineInfo = document.getElementById('ocr-engine-info');
 
 // 翻译引擎管理相关元素
 const engineStatusBadge = document.getElementById('engine-status');
@@ -30,20 +68,34 @@ const downloadBtn = document.getElementById('download-btn');
 const progressContainer = document.getElementById('progress-container');
 const progressBar = document.getElementById('download-progress');
 const progressLabel = document.getElementById('progress-label');
+const cancelDownloadBtn = document.getElementById('cancel-download-btn');
+const installTranslatorFileBtn = document.getElementById('install-translator-file-btn');
+const uninstallTranslatorBtn = document.getElementById('uninstall-translator-btn');
+const translatorEngineInfo = document.getElementById('translator-engine-info');
+
+// 引擎自检
+const selfTestBtn = document.getElementById('self-test-btn');
+const selfTestResults = document.getElementById('self-test-results');
+const copySelfTestBtn = document.getElementById('copy-self-test-btn');
+
+// 缓存清理
+const clearCacheBtn = document.getElementById('clear-cache-btn');
+const clearCacheResult = document.getElementById('clear-cache-result');
 
 // --- 全局状态与默认值 ---
 // 用于管理前端 UI 状态和缓存数据
 
 // 快捷键录制状态
-let isRecording = { main: false, view: false };
+let isRecording = { main: false, view: false, delayed: false, long: false, watch: false };
 // 当前从后端加载的设置，用于比对和恢复
 let currentSettings = {};
 // 引擎安装状态标志
 let isOcrInstalled = false;
 let isTranslatorInstalled = false;
-// 引擎下载状态标志，防止重复点击
-let isOcrDownloading = false;
-let isTranslatorDownloading = false;
+// 按引擎清单检查到的更新信息，键为引擎 id (ocr / translator)
+let engineUpdates = {};
+// 最近一次引擎自检的完整报告
+let lastSelfTestReport = null;
 
 // --- 函数定义 ---
 
@@ -70,25 +122,57 @@ async function checkOcrStatus() {
  * 根据 OCR 引擎的安装状态，更新相关的 UI 元素（状态徽章、按钮文本）。
  */
 function updateOcrUI() {
-    if (isOcrInstalled) {
-        ocrEngineStatusBadge.textContent = "已安装";
+    // Tesseract 由系统安装，不提供下载
+    const isTesseract = ocrEngineSelect.value === 'tesseract';
+    downloadOcrBtn.style.display = isTesseract ? 'none' : '';
+    installOcrFileBtn.style.display = isTesseract ? 'none' : '';
+    ocrDownloadHint.style.display = isTesseract ? 'none' : '';
+    tesseractSettings.style.display = isTesseract ? 'block' : 'none';
+    ocrCustomPathContainer.style.display = isTesseract ? 'none' : '';
+
+    const update = engineUpdates.ocr;
+    if (isOcrInstalled && !isTesseract && update?.update_available) {
+        ocrEngineStatusBadge.textContent = `可更新 (v${update.latest_version})`;
+        ocrEngineStatusBadge.className = "status-badge outdated";
+        downloadOcrBtn.textContent = `更新到 v${update.latest_version}`;
+    } else if (isOcrInstalled) {
+        ocrEngineStatusBadge.textContent = isTesseract ? "可用" : "已安装";
         ocrEngineStatusBadge.className = "status-badge installed";
         downloadOcrBtn.textContent = "重新下载 / 更新";
     } else {
-        ocrEngineStatusBadge.textContent = "未安装";
+        ocrEngineStatusBadge.textContent = isTesseract ? "未找到 Tesseract" : "未安装";
         ocrEngineStatusBadge.className = "status-badge missing";
         downloadOcrBtn.textContent = "立即下载安装";
     }
 }
 
+/**
+ * 查询本机 Tesseract 已安装的语言包，并显示在语言输入框下方。
+ * @async
+ */
+async function loadTesseractLanguages() {
+    if (ocrEngineSelect.value !== 'tesseract') return;
+    try {
+        const langs = await invoke('list_tesseract_languages');
+        tesseractLangsHint.textContent = langs.length
+            ? `已安装: ${langs.join(', ')}`
+            : '未找到任何语言包';
+    } catch (e) {
+        console.error("[前端] 查询 Tesseract 语言包失败:", e);
+        tesseractLangsHint.textContent = '无法查询语言包，请检查 Tesseract 是否已安装';
+    }
+}
+
 /**
  * 检查本地翻译引擎的安装状态。
  * @async
  */
 async function checkTranslatorStatus() {
     try {
-        isTranslatorInstalled = await invoke('check_translator_status');
+        const status = await invoke('check_translator_status');
+        isTranslatorInstalled = status.installed;
         updateTranslatorUI();
+        updateWorkerStatus(status.worker);
     } catch (e) {
         console.error("检查翻译引擎状态失败:", e);
         engineStatusBadge.textContent = "检查失败";
@@ -100,7 +184,12 @@ async function checkTranslatorStatus() {
  * 根据翻译引擎的安装状态，更新相关的 UI 元素。
  */
 function updateTranslatorUI() {
-    if (isTranslatorInstalled) {
+    const update = engineUpdates.translator;
+    if (isTranslatorInstalled && update?.update_available) {
+        engineStatusBadge.textContent = `可更新 (v${update.latest_version})`;
+        engineStatusBadge.className = "status-badge outdated";
+        downloadBtn.textContent = `更新到 v${update.latest_version}`;
+    } else if (isTranslatorInstalled) {
         engineStatusBadge.textContent = "已安装";
         engineStatusBadge.className = "status-badge installed";
         downloadBtn.textContent = "重新下载 / 更新";
@@ -111,6 +200,88 @@ function updateTranslatorUI() {
     }
 }
 
+/**
+ * 按引擎清单检查已安装引擎的新版本，并刷新引擎状态显示。
+ * @async
+ */
+async function checkEngineUpdates() {
+    try {
+        const updates = await invoke('check_engine_updates');
+        engineUpdates = Object.fromEntries(updates.map(info => [info.id, info]));
+    } catch (e) {
+        // 清单不可用（如网络错误）不影响引擎的正常使用，仅记录日志
+        console.error("检查引擎更新失败:", e);
+        engineUpdates = {};
+    }
+    updateOcrUI();
+    updateTranslatorUI();
+}
+
+/**
+ * 将字节数格式化为便于阅读的大小。
+ * @param {number} bytes - 字节数。
+ * @returns {string}
+ */
+function formatSize(bytes) {
+    if (bytes >= 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024 / 1024).toFixed(2)}GB`;
+    if (bytes >= 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)}MB`;
+    return `${Math.ceil(bytes / 1024)}KB`;
+}
+
+/**
+ * 查询引擎的安装信息（版本、占用空间、最近使用时间），显示在引擎状态下方。
+ * @async
+ * @param {string} engineId - 引擎 id ('ocr' / 'translator')。
+ */
+async function refreshEngineInfo(engineId) {
+    const ui = engineDownloads[engineId];
+    try {
+        const info = await invoke('get_engine_info', { engineId });
+        const parts = [];
+        if (info.version) parts.push(`v${info.version}`);
+        if (info.size_on_disk > 0) parts.push(`占用 ${formatSize(info.size_on_disk)}`);
+        if (info.previous_version) parts.push(`保留 v${info.previous_version} 用于回滚`);
+        if (info.installed) {
+            parts.push(info.last_used
+                ? `上次使用 ${new Date(info.last_used * 1000).toLocaleString()}`
+                : '尚未使用');
+        }
+        ui.infoLabel.textContent = parts.join(' · ');
+        ui.infoLabel.title = info.path || '';
+        // 只有应用数据目录中有引擎文件时才可以卸载，自定义路径的引擎由用户自行管理
+        ui.uninstallButton.style.display = info.size_on_disk > 0 ? '' : 'none';
+    } catch (e) {
+        console.error(`[前端] 查询${ui.name}信息失败:`, e);
+        ui.infoLabel.textContent = '';
+    }
+}
+
+/**
+ * 显示翻译引擎常驻进程的运行状态。
+ * @param {object} worker - 后端返回的 WorkerStatus。
+ */
+function updateWorkerStatus(worker) {
+    let text;
+    if (worker.busy) {
+        translatorWorkerStatus.textContent = '正在翻译中，稍后刷新查看状态';
+        translatorWorkerStatus.title = '';
+        return;
+    }
+    if (worker.supported === false) {
+        text = '引擎不支持常驻模式，每次翻译单独启动';
+    } else if (!worker.running) {
+        text = '未运行 (首次翻译时启动)';
+    } else if (worker.healthy === false) {
+        text = '无响应，已结束，下次翻译时重启';
+    } else {
+        text = `运行中 (PID ${worker.pid ?? '-'})`;
+    }
+    if (worker.restarts > 0) {
+        text += `，已重启 ${worker.restarts} 次`;
+    }
+    translatorWorkerStatus.textContent = text;
+    translatorWorkerStatus.title = worker.last_error || '';
+}
 
 /**
  * 根据用户选择的“首要动作”，动态显示或隐藏相关的设置项。
@@ -139,6 +310,138 @@ function updateUIBasedOnAction(actionValue) {
     }
 }
 
+// 保存区域可选的动作与目标语言（空字符串表示沿用全局设置）
+const REGION_ACTION_OPTIONS = [
+    ['', '沿用全局动作'],
+    ['ocr', '识别文字'],
+    ['ocr_translate', '识别并翻译'],
+    ['copy', '复制图片'],
+    ['save', '保存图片'],
+    ['preview', '预览'],
+];
+const REGION_LANG_OPTIONS = [
+    ['', '沿用全局语言'],
+    ['zh', '简体中文'],
+    ['en', 'English'],
+    ['ja', '日本語'],
+];
+
+/**
+ * 创建一个下拉框并选中指定的值。
+ * @param {Array<[string, string]>} options - [值, 显示文本] 列表。
+ * @param {string|null} value - 当前值，null 视为空字符串。
+ * @returns {HTMLSelectElement}
+ */
+function createSelect(options, value) {
+    const select = document.createElement('select');
+    for (const [optionValue, text] of options) {
+        const option = document.createElement('option');
+        option.value = optionValue;
+        option.textContent = text;
+        select.appendChild(option);
+    }
+    select.value = value || '';
+    return select;
+}
+
+/**
+ * 根据 `currentSettings.saved_regions` 重新渲染保存区域列表。
+ * 每一行的修改都会直接写回 `currentSettings` 并保存。
+ */
+function renderSavedRegions() {
+    const regions = currentSettings.saved_regions || [];
+    savedRegionsList.innerHTML = '';
+
+    if (regions.length === 0) {
+        const empty = document.createElement('div');
+        empty.className = 'region-empty';
+        empty.textContent = '尚未保存任何区域。';
+        savedRegionsList.appendChild(empty);
+        return;
+    }
+
+    regions.forEach((region, index) => {
+        const row = document.createElement('div');
+        row.className = 'region-row';
+
+        // 名称
+        const nameInput = document.createElement('input');
+        nameInput.type = 'text';
+        nameInput.value = region.name;
+        nameInput.title = `${region.width} x ${region.height} @ (${region.x}, ${region.y})`;
+        nameInput.addEventListener('change', () => {
+            const name = nameInput.value.trim();
+            if (!name || regions.some((r, i) => i !== index && r.name === name)) {
+                nameInput.value = region.name; // 名称不能为空或重复
+                return;
+            }
+            region.name = name;
+            saveSettings();
+        });
+
+        // 动作与语言覆盖
+        const actionSelect = createSelect(REGION_ACTION_OPTIONS, region.action);
+        actionSelect.addEventListener('change', () => {
+            region.action = actionSelect.value || null;
+            saveSettings();
+        });
+        const langSelect = createSelect(REGION_LANG_OPTIONS, region.target_lang);
+        langSelect.addEventListener('change', () => {
+            region.target_lang = langSelect.value || null;
+            saveSettings();
+        });
+
+        // 独立快捷键
+        const shortcutField = document.createElement('input');
+        shortcutField.type = 'text';
+        shortcutField.readOnly = true;
+        shortcutField.placeholder = '无快捷键';
+        shortcutField.value = region.shortcut || '';
+        shortcutField.addEventListener('focus', () => { shortcutField.value = '请按下快捷键...'; });
+        shortcutField.addEventListener('blur', () => { shortcutField.value = region.shortcut || ''; });
+        shortcutField.addEventListener('keydown', (e) => {
+            e.preventDefault();
+            if (e.key === 'Backspace' || e.key === 'Delete') {
+                region.shortcut = null;
+            } else {
+                const formatted = formatShortcut(e);
+                if (!formatted || !(formatted.includes('+') || formatted.startsWith('F'))) return;
+                region.shortcut = formatted;
+            }
+            shortcutField.blur();
+            saveSettings();
+        });
+
+        // 操作按钮
+        const buttons = document.createElement('div');
+        buttons.className = 'region-buttons';
+        const captureBtn = document.createElement('button');
+        captureBtn.className = 'download-button';
+        captureBtn.textContent = '截取';
+        captureBtn.addEventListener('click', async () => {
+            try {
+                await invoke('capture_saved_region', { name: region.name });
+            } catch (e) {
+                await message(`截取失败: ${e}`, { title: '错误', type: 'error' });
+            }
+        });
+        const deleteBtn = document.createElement('button');
+        deleteBtn.className = 'download-button';
+        deleteBtn.textContent = '删除';
+        deleteBtn.addEventListener('click', async () => {
+            const confirmed = await confirm(`确定要删除区域「${region.name}」吗？`, { title: '删除区域', type: 'warning' });
+            if (!confirmed) return;
+            regions.splice(index, 1);
+            renderSavedRegions();
+            saveSettings();
+        });
+        buttons.append(captureBtn, deleteBtn);
+
+        row.append(nameInput, actionSelect, langSelect, shortcutField, buttons);
+        savedRegionsList.appendChild(row);
+    });
+}
+
 /**
  * 从后端加载应用设置，并更新整个 UI 界面以反映这些设置。
  * @async
@@ -153,8 +456,30 @@ async function loadSettings() {
         // 更新各个表单控件的值
         shortcutInput.value = settings.shortcut;
         viewShortcutInput.value = settings.view_image_shortcut;
+        delayedShortcutInput.value = settings.delayed_capture_shortcut;
+        captureDelayInput.value = settings.capture_delay_secs;
+        longCaptureShortcutInput.value = settings.long_capture_shortcut;
+        watchShortcutInput.value = settings.watch_shortcut;
+        watchIntervalInput.value = settings.watch_interval_ms;
         targetLangSelect.value = settings.target_lang;
         lineBreakCheckbox.checked = settings.preserve_line_breaks;
+        ocrEngineSelect.value = settings.ocr_engine;
+        tesseractLangsInput.value = settings.tesseract_langs;
+        tesseractPathInput.value = settings.engine_paths?.tesseract || '';
+        ocrCustomPathInput.value = settings.engine_paths?.ocr || '';
+        translatorCustomPathInput.value = settings.engine_paths?.translator || '';
+        engineEncodingSelect.value = settings.engine_output_encoding;
+        engineManifestInput.value = settings.engine_manifest_url || '';
+        networkProxyInput.value = settings.network_proxy || '';
+        networkCaCertInput.value = settings.network_ca_cert || '';
+        downloadMirrorsInput.value = (settings.download_mirrors || []).join('\n');
+        translatorWorkerCheckbox.checked = settings.translator_worker_enabled;
+        translatorIdleTimeoutInput.value = settings.translator_idle_timeout_mins;
+        const preprocess = settings.ocr_preprocess || {};
+        for (const checkbox of preprocessCheckboxes) {
+            checkbox.checked = !!preprocess[checkbox.dataset.preprocessKey];
+        }
+        renderSavedRegions();
 
         // 根据加载的 'primary_action' 设置单选框的选中状态
         for (const radio of radioInputs) {
@@ -171,6 +496,35 @@ async function loadSettings() {
     }
 }
 
+// 后端校验错误中的字段名 -> 对应的输入控件，用于标记出错的设置项
+const SETTINGS_FIELD_INPUTS = {
+    shortcut: shortcutInput,
+    view_image_shortcut: viewShortcutInput,
+    delayed_capture_shortcut: delayedShortcutInput,
+    long_capture_shortcut: longCaptureShortcutInput,
+    watch_shortcut: watchShortcutInput,
+    target_lang: targetLangSelect,
+    download_mirrors: downloadMirrorsInput,
+};
+
+/**
+ * 标记校验未通过的输入控件，并清除其它控件上的标记。
+ * @param {Array<{field: string, message: string}>} fieldErrors - 后端返回的字段错误。
+ */
+function markInvalidFields(fieldErrors) {
+    for (const input of Object.values(SETTINGS_FIELD_INPUTS)) {
+        input.classList.remove('invalid');
+        input.removeAttribute('title');
+    }
+    for (const { field, message: text } of fieldErrors) {
+        const input = SETTINGS_FIELD_INPUTS[field];
+        if (input) {
+            input.classList.add('invalid');
+            input.title = text;
+        }
+    }
+}
+
 /**
  * 收集当前 UI 上的所有设置，并将其保存到后端。
  * @async
@@ -187,6 +541,35 @@ async function saveSettings() {
         viewShortcutInput.value = currentSettings.view_image_shortcut || 'F3'; // 恢复
         return;
     }
+    const delayedShortcutValue = delayedShortcutInput.value.trim();
+    if (!delayedShortcutValue) {
+        delayedShortcutInput.value = currentSettings.delayed_capture_shortcut || 'Shift+F1'; // 恢复
+        return;
+    }
+    // 延时限制在 1 ~ 60 秒之间
+    const captureDelay = Math.min(60, Math.max(1, parseInt(captureDelayInput.value, 10) || currentSettings.capture_delay_secs || 3));
+    captureDelayInput.value = captureDelay;
+    const longCaptureShortcutValue = longCaptureShortcutInput.value.trim();
+    if (!longCaptureShortcutValue) {
+        longCaptureShortcutInput.value = currentSettings.long_capture_shortcut || 'Ctrl+F1'; // 恢复
+        return;
+    }
+    const watchShortcutValue = watchShortcutInput.value.trim();
+    if (!watchShortcutValue) {
+        watchShortcutInput.value = currentSettings.watch_shortcut || 'F4'; // 恢复
+        return;
+    }
+    // 监视间隔不得低于 300 毫秒
+    const watchInterval = Math.max(300, parseInt(watchIntervalInput.value, 10) || currentSettings.watch_interval_ms || 1500);
+    watchIntervalInput.value = watchInterval;
+    const idleTimeout = Math.max(0, parseInt(translatorIdleTimeoutInput.value, 10) || 0);
+    translatorIdleTimeoutInput.value = idleTimeout;
+
+    // 预处理选项：保留界面未展示的字段（如放大阈值）
+    const ocrPreprocess = { ...currentSettings.ocr_preprocess };
+    for (const checkbox of preprocessCheckboxes) {
+        ocrPreprocess[checkbox.dataset.preprocessKey] = checkbox.checked;
+    }
 
     // 获取当前选中的“首要动作”
     let selectedAction = 'ocr';
@@ -197,12 +580,34 @@ async function saveSettings() {
         }
     }
 
-    // 构造新的设置对象
+    // 构造新的设置对象：在已加载的设置基础上覆盖界面上的字段，
+    // 这样界面未展示的字段（如监视阈值）不会在保存时丢失
     const newSettings = {
+        ...currentSettings,
         shortcut: shortcutValue,
         view_image_shortcut: viewShortcutValue,
+        delayed_capture_shortcut: delayedShortcutValue,
+        capture_delay_secs: captureDelay,
+        long_capture_shortcut: longCaptureShortcutValue,
+        watch_shortcut: watchShortcutValue,
+        watch_interval_ms: watchInterval,
         target_lang: targetLangSelect.value,
         preserve_line_breaks: lineBreakCheckbox.checked,
+        ocr_preprocess: ocrPreprocess,
+        ocr_engine: ocrEngineSelect.value,
+        tesseract_langs: tesseractLangsInput.value.trim() || 'eng',
+        engine_paths: {
+            ocr: ocrCustomPathInput.value.trim() || null,
+            translator: translatorCustomPathInput.value.trim() || null,
+            tesseract: tesseractPathInput.value.trim() || null,
+        },
+        engine_output_encoding: engineEncodingSelect.value,
+        engine_manifest_url: engineManifestInput.value.trim() || null,
+        network_proxy: networkProxyInput.value.trim() || null,
+        network_ca_cert: networkCaCertInput.value.trim() || null,
+        download_mirrors: downloadMirrorsInput.value.split('\n').map(line => line.trim()).filter(Boolean),
+        translator_worker_enabled: translatorWorkerCheckbox.checked,
+        translator_idle_timeout_mins: idleTimeout,
         primary_action: selectedAction,
     };
 
@@ -210,8 +615,12 @@ async function saveSettings() {
         // 调用后端 `set_settings` 命令
         await invoke('set_settings', { settings: newSettings });
         currentSettings = newSettings; // 更新本地缓存
+        markInvalidFields([]);
     } catch (error) {
         console.error("保存设置失败:", error);
+        // 后端未应用任何修改；保留界面上的输入，标记出错的项以便用户直接修改
+        markInvalidFields(error.fields || []);
+        await message(error.message || String(error), { title: '设置未保存', type: 'error' });
     }
 }
 
@@ -240,121 +649,288 @@ function formatShortcut(e) {
 // --- 事件监听 ---
 // 为页面上的交互元素绑定功能
 
-// 1. OCR 引擎下载按钮点击事件
-downloadOcrBtn.addEventListener('click', async () => {
-    if (isOcrDownloading) return; // 如果正在下载，则忽略点击
+// 1. 引擎下载：各引擎的下载界面元素与状态，键为引擎 id (与后端 EngineId 一致)
+const engineDownloads = {
+    ocr: {
+        name: '识别引擎',
+        button: downloadOcrBtn,
+        fileButton: installOcrFileBtn,
+        uninstallButton: uninstallOcrBtn,
+        infoLabel: ocrEngineInfo,
+        container: ocrProgressContainer,
+        bar: ocrProgressBar,
+        label: ocrProgressLabel,
+        cancelButton: cancelOcrDownloadBtn,
+        downloading: false,
+        cancelled: false,
+        isInstalled: () => isOcrInstalled,
+        onInstalled: () => { isOcrInstalled = true; },
+        refresh: () => updateOcrUI(),
+        recheck: () => checkOcrStatus(),
+    },
+    translator: {
+        name: '翻译引擎',
+        button: downloadBtn,
+        fileButton: installTranslatorFileBtn,
+        uninstallButton: uninstallTranslatorBtn,
+        infoLabel: translatorEngineInfo,
+        container: progressContainer,
+        bar: progressBar,
+        label: progressLabel,
+        cancelButton: cancelDownloadBtn,
+        downloading: false,
+        cancelled: false,
+        isInstalled: () => isTranslatorInstalled,
+        onInstalled: () => { isTranslatorInstalled = true; },
+        refresh: () => updateTranslatorUI(),
+        recheck: () => checkTranslatorStatus(),
+    },
+};
+
+/**
+ * 下载并安装指定引擎，进度通过 'engine-download-progress' 事件更新。
+ * @async
+ * @param {string} engineId - 引擎 id ('ocr' / 'translator')。
+ * @param {string} [archivePath] - 本地引擎包路径，提供时从该文件离线安装而不下载。
+ */
+async function startEngineDownload(engineId, archivePath) {
+    const ui = engineDownloads[engineId];
+    if (ui.downloading) return; // 如果正在下载，则忽略点击
 
     // 如果已安装，向用户确认是否要覆盖
-    if (isOcrInstalled) {
-        const confirmed = await confirm('本地已存在识别引擎，确定要重新下载覆盖吗？', { title: '确认重新下载', type: 'warning' });
+    if (ui.isInstalled()) {
+        const confirmed = await confirm(`本地已存在${ui.name}，确定要重新安装覆盖吗？`, { title: '确认重新安装', type: 'warning' });
         if (!confirmed) return;
     }
 
     // 更新 UI 为下载状态
-    isOcrDownloading = true;
-    downloadOcrBtn.disabled = true;
-    downloadOcrBtn.textContent = "正在连接...";
-    ocrProgressContainer.style.display = 'block';
-    ocrProgressBar.value = 0;
-    ocrProgressLabel.textContent = "初始化...";
-    console.log("[前端] UI 已更新为下载状态, 调用后端 download_ocr...");
+    ui.downloading = true;
+    ui.cancelled = false;
+    ui.button.disabled = true;
+    ui.fileButton.disabled = true;
+    ui.button.textContent = archivePath ? "正在安装..." : "正在连接...";
+    ui.cancelButton.disabled = false;
+    ui.cancelButton.style.display = '';
+    ui.container.style.display = 'block';
+    ui.bar.value = 0;
+    ui.label.textContent = "初始化...";
 
     try {
-        await invoke('download_ocr');
+        if (archivePath) {
+            await invoke('install_engine_from_file', { engineId, path: archivePath });
+        } else {
+            await invoke('download_engine', { engineId });
+        }
     } catch (e) {
-        console.error("[前端] 后端 download_ocr 调用失败:", e);
-        await message(`下载失败: ${e}`, { title: '错误', type: 'error' });
-        // 下载失败后，重置 UI 状态
-        isOcrDownloading = false;
-        downloadOcrBtn.disabled = false;
-        updateOcrUI();
-        ocrProgressContainer.style.display = 'none';
-        console.log("[前端] 下载错误处理完成, UI已重置.");
+        // 用户主动取消时由 'cancelled' 事件处理界面，不再弹出错误
+        if (ui.cancelled) return;
+        console.error(`[前端] ${ui.name}安装失败:`, e);
+        await message(`安装失败: ${e}`, { title: '错误', type: 'error' });
+        // 安装失败后，重置 UI 状态
+        ui.downloading = false;
+        ui.button.disabled = false;
+        ui.fileButton.disabled = false;
+        ui.refresh();
+        ui.container.style.display = 'none';
     }
-});
+}
 
-// 2. 翻译引擎下载按钮点击事件
-downloadBtn.addEventListener('click', async () => {
-    if (isTranslatorDownloading) return;
+/**
+ * 选择本地引擎包并离线安装，用于无法联网的机器。
+ * @async
+ * @param {string} engineId - 引擎 id ('ocr' / 'translator')。
+ */
+async function installEngineFromFile(engineId) {
+    const selected = await open({
+        multiple: false,
+        filters: [{ name: '引擎包', extensions: ['7z', 'zip', 'gz', 'tgz'] }],
+    });
+    if (!selected) return;
+    await startEngineDownload(engineId, selected);
+}
 
-    if (isTranslatorInstalled) {
-        const confirmed = await confirm('本地已存在翻译引擎，确定要重新下载覆盖吗？', { title: '确认重新下载', type: 'warning' });
-        if (!confirmed) return;
+/**
+ * 取消指定引擎正在进行的下载。
+ * @async
+ * @param {string} engineId - 引擎 id ('ocr' / 'translator')。
+ */
+async function cancelEngineDownload(engineId) {
+    const ui = engineDownloads[engineId];
+    ui.cancelButton.disabled = true;
+    ui.label.textContent = "正在取消...";
+    try {
+        await invoke('cancel_download', { engineId });
+    } catch (e) {
+        console.error(`[前端] 取消${ui.name}下载失败:`, e);
+        ui.cancelButton.disabled = false;
     }
+}
 
-    isTranslatorDownloading = true;
-    downloadBtn.disabled = true;
-    downloadBtn.textContent = "正在连接...";
-    progressContainer.style.display = 'block';
-    progressBar.value = 0;
-    progressLabel.textContent = "初始化...";
+/**
+ * 卸载指定引擎，删除应用数据目录中的引擎文件。
+ * @async
+ * @param {string} engineId - 引擎 id ('ocr' / 'translator')。
+ */
+async function uninstallEngine(engineId) {
+    const ui = engineDownloads[engineId];
+    if (ui.downloading) return;
+    const confirmed = await confirm(`确定要卸载${ui.name}吗？卸载后需要重新下载才能使用。`, { title: '确认卸载', type: 'warning' });
+    if (!confirmed) return;
 
+    ui.uninstallButton.disabled = true;
     try {
-        await invoke('download_translator');
+        const freed = await invoke('uninstall_engine', { engineId });
+        await message(`${ui.name}已卸载，释放了 ${formatSize(freed)} 空间。`, { title: '卸载完成' });
     } catch (e) {
-        console.error("翻译引擎下载出错:", e);
-        await message(`下载失败: ${e}`, { title: '错误', type: 'error' });
-        isTranslatorDownloading = false;
-        downloadBtn.disabled = false;
-        updateTranslatorUI();
-        progressContainer.style.display = 'none';
+        console.error(`[前端] 卸载${ui.name}失败:`, e);
+        await message(`卸载失败: ${e}`, { title: '错误', type: 'error' });
     }
-});
+    ui.uninstallButton.disabled = false;
+    await ui.recheck();
+    await checkEngineUpdates();
+    await refreshEngineInfo(engineId);
+}
 
-// 3. 监听后端发送的 OCR 下载进度事件
-listen('ocr-download-progress', (event) => {
-    console.log("[前端] 收到 'ocr-download-progress' 事件, payload:", JSON.stringify(event.payload));
-    const { progress, total, status } = event.payload;
+/**
+ * 实际运行各引擎进行自检，并逐个显示诊断结果。
+ * @async
+ */
+async function runSelfTest() {
+    selfTestBtn.disabled = true;
+    selfTestBtn.textContent = "正在自检，首次运行翻译引擎可能需要较长时间...";
+    selfTestResults.replaceChildren();
+    copySelfTestBtn.style.display = 'none';
+    try {
+        lastSelfTestReport = await invoke('self_test_engines');
+        for (const result of lastSelfTestReport.results) {
+            const item = document.createElement('div');
+            item.className = 'self-test-result';
 
-    if (status === 'downloading') {
-        const percent = Math.round((progress / total) * 100);
-        ocrProgressBar.value = percent;
-        const downloadedMB = (progress / 1024 / 1024).toFixed(1);
-        const totalMB = (total / 1024 / 1024).toFixed(1);
-        ocrProgressLabel.textContent = `正在下载... ${percent}% (${downloadedMB}MB / ${totalMB}MB)`;
-    } else if (status === 'extracting') {
-        ocrProgressBar.removeAttribute('value'); // 进入不确定进度状态
-        ocrProgressLabel.textContent = "下载完成，正在解压安装，请稍候...";
-    } else if (status === 'completed') {
-        ocrProgressBar.value = 100;
-        ocrProgressLabel.textContent = "安装完成！";
-        isOcrDownloading = false;
-        isOcrInstalled = true;
-        downloadOcrBtn.disabled = false;
-        updateOcrUI();
-        console.log("[前端] OCR 引擎安装完成.");
+            const row = document.createElement('div');
+            row.className = 'status-row';
+            const name = document.createElement('span');
+            name.className = 'status-label';
+            name.textContent = result.display_name;
+            const badge = document.createElement('span');
+            if (result.diagnosis === 'ok') {
+                badge.className = 'status-badge installed';
+                badge.textContent = result.latency_ms != null ? `正常 (${result.latency_ms}ms)` : '正常';
+            } else if (result.passed) {
+                badge.className = 'status-badge outdated';
+                badge.textContent = '结果异常';
+            } else {
+                badge.className = 'status-badge missing';
+                badge.textContent = result.diagnosis === 'not_installed' ? '未安装' : '失败';
+            }
+            row.append(name, badge);
 
-        setTimeout(() => { ocrProgressContainer.style.display = 'none'; }, 2000);
+            const detail = document.createElement('small');
+            detail.className = 'engine-info';
+            detail.textContent = result.message;
+            detail.title = result.stderr_excerpt || result.stdout_excerpt || '';
+            item.append(row, detail);
+            selfTestResults.append(item);
+        }
+        copySelfTestBtn.style.display = '';
+    } catch (e) {
+        console.error("[前端] 引擎自检失败:", e);
+        await message(`自检失败: ${e}`, { title: '错误', type: 'error' });
     }
-});
+    selfTestBtn.disabled = false;
+    selfTestBtn.textContent = "运行自检";
+}
 
+/**
+ * 把最近一次自检的完整报告复制到剪贴板，便于附在问题反馈中。
+ * @async
+ */
+async function copySelfTestReport() {
+    if (!lastSelfTestReport) return;
+    await writeText(JSON.stringify(lastSelfTestReport, null, 2));
+    copySelfTestBtn.textContent = "已复制";
+    setTimeout(() => { copySelfTestBtn.textContent = "复制自检报告 (用于反馈问题)"; }, 2000);
+}
 
-// 4. 监听后端发送的翻译引擎下载进度事件
-listen('download-progress', (event) => {
-    const { progress, total, status } = event.payload;
+/**
+ * 清理不再被截图历史引用的缓存文件，并显示清理结果。
+ * @async
+ */
+async function clearCache() {
+    clearCacheBtn.disabled = true;
+    try {
+        const report = await invoke('clear_cache');
+        clearCacheResult.textContent = `已删除 ${report.removed_files} 个文件，释放 ${formatSize(report.freed_bytes)}` +
+            (report.kept_files > 0 ? `，保留 ${report.kept_files} 个历史截图或正在使用的文件。` : '。');
+    } catch (e) {
+        console.error("[前端] 清理缓存失败:", e);
+        clearCacheResult.textContent = `清理失败: ${e}`;
+    }
+    clearCacheBtn.disabled = false;
+}
+
+downloadOcrBtn.addEventListener('click', () => startEngineDownload('ocr'));
+downloadBtn.addEventListener('click', () => startEngineDownload('translator'));
+installOcrFileBtn.addEventListener('click', () => installEngineFromFile('ocr'));
+installTranslatorFileBtn.addEventListener('click', () => installEngineFromFile('translator'));
+cancelOcrDownloadBtn.addEventListener('click', () => cancelEngineDownload('ocr'));
+cancelDownloadBtn.addEventListener('click', () => cancelEngineDownload('translator'));
+uninstallOcrBtn.addEventListener('click', () => uninstallEngine('ocr'));
+uninstallTranslatorBtn.addEventListener('click', () => uninstallEngine('translator'));
+clearCacheBtn.addEventListener('click', clearCache);
+selfTestBtn.addEventListener('click', runSelfTest);
+copySelfTestBtn.addEventListener('click', copySelfTestReport);
+
+// 2. 监听后端发送的引擎下载进度事件
+listen('engine-download-progress', (event) => {
+    const { engine_id, progress, total, status } = event.payload;
+    const ui = engineDownloads[engine_id];
+    if (!ui) return;
 
     if (status === 'downloading') {
-        const percent = Math.round((progress / total) * 100);
-        progressBar.value = percent;
         const downloadedMB = (progress / 1024 / 1024).toFixed(1);
-        const totalMB = (total / 1024 / 1024).toFixed(1);
-        progressLabel.textContent = `正在下载... ${percent}% (${downloadedMB}MB / ${totalMB}MB)`;
+        if (total > 0) {
+            // 续传时 progress 包含之前已下载的部分
+            const percent = Math.round((progress / total) * 100);
+            ui.bar.value = percent;
+            const totalMB = (total / 1024 / 1024).toFixed(1);
+            ui.label.textContent = `正在下载... ${percent}% (${downloadedMB}MB / ${totalMB}MB)`;
+        } else {
+            // 服务器未返回文件大小
+            ui.bar.removeAttribute('value');
+            ui.label.textContent = `正在下载... ${downloadedMB}MB`;
+        }
+    } else if (status === 'verifying') {
+        ui.bar.removeAttribute('value');
+        ui.label.textContent = "下载完成，正在校验文件...";
     } else if (status === 'extracting') {
-        progressBar.removeAttribute('value');
-        progressLabel.textContent = "下载完成，正在解压安装，请稍候...";
+        ui.bar.removeAttribute('value'); // 进入不确定进度状态
+        ui.label.textContent = "校验通过，正在解压安装，请稍候...";
+        ui.cancelButton.style.display = 'none'; // 解压过程不可取消
     } else if (status === 'completed') {
-        progressBar.value = 100;
-        progressLabel.textContent = "安装完成！";
-        isTranslatorDownloading = false;
-        isTranslatorInstalled = true;
-        downloadBtn.disabled = false;
-        updateTranslatorUI();
+        ui.bar.value = 100;
+        ui.label.textContent = "安装完成！";
+        ui.downloading = false;
+        ui.onInstalled();
+        ui.button.disabled = false;
+        ui.fileButton.disabled = false;
+        ui.refresh();
+        checkEngineUpdates();
+        refreshEngineInfo(engine_id);
 
-        setTimeout(() => { progressContainer.style.display = 'none'; }, 2000);
+        setTimeout(() => { ui.container.style.display = 'none'; }, 2000);
+    } else if (status === 'cancelled') {
+        ui.cancelled = true;
+        ui.downloading = false;
+        ui.button.disabled = false;
+        ui.fileButton.disabled = false;
+        ui.refresh();
+        ui.label.textContent = "下载已取消";
+        ui.cancelButton.style.display = 'none';
+        setTimeout(() => { ui.container.style.display = 'none'; }, 2000);
     }
 });
 
-// 5. 为所有设置控件绑定 'change' 事件，任何变动都立即保存
+// 3. 为所有设置控件绑定 'change' 事件，任何变动都立即保存
 for (const radio of radioInputs) {
     radio.addEventListener('change', (e) => {
         if (e.target.checked) {
@@ -365,55 +941,89 @@ for (const radio of radioInputs) {
 }
 targetLangSelect.addEventListener('change', saveSettings);
 lineBreakCheckbox.addEventListener('change', saveSettings);
+preprocessCheckboxes.forEach(checkbox => checkbox.addEventListener('change', saveSettings));
 
-// 6. 快捷键输入框的交互逻辑
-shortcutInput.addEventListener('focus', () => {
-    isRecording.main = true;
-    shortcutInput.value = '请按下快捷键...';
-});
-shortcutInput.addEventListener('blur', () => {
-    isRecording.main = false;
-    // 如果用户未输入就失去焦点，恢复之前的值
-    if (shortcutInput.value === '请按下快捷键...') {
-        shortcutInput.value = currentSettings.shortcut || 'F1';
-    }
-    saveSettings(); // 保存最终结果
+// 切换识别引擎或修改 Tesseract 设置后，重新检查引擎可用性
+async function onOcrEngineSettingsChange() {
+    await saveSettings();
+    await checkOcrStatus();
+    await loadTesseractLanguages();
+}
+ocrEngineSelect.addEventListener('change', onOcrEngineSettingsChange);
+tesseractLangsInput.addEventListener('change', saveSettings);
+tesseractPathInput.addEventListener('change', onOcrEngineSettingsChange);
+ocrCustomPathInput.addEventListener('change', onOcrEngineSettingsChange);
+engineEncodingSelect.addEventListener('change', saveSettings);
+engineManifestInput.addEventListener('change', async () => {
+    await saveSettings();
+    await checkEngineUpdates();
 });
-shortcutInput.addEventListener('keydown', (e) => {
-    if (isRecording.main) {
-        e.preventDefault(); // 阻止默认按键行为，如F1弹出帮助
-        const formatted = formatShortcut(e);
-        // 只接受有效的快捷键组合（带修饰键或功能键）
-        if (formatted && (formatted.includes('+') || formatted.startsWith('F'))) {
-            shortcutInput.value = formatted;
-            shortcutInput.blur(); // 录制成功后自动失焦
-        }
-    }
+networkProxyInput.addEventListener('change', saveSettings);
+networkCaCertInput.addEventListener('change', saveSettings);
+downloadMirrorsInput.addEventListener('change', saveSettings);
+translatorWorkerCheckbox.addEventListener('change', saveSettings);
+translatorIdleTimeoutInput.addEventListener('change', saveSettings);
+translatorCustomPathInput.addEventListener('change', async () => {
+    await saveSettings();
+    await checkTranslatorStatus();
 });
+captureDelayInput.addEventListener('change', saveSettings);
+watchIntervalInput.addEventListener('change', saveSettings);
 
-// (与上面类似) 查看快捷键输入框的交互逻辑
-viewShortcutInput.addEventListener('focus', () => {
-    isRecording.view = true;
-    viewShortcutInput.value = '请按下快捷键...';
-});
-viewShortcutInput.addEventListener('blur', () => {
-    isRecording.view = false;
-    if (viewShortcutInput.value === '请按下快捷键...') {
-        viewShortcutInput.value = currentSettings.view_image_shortcut || 'F3';
+// 4. 保存区域：框选新区域，并在后端保存后刷新列表
+addRegionBtn.addEventListener('click', async () => {
+    try {
+        await invoke('select_region_to_save');
+    } catch (e) {
+        await message(`无法开始框选: ${e}`, { title: '错误', type: 'error' });
     }
-    saveSettings();
 });
-viewShortcutInput.addEventListener('keydown', (e) => {
-    if (isRecording.view) {
-        e.preventDefault();
-        const formatted = formatShortcut(e);
-        if (formatted && (formatted.includes('+') || formatted.startsWith('F'))) {
-            viewShortcutInput.value = formatted;
-            viewShortcutInput.blur();
-        }
-    }
+listen('saved-regions-changed', (event) => {
+    currentSettings.saved_regions = event.payload.regions;
+    renderSavedRegions();
 });
 
+// 5. 快捷键输入框的交互逻辑
+
+/**
+ * 为快捷键输入框绑定录制逻辑：获得焦点时开始录制，按下有效组合键后自动失焦并保存。
+ * @param {HTMLInputElement} input - 快捷键输入框。
+ * @param {string} recordingKey - `isRecording` 中对应的键名。
+ * @param {string} settingsKey - 设置对象中对应的字段名，用于失焦时恢复原值。
+ * @param {string} defaultValue - 设置中没有值时使用的默认快捷键。
+ */
+function bindShortcutRecorder(input, recordingKey, settingsKey, defaultValue) {
+    input.addEventListener('focus', () => {
+        isRecording[recordingKey] = true;
+        input.value = '请按下快捷键...';
+    });
+    input.addEventListener('blur', () => {
+        isRecording[recordingKey] = false;
+        // 如果用户未输入就失去焦点，恢复之前的值
+        if (input.value === '请按下快捷键...') {
+            input.value = currentSettings[settingsKey] || defaultValue;
+        }
+        saveSettings(); // 保存最终结果
+    });
+    input.addEventListener('keydown', (e) => {
+        if (isRecording[recordingKey]) {
+            e.preventDefault(); // 阻止默认按键行为，如F1弹出帮助
+            const formatted = formatShortcut(e);
+            // 只接受有效的快捷键组合（带修饰键或功能键）
+            if (formatted && (formatted.includes('+') || formatted.startsWith('F'))) {
+                input.value = formatted;
+                input.blur(); // 录制成功后自动失焦
+            }
+        }
+    });
+}
+
+bindShortcutRecorder(shortcutInput, 'main', 'shortcut', 'F1');
+bindShortcutRecorder(viewShortcutInput, 'view', 'view_image_shortcut', 'F3');
+bindShortcutRecorder(delayedShortcutInput, 'delayed', 'delayed_capture_shortcut', 'Shift+F1');
+bindShortcutRecorder(longCaptureShortcutInput, 'long', 'long_capture_shortcut', 'Ctrl+F1');
+bindShortcutRecorder(watchShortcutInput, 'watch', 'watch_shortcut', 'F4');
+
 // --- 初始化 ---
 
 /**
@@ -429,6 +1039,11 @@ async function initialize() {
         checkOcrStatus(),        // 检查 OCR 引擎状态
         checkTranslatorStatus()  // 检查翻译引擎状态
     ]);
+    // 引擎选择来自设置，需在设置加载后刷新一次引擎相关界面
+    updateOcrUI();
+    await loadTesseractLanguages();
+    await checkEngineUpdates();
+    await Promise.all(Object.keys(engineDownloads).map(refreshEngineInfo));
     console.log("前端初始化完成。");
 }
 
@@ -14,6 +14,7 @@ const copyTranslatedBtn = document.getElementById('copy-translated-btn');
 const copyImageBtn = document.getElementById('copy-image-btn');
 const saveImageBtn = document.getElementById('save-image-btn');
 const ttsBtn = document.getElementById('tts-btn');
+const confidenceBadge = document.getElementById('confidence-badge');
 
 // --- 状态变量 ---
 let isPinned = true;
@@ -66,6 +67,44 @@ function speakText() {
     window.speechSynthesis.speak(utterance);
 }
 
+/**
+ * 渲染原文：低置信度的文本块用高亮标出，鼠标悬停可查看置信度。
+ * 文本块按顺序在原文中定位，块与块之间的分隔符（空格或换行）原样保留。
+ */
+function renderOriginalText(text, blocks) {
+    originalTextEl.textContent = '';
+    let cursor = 0;
+    for (const block of blocks || []) {
+        const start = text.indexOf(block.text, cursor);
+        if (start < 0 || !block.text) continue;
+        originalTextEl.append(text.slice(cursor, start));
+        const span = document.createElement('span');
+        span.textContent = block.text;
+        if (block.low_confidence) {
+            span.className = 'low-confidence';
+            span.title = `置信度 ${(block.score * 100).toFixed(0)}%，可能识别有误`;
+        }
+        originalTextEl.append(span);
+        cursor = start + block.text.length;
+    }
+    originalTextEl.append(text.slice(cursor));
+}
+
+/**
+ * 更新整体置信度标记，没有置信度信息时隐藏。
+ */
+function updateConfidenceBadge(payload) {
+    if (payload.error_message || payload.confidence == null) {
+        confidenceBadge.style.display = 'none';
+        return;
+    }
+    const lowCount = (payload.blocks || []).filter(b => b.low_confidence).length;
+    confidenceBadge.textContent = `置信度 ${(payload.confidence * 100).toFixed(0)}%`;
+    confidenceBadge.classList.toggle('low', lowCount > 0);
+    confidenceBadge.title = lowCount > 0 ? `${lowCount} 处识别置信度较低，已在原文中标出` : '识别置信度';
+    confidenceBadge.style.display = 'inline-block';
+}
+
 // --- 核心修改：重构后的 UI 更新逻辑 ---
 
 /**
@@ -103,8 +142,9 @@ function handleOcrResultPayload(payload) {
         if (!translatedTextContent) {
             translatedTextEl.textContent = '翻译中...';
         }
-        originalTextEl.textContent = originalTextContent;
+        renderOriginalText(originalTextContent, payload.blocks);
     }
+    updateConfidenceBadge(payload);
 }
 
 /**
@@ -211,7 +251,9 @@ async function init() {
             handleOcrResultPayload({
                 original_text: cached.original_text,
                 error_message: null,
-                image_path: cached.image_path
+                image_path: cached.image_path,
+                confidence: cached.confidence,
+                blocks: cached.blocks
             });
 
             // 2. 恢复翻译数据 (如果有)
@@ -15,6 +15,19 @@ let isDrawing = false;      // 标记是否正在绘制选区
 let startX, startY;         // 选区起始坐标
 let currentX, currentY;     // 鼠标当前坐标
 let screenCapture = null;   // 存储从后端接收的全屏截图 Image 对象
+let selectionMode = 'capture'; // 选区用途：'capture' 普通截图，'watch' 开始区域监视，'region' 保存命名区域，'long' 滚动长截图
+
+// --- 窗口吸附相关状态 ---
+let windowRects = [];       // 后端枚举的可见窗口矩形（原始图片坐标），从前到后排列
+let hoveredWindow = null;   // 当前鼠标下方最靠前的窗口
+
+// 不同选区用途对应的后端指令
+const SELECTION_COMMANDS = {
+    capture: 'process_screenshot_area',
+    watch: 'start_watch',
+    region: 'save_region_from_selection',
+    long: 'start_long_capture',
+};
 
 // --- 新增：精准取色专用变量 ---
 // 使用离屏 Canvas 存储原始图像数据，避免受遮罩层影响导致颜色变暗
@@ -209,6 +222,39 @@ function drawSizeIndicator() {
     ctx.fillText(text, textX, textY);
 }
 
+/**
+ * 在未开始拖拽时高亮鼠标下方的窗口，单击即可选中整个窗口。
+ */
+function drawHoveredWindow() {
+    if (isDrawing || !hoveredWindow || !screenCapture) return;
+
+    const scaleX = screenCapture.naturalWidth / canvas.width;
+    const scaleY = screenCapture.naturalHeight / canvas.height;
+    // 将原始图片坐标换算为画布坐标
+    const x = hoveredWindow.x / scaleX;
+    const y = hoveredWindow.y / scaleY;
+    const w = hoveredWindow.width / scaleX;
+    const h = hoveredWindow.height / scaleY;
+
+    ctx.drawImage(screenCapture,
+        hoveredWindow.x, hoveredWindow.y, hoveredWindow.width, hoveredWindow.height, // 源区域
+        x, y, w, h // 目标区域
+    );
+    ctx.strokeStyle = 'rgba(76, 217, 100, 0.9)';
+    ctx.lineWidth = 2;
+    ctx.strokeRect(x, y, w, h);
+
+    // 在窗口左上角显示窗口标题
+    const label = hoveredWindow.title || hoveredWindow.app_name || '窗口';
+    ctx.font = '14px Arial';
+    const labelWidth = ctx.measureText(label).width;
+    const labelY = y > 24 ? y - 24 : y + 4;
+    ctx.fillStyle = 'rgba(0, 0, 0, 0.7)';
+    ctx.fillRect(x, labelY, labelWidth + 10, 20);
+    ctx.fillStyle = '#fff';
+    ctx.fillText(label, x + 5, labelY + 15);
+}
+
 /**
  * 主绘制函数，每一帧都会被调用以更新画布。
  */
@@ -253,6 +299,7 @@ function draw() {
     }
 
     // 5. 绘制辅助工具
+    drawHoveredWindow();
     drawMagnifier();
     drawSizeIndicator();
 }
@@ -331,6 +378,11 @@ canvas.addEventListener('mousemove', (e) => {
                 rgb: rgbToRgbString(r, g, b),
             };
         }
+
+        // 4. 查找鼠标下方最靠前的窗口，用于单击吸附（窗口矩形与原始图片同为物理像素坐标）
+        hoveredWindow = windowRects.find(w =>
+            rawX >= w.x && rawX < w.x + w.width && rawY >= w.y && rawY < w.y + w.height
+        ) || null;
     }
 
     // 请求浏览器在下一帧重绘画布
@@ -347,32 +399,36 @@ canvas.addEventListener('mouseup', async (e) => {
     const width = Math.abs(currentX - startX);
     const height = Math.abs(currentY - startY);
 
-    // 忽略过小的无效选区
-    if (width < 10 || height < 10) {
-        await cancel_screenshot();
-        return;
-    }
-
     // --- 坐标修正：将选区坐标转换回原始图片坐标系发送给后端 ---
     // 后端裁剪是基于原始图片的，所以这里也需要按比例转换
     const scaleX = screenCapture.naturalWidth / canvas.width;
     const scaleY = screenCapture.naturalHeight / canvas.height;
 
-    const realX = x * scaleX;
-    const realY = y * scaleY;
-    const realW = width * scaleX;
-    const realH = height * scaleY;
+    let realX = x * scaleX;
+    let realY = y * scaleY;
+    let realW = width * scaleX;
+    let realH = height * scaleY;
+
+    // 过小的选区视为单击：吸附到鼠标下方的窗口，没有窗口时取消截图
+    if (width < 10 || height < 10) {
+        if (!hoveredWindow) {
+            await cancel_screenshot();
+            return;
+        }
+        ({ x: realX, y: realY, width: realW, height: realH } = hoveredWindow);
+    }
 
-    // 将有效的选区信息发送给后端进行处理
+    // 将有效的选区信息发送给后端进行处理，根据选区用途调用不同的指令
+    const command = SELECTION_COMMANDS[selectionMode] || SELECTION_COMMANDS.capture;
     try {
-        await invoke('process_screenshot_area', {
+        await invoke(command, {
             x: realX,
             y: realY,
             width: realW,
             height: realH
         });
     } catch (error) {
-        console.error("调用后端 'process_screenshot_area' 指令失败:", error);
+        console.error(`调用后端 '${command}' 指令失败:`, error);
         await cancel_screenshot(); // 即使失败也要确保取消截图状态
     }
 });
@@ -418,6 +474,9 @@ async function initialize() {
 
         // 鲁棒性检查：确保收到的数据是有效的
         if (event.payload && event.payload.image_data_url) {
+            selectionMode = event.payload.mode || 'capture';
+            windowRects = event.payload.windows || [];
+            hoveredWindow = null;
             setupCanvas(event.payload.image_data_url);
         } else {
             console.error("初始化事件的载荷无效:", event.payload);